@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::{Keypair, Pubkey};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solcat_diamond_hands_sdk::{
+    basket::{
+        deposit_basket_txs, empty_basket_txs, lock_basket_txs, BasketDeposit, BasketTransaction,
+    },
+    basket_address,
+    instructions::create_basket::CreateBasketIxData,
+    is_native_mint, BasketState,
+};
+
+use crate::{
+    fees::FeeOptions, get_token_program, parse_mint, send_transaction, token_account_amount,
+    view::format_ui_amount, LockPeriod,
+};
+
+/// `--mint` for baskets is `MINT` ( all of your tokens ) or `MINT:AMOUNT` in base units
+pub fn parse_basket_mint(value: &str) -> Result<(Pubkey, Option<u64>)> {
+    let (mint, amount) = match value.split_once(':') {
+        Some((mint, amount)) => {
+            let amount = u64::from_str(amount)
+                .map_err(|e| anyhow!("Could not read the amount for {}: {}", mint, e))?;
+            (mint, Some(amount))
+        }
+        None => (value, None),
+    };
+
+    // `lock --sol` wraps SOL for you, baskets don't - that would be one more account per deposit
+    let mint = parse_mint(mint)?;
+    if is_native_mint(&mint) {
+        return Err(anyhow!(
+            "SOL can't go in a basket, lock it in its own vault with `lock --sol`"
+        ));
+    }
+
+    Ok((mint, amount))
+}
+
+/// Baskets are all-or-nothing, so there is no vesting
+pub fn create_basket_ix_data(
+    lock_period: LockPeriod,
+    basket_index: u64,
+) -> Result<CreateBasketIxData> {
+    match lock_period {
+        LockPeriod::Slots(slots_to_lock) => {
+            Ok(CreateBasketIxData::new(0, basket_index, slots_to_lock))
+        }
+        LockPeriod::UnixTimestamp(unlock_timestamp) => Ok(CreateBasketIxData::new_unix_timestamp(
+            0,
+            basket_index,
+            unlock_timestamp,
+        )),
+        LockPeriod::LinearVesting { .. } => Err(anyhow!("Baskets can't vest")),
+    }
+}
+
+pub fn lock_basket(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    ix_data: CreateBasketIxData,
+    deposits: &[(Pubkey, Option<u64>)],
+    fees: &FeeOptions,
+) -> Result<()> {
+    let deposits = basket_deposits(rpc_client, deposits)?;
+    let txs = lock_basket_txs(&keypair.pubkey(), ix_data, &deposits);
+
+    send_basket_txs(rpc_client, keypair, ix_data.basket_index.get(), &txs, fees)
+}
+
+pub fn deposit_basket(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    basket_index: u64,
+    deposits: &[(Pubkey, Option<u64>)],
+    fees: &FeeOptions,
+) -> Result<()> {
+    let deposits = basket_deposits(rpc_client, deposits)?;
+    let txs = deposit_basket_txs(&keypair.pubkey(), basket_index, &deposits);
+
+    send_basket_txs(rpc_client, keypair, basket_index, &txs, fees)
+}
+
+/// Everything in the basket comes out, then the basket is closed. If a transaction fails
+/// part way, running it again picks up the mints that are left
+pub fn empty_basket(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    basket_index: u64,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let state = fetch_basket(rpc_client, &keypair.pubkey(), basket_index)?;
+
+    let mut mints = Vec::with_capacity(state.entries.len());
+    for entry in &state.entries {
+        mints.push((entry.mint, get_token_program(rpc_client, &entry.mint)?));
+    }
+    let txs = empty_basket_txs(&keypair.pubkey(), basket_index, &mints);
+
+    send_basket_txs(rpc_client, keypair, basket_index, &txs, fees)
+}
+
+/// The basket, and how much of each mint is in it
+pub fn view_basket(rpc_client: &RpcClient, admin: &Pubkey, basket_index: u64) -> Result<()> {
+    let state = fetch_basket(rpc_client, admin, basket_index)?;
+    let (basket, _) = basket_address(admin, basket_index);
+
+    println!("Basket: {}", basket);
+    println!("├─ Basket index: {}", state.basket_index);
+    println!("├─ Start slot: {}", state.start_slot);
+    match state.unlock_slot() {
+        Some(unlock_slot) => println!("├─ Unlock slot: {}", unlock_slot),
+        None => println!("├─ {}", LockPeriod::UnixTimestamp(state.unlock_timestamp)),
+    }
+    println!("└─ Mints: {}", state.entries.len());
+
+    for entry in &state.entries {
+        let vault_token_account = rpc_client
+            .get_account(&entry.vault_token)
+            .map_err(|e| anyhow!("Could not fetch basket token account {}", e))?;
+        let tokens_locked = token_account_amount(&vault_token_account.data)?;
+
+        println!(
+            "   {} {}",
+            entry.mint,
+            format_ui_amount(tokens_locked, entry.mint_decimals)
+        );
+    }
+
+    Ok(())
+}
+
+fn fetch_basket(rpc_client: &RpcClient, admin: &Pubkey, basket_index: u64) -> Result<BasketState> {
+    let (basket, _) = basket_address(admin, basket_index);
+    let basket_account = rpc_client
+        .get_account(&basket)
+        .map_err(|e| anyhow!("Could not fetch basket {}", e))?;
+
+    BasketState::from_account_data(&basket_account.data)
+}
+
+fn basket_deposits(
+    rpc_client: &RpcClient,
+    deposits: &[(Pubkey, Option<u64>)],
+) -> Result<Vec<BasketDeposit>> {
+    deposits
+        .iter()
+        .map(|(mint, tokens_to_deposit)| {
+            Ok(BasketDeposit {
+                mint: *mint,
+                token_program: get_token_program(rpc_client, mint)?,
+                tokens_to_deposit: *tokens_to_deposit,
+            })
+        })
+        .collect()
+}
+
+/// In order, and stop at the first failure - the next transaction might depend on it
+fn send_basket_txs(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    basket_index: u64,
+    txs: &[BasketTransaction],
+    fees: &FeeOptions,
+) -> Result<()> {
+    let (basket, _) = basket_address(&keypair.pubkey(), basket_index);
+
+    for (i, basket_tx) in txs.iter().enumerate() {
+        println!("\nTransaction {} of {}", i + 1, txs.len());
+        let ixs = fees.apply(
+            rpc_client,
+            basket_tx.instructions.clone(),
+            basket_tx.compute_units,
+        )?;
+
+        let blockhash = rpc_client.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&keypair.pubkey()),
+            &[&keypair],
+            blockhash,
+        );
+
+        send_transaction(rpc_client, &tx, &basket)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::{
+    io::{BufRead, Write},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::Pubkey;
+use solcat_diamond_hands_sdk::{is_native_mint, token_address};
+
+use crate::{
+    get_token_program, mint_decimals, token_account_amount,
+    view::{format_ui_amount, ClusterClock},
+    LockPeriod,
+};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Locks can't be undone, these stop the obvious typos - an extra zero on the slots, or
+/// forgetting --tokens-to-lock and locking the whole balance. `--force` skips them
+#[derive(Debug, Clone, Copy)]
+pub struct LockGuards {
+    pub max_duration: Duration,
+    /// 0.0 to 1.0 of the token balance
+    pub max_fraction: f64,
+}
+
+impl LockGuards {
+    pub fn check(&self, summary: &LockSummary) -> Result<()> {
+        if summary.duration > self.max_duration {
+            return Err(anyhow!(
+                "Lock is {:.1} days, more than the {:.1} day maximum. Use --force if you really mean it",
+                days(summary.duration),
+                days(self.max_duration)
+            ));
+        }
+
+        if let Some(fraction) = summary.fraction() {
+            if fraction > self.max_fraction {
+                return Err(anyhow!(
+                    "Lock is {:.1}% of your balance, more than the {:.1}% maximum. Use --force if you really mean it",
+                    fraction * 100.0,
+                    self.max_fraction * 100.0
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Everything you'd want to double check before locking, in units a person can read
+#[derive(Debug, Clone)]
+pub struct LockSummary {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub tokens_to_lock: u64,
+    pub balance: u64,
+    pub duration: Duration,
+    /// Exact for timestamp locks, estimated from the recent slot times otherwise
+    pub estimated_unlock: Option<DateTime<Utc>>,
+}
+
+impl LockSummary {
+    pub fn fetch(
+        rpc_client: &RpcClient,
+        admin: &Pubkey,
+        mint: &Pubkey,
+        tokens_to_lock: Option<u64>,
+        lock_period: LockPeriod,
+    ) -> Result<Self> {
+        // SOL is wrapped as part of the lock, so it's the wallet's SOL that gets locked
+        let balance = if is_native_mint(mint) {
+            rpc_client
+                .get_balance(admin)
+                .map_err(|e| anyhow!("Could not fetch your SOL balance {}", e))?
+        } else {
+            let token_program = get_token_program(rpc_client, mint)?;
+            let admin_token = token_address(admin, mint, &token_program);
+            let admin_token_account = rpc_client
+                .get_account(&admin_token)
+                .map_err(|e| anyhow!("Could not fetch your token account {}", e))?;
+            token_account_amount(&admin_token_account.data)?
+        };
+
+        let clock = ClusterClock::fetch(rpc_client)?;
+        let (duration, estimated_unlock) = match lock_period {
+            LockPeriod::Slots(slots) => {
+                let unlock_slot = clock.slot.saturating_add(slots);
+                (
+                    clock.time_until_slot(unlock_slot),
+                    clock.estimate_slot_time(unlock_slot),
+                )
+            }
+            // Fully vested at the end, that is the one that matters for the guard
+            LockPeriod::LinearVesting { slots_to_vest, .. } => {
+                let unlock_slot = clock.slot.saturating_add(slots_to_vest);
+                (
+                    clock.time_until_slot(unlock_slot),
+                    clock.estimate_slot_time(unlock_slot),
+                )
+            }
+            LockPeriod::UnixTimestamp(unlock_timestamp) => (
+                Duration::from_secs(
+                    unlock_timestamp.saturating_sub(clock.unix_timestamp).max(0) as u64
+                ),
+                DateTime::<Utc>::from_timestamp(unlock_timestamp, 0),
+            ),
+        };
+
+        Ok(Self {
+            mint: *mint,
+            decimals: mint_decimals(rpc_client, mint)?,
+            // No --tokens-to-lock is everything in the token account
+            tokens_to_lock: tokens_to_lock.unwrap_or(balance),
+            balance,
+            duration,
+            estimated_unlock,
+        })
+    }
+
+    /// `None` with an empty balance, the program will refuse that one anyway
+    pub fn fraction(&self) -> Option<f64> {
+        (self.balance > 0).then(|| self.tokens_to_lock as f64 / self.balance as f64)
+    }
+}
+
+impl std::fmt::Display for LockSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Mint: {}", self.mint)?;
+        writeln!(
+            f,
+            "├─ Amount: {} of {} ({})",
+            format_ui_amount(self.tokens_to_lock, self.decimals),
+            format_ui_amount(self.balance, self.decimals),
+            self.fraction()
+                .map_or("no balance".to_string(), |fraction| format!(
+                    "{:.1}%",
+                    fraction * 100.0
+                ))
+        )?;
+        writeln!(f, "├─ Duration: {:.1} days", days(self.duration))?;
+        write!(
+            f,
+            "└─ Unlocks: {}",
+            self.estimated_unlock
+                .map_or("Unknown".to_string(), |unlock| unlock.to_rfc3339())
+        )
+    }
+}
+
+fn days(duration: Duration) -> f64 {
+    duration.as_secs_f64() / SECONDS_PER_DAY
+}
+
+/// Anything other than y / yes is a no
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// For clap, `--max-fraction` has to be between 0 and 1
+pub fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!("{} is not between 0 and 1", fraction));
+    }
+
+    Ok(fraction)
+}
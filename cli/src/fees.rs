@@ -0,0 +1,106 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use solana_client::{client_error::Result as ClientResult, rpc_client::RpcClient};
+use solana_instruction::Instruction;
+use solana_keypair::Pubkey;
+use solcat_diamond_hands_sdk::{compute_budget::recommended_unit_price, ComputeBudget};
+
+/// `--priority-fee-micro-lamports`, either a price per CU or `auto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// Whatever recent transactions touching the same accounts paid, from `getRecentPrioritizationFees`
+    Auto,
+    MicroLamports(u64),
+}
+
+impl FromStr for PriorityFee {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+
+        value
+            .parse()
+            .map(Self::MicroLamports)
+            .map_err(|_| format!("{} is not a number of micro-lamports or auto", value))
+    }
+}
+
+/// The global fee flags. With neither set the transactions go out exactly as before
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeeOptions {
+    pub priority_fee: Option<PriorityFee>,
+    pub cu_limit: Option<u32>,
+}
+
+impl FeeOptions {
+    /// `ixs` with the compute budget instructions in front, printing what was picked
+    pub fn apply(
+        &self,
+        rpc_client: &RpcClient,
+        ixs: Vec<Instruction>,
+        default_cu_limit: u32,
+    ) -> Result<Vec<Instruction>> {
+        let compute_budget = self
+            .compute_budget(rpc_client, &ixs, default_cu_limit)
+            .map_err(|e| anyhow!("Could not fetch prioritization fees {}", e))?;
+
+        if let Some(unit_price) = compute_budget.unit_price_micro_lamports {
+            println!("Priority fee: {} micro-lamports per CU", unit_price);
+        }
+        if let Some(unit_limit) = compute_budget.unit_limit {
+            println!("Compute unit limit: {}", unit_limit);
+        }
+
+        Ok(compute_budget.prepend(ixs))
+    }
+
+    /// `default_cu_limit` is one of the SDK's `*_COMPUTE_UNITS`, used when there is a priority fee
+    /// but no `--cu-limit` - the fee is per CU, so leaving the limit at the runtime default would
+    /// mean paying for CUs we never use
+    pub fn compute_budget(
+        &self,
+        rpc_client: &RpcClient,
+        ixs: &[Instruction],
+        default_cu_limit: u32,
+    ) -> ClientResult<ComputeBudget> {
+        let mut compute_budget = ComputeBudget::new();
+
+        if let Some(priority_fee) = self.priority_fee {
+            let unit_price = match priority_fee {
+                PriorityFee::MicroLamports(unit_price) => unit_price,
+                PriorityFee::Auto => auto_unit_price(rpc_client, ixs)?,
+            };
+
+            compute_budget = compute_budget
+                .with_unit_price(unit_price)
+                .with_unit_limit(default_cu_limit);
+        }
+
+        if let Some(cu_limit) = self.cu_limit {
+            compute_budget = compute_budget.with_unit_limit(cu_limit);
+        }
+
+        Ok(compute_budget)
+    }
+}
+
+/// Fees are local to the accounts being written to, so only ask about those ( the vault and its
+/// token accounts, mostly )
+fn auto_unit_price(rpc_client: &RpcClient, ixs: &[Instruction]) -> ClientResult<u64> {
+    let mut writable: Vec<Pubkey> = ixs
+        .iter()
+        .flat_map(|ix| &ix.accounts)
+        .filter(|account| account.is_writable)
+        .map(|account| account.pubkey)
+        .collect();
+    writable.sort();
+    writable.dedup();
+
+    let fees = rpc_client.get_recent_prioritization_fees(&writable)?;
+
+    Ok(recommended_unit_price(&fees))
+}
@@ -1,18 +1,57 @@
 use anyhow::{anyhow, Result};
-use clap::{Parser, Subcommand};
-use solana_account_decoder::UiAccountEncoding;
-use solana_client::{
-    rpc_client::RpcClient,
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
-    rpc_filter::{Memcmp, RpcFilterType},
+use basket::{
+    create_basket_ix_data, deposit_basket, empty_basket, lock_basket, parse_basket_mint,
+    view_basket,
 };
+use chrono::{DateTime, Utc};
+use clap::{ArgGroup, Parser, Subcommand};
+use confirm::{confirm, parse_fraction, LockGuards, LockSummary};
+use fees::{FeeOptions, PriorityFee};
+use prove::{prove, read_certificate, verify_offline, verify_on_chain};
+use simulate::simulate_transaction;
+use solana_client::rpc_client::RpcClient;
 use solana_keypair::{read_keypair_file, Keypair, Pubkey};
 use solana_program::program_pack::Pack;
 use solana_signer::Signer;
-use solana_transaction::Transaction;
-use solcat_diamond_hands_sdk::{deserialize_vault, empty_vault_ix, id, lock_vault_ix};
-use spl_associated_token_account_interface::address::get_associated_token_address;
-use std::{path::PathBuf, str::FromStr};
+use solana_transaction::{Transaction, TransactionError};
+use solcat_diamond_hands_sdk::{
+    accounts::{vault::LockMode, vault_extension::VaultLabel},
+    compute_budget::{
+        CRANK_EMPTY_COMPUTE_UNITS, DEPOSIT_VAULT_COMPUTE_UNITS, EMPTY_VAULT_COMPUTE_UNITS,
+        EXTEND_LOCK_COMPUTE_UNITS, LOCK_VAULT_COMPUTE_UNITS, TRANSFER_ADMIN_COMPUTE_UNITS,
+        UNWRAP_SOL_COMPUTE_UNITS, UPDATE_LABEL_COMPUTE_UNITS, WITHDRAW_COMPUTE_UNITS,
+        WRAP_SOL_COMPUTE_UNITS,
+    },
+    crank_empty_ix, decode_instruction_error, deposit_vault_ix, deserialize_vault,
+    empty_vault_as_beneficiary_ix, empty_vault_ix,
+    errors::DiamondHandsError,
+    extend_lock_as_beneficiary_ix, find_vault_address, id,
+    instructions::extend_lock::ExtendLockIxData,
+    instructions::lock_vault::LockVaultIxData,
+    is_native_mint, is_token_program, lock_vault_with_ix_data_ix, native_mint,
+    native_sol::{deposit_sol_ix, empty_sol_vault_ix, lock_sol_with_ix_data_ix, withdraw_sol_ix},
+    token_address, transfer_admin_ix, update_label_ix, withdraw_as_beneficiary_ix, withdraw_ix,
+    VaultState,
+};
+use stats::view_mint_stats;
+use status::vault_status;
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use view::{fetch_vaults, parse_ui_amount, view_vaults, OutputFormat};
+use watch::{watch_and_empty, RetryPolicy, WatchConfig};
+
+mod basket;
+mod confirm;
+mod fees;
+mod prove;
+mod simulate;
+mod stats;
+mod status;
+mod view;
+mod watch;
 
 #[derive(Parser, Debug)]
 #[command(name = "solcat")]
@@ -27,6 +66,16 @@ struct Cli {
     )]
     rpc: String,
 
+    /// Priority fee in micro-lamports per compute unit, or "auto" to go off
+    /// getRecentPrioritizationFees for the accounts the transaction writes to
+    #[arg(long, global = true, env = "PRIORITY_FEE")]
+    priority_fee_micro_lamports: Option<PriorityFee>,
+
+    /// Compute unit limit for the transaction, defaults to what the instruction needs when a
+    /// priority fee is set
+    #[arg(long, global = true, env = "CU_LIMIT")]
+    cu_limit: Option<u32>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -38,17 +87,98 @@ enum Commands {
         /// Wallet to query vaults for
         #[arg(short, long, env = "WALLET")]
         wallet: String,
+
+        /// Only show the vault with this index, v1 vaults don't have one
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// How to print the vaults, json and csv are handy for piping into other tools
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+
+    /// Show how long is left on a vault, and roughly when it unlocks
+    Status {
+        /// Wallet that locked the vault
+        #[arg(short, long, env = "WALLET")]
+        wallet: String,
+
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Keep refreshing until the vault can be emptied
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between refreshes with --watch
+        #[arg(long, default_value_t = 10, requires = "watch")]
+        interval: u64,
+    },
+
+    /// Wait for vaults to unlock and empty them as soon as they do, logging JSON lines
+    #[command(group(
+        ArgGroup::new("vaults")
+            .required(true)
+            .args(["config", "wallet"]),
+    ))]
+    WatchAndEmpty {
+        /// Path to the Solana keypair file that pays for the transactions. Vaults it isn't the
+        /// withdraw authority of are cranked, the tokens still go back to their owner
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// JSON file with the vaults to watch: { "vaults": ["<VAULT_ADDRESS>"] }
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Watch every vault this wallet is the admin or beneficiary of
+        #[arg(short, long)]
+        wallet: Option<String>,
+
+        /// Seconds between checks
+        #[arg(long, default_value_t = 10)]
+        poll_interval: u64,
+
+        /// Times to try the empty before giving up on a vault
+        #[arg(long, default_value_t = 5)]
+        max_attempts: u32,
+
+        /// Seconds to wait after the first failed attempt, doubled after every one after that
+        #[arg(long, default_value_t = 2)]
+        backoff: u64,
+
+        /// The most seconds to wait between attempts
+        #[arg(long, default_value_t = 60)]
+        max_backoff: u64,
     },
 
     /// Lock tokens in a vault
+    #[command(group(
+        ArgGroup::new("lock_period")
+            .required(true)
+            .args(["slots_to_lock", "until", "duration", "vest_slots"]),
+    ))]
     Lock {
         /// Path to the Solana keypair file
         #[arg(short, long, env = "KEYPAIR")]
         keypair: PathBuf,
 
-        /// Token mint address
+        /// Token mint address, or "native" for SOL ( with --tokens-to-lock in lamports )
+        #[arg(short, long, required_unless_present = "sol")]
+        mint: Option<String>,
+
+        /// Lock this much SOL (e.g. 1.5), it is wrapped into wSOL for you. Instead of --mint
+        #[arg(long, value_parser = parse_sol, conflicts_with_all = ["mint", "tokens_to_lock"])]
+        sol: Option<u64>,
+
+        /// Vault index, leave it out for the original (v1) vault
         #[arg(short, long)]
-        mint: String,
+        index: Option<u64>,
 
         /// Amount of tokens to lock (in base units). If not provided, locks all tokens
         #[arg(short, long)]
@@ -56,7 +186,190 @@ enum Commands {
 
         /// Number of slots to lock the vault for
         #[arg(short, long)]
-        slots_to_lock: u64,
+        slots_to_lock: Option<u64>,
+
+        /// Lock until a wall-clock time, in RFC3339 (e.g. 2026-04-28T00:00:00Z)
+        #[arg(short, long)]
+        until: Option<String>,
+
+        /// Lock for a wall-clock duration from now (e.g. 180d, 12h)
+        #[arg(short, long)]
+        duration: Option<String>,
+
+        /// Vest the tokens linearly over this many slots, vested tokens are taken out with `withdraw`
+        #[arg(long)]
+        vest_slots: Option<u64>,
+
+        /// Nothing vests until this many slots have passed, only used with --vest-slots
+        #[arg(long, requires = "vest_slots")]
+        cliff_slots: Option<u64>,
+
+        /// Lock the tokens for someone else, only the beneficiary can take them out of the vault
+        #[arg(short, long)]
+        beneficiary: Option<String>,
+
+        /// Name the vault (e.g. "team allocation") so you can tell it apart in `view`, up to 32 bytes
+        #[arg(long, value_parser = parse_label)]
+        label: Option<VaultLabel>,
+
+        /// Simulate the lock and print what would happen, nothing is locked
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Refuse locks longer than this (e.g. 365d, 2y) unless --force is given
+        #[arg(long, env = "MAX_LOCK_DURATION", default_value = "365d", value_parser = humantime::parse_duration)]
+        max_duration: Duration,
+
+        /// Refuse locks of more than this fraction of your balance (0 to 1) unless --force is given
+        #[arg(long, env = "MAX_LOCK_FRACTION", default_value_t = 1.0, value_parser = parse_fraction)]
+        max_fraction: f64,
+
+        /// Lock even if it goes over --max-duration or --max-fraction
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Add more tokens to an existing vault, the lock is not changed
+    Deposit {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL ( with --tokens-to-deposit in lamports )
+        #[arg(short, long, required_unless_present = "sol")]
+        mint: Option<String>,
+
+        /// Deposit this much SOL (e.g. 1.5) into a SOL vault. Instead of --mint
+        #[arg(long, value_parser = parse_sol, conflicts_with_all = ["mint", "tokens_to_deposit"])]
+        sol: Option<u64>,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Amount of tokens to deposit (in base units). If not provided, deposits all tokens
+        #[arg(short, long)]
+        tokens_to_deposit: Option<u64>,
+
+        /// Admin that locked the vault, only needed if the vault was transferred
+        #[arg(short, long)]
+        admin: Option<String>,
+    },
+
+    /// Extend the lock of an existing vault, a lock can only ever get longer
+    #[command(group(
+        ArgGroup::new("extend_period")
+            .required(true)
+            .args(["slots_to_add", "until", "duration"]),
+    ))]
+    Extend {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Number of slots to add to a slot based lock
+        #[arg(short, long)]
+        slots_to_add: Option<u64>,
+
+        /// Move a timestamp lock to a later wall-clock time, in RFC3339 (e.g. 2026-04-28T00:00:00Z)
+        #[arg(short, long)]
+        until: Option<String>,
+
+        /// Add a wall-clock duration to a timestamp lock (e.g. 30d, 12h)
+        #[arg(short, long)]
+        duration: Option<String>,
+
+        /// Admin that locked the vault, only needed if you are the beneficiary or a new admin
+        #[arg(short, long)]
+        admin: Option<String>,
+    },
+
+    /// Withdraw everything that has vested so far from a vesting vault
+    Withdraw {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Admin that locked the vault, only needed if you are the beneficiary or a new admin
+        #[arg(short, long)]
+        admin: Option<String>,
+    },
+
+    /// Empty someone else's unlocked vault, the tokens and rent go back to them - you just pay the fee
+    Crank {
+        /// Path to the Solana keypair file that pays for the transaction
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Address of the vault to empty
+        #[arg(short, long)]
+        vault: String,
+    },
+
+    /// Hand a vault over to a new admin, both keypairs have to sign
+    TransferAdmin {
+        /// Path to the current admin's Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Path to the new admin's Solana keypair file
+        #[arg(short, long)]
+        new_keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Admin that locked the vault, only needed if the vault was already transferred once
+        #[arg(short, long)]
+        admin: Option<String>,
+    },
+
+    /// Rename a vault, or clear its name with --label ""
+    Label {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// The new name, up to 32 bytes
+        #[arg(short, long, value_parser = parse_label)]
+        label: VaultLabel,
+
+        /// Admin that locked the vault, only needed if the vault was transferred
+        #[arg(short, long)]
+        admin: Option<String>,
     },
 
     /// Empty a vault and withdraw all tokens
@@ -65,9 +378,149 @@ enum Commands {
         #[arg(short, long, env = "KEYPAIR")]
         keypair: PathBuf,
 
-        /// Token mint address
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Admin that locked the vault, only needed if you are the beneficiary or a new admin
+        #[arg(short, long)]
+        admin: Option<String>,
+
+        /// Simulate the empty and print what would happen, nothing is sent
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Lock several mints in one basket, they all unlock at the same time
+    #[command(group(
+        ArgGroup::new("lock_period")
+            .required(true)
+            .args(["slots_to_lock", "until", "duration"]),
+    ))]
+    LockBasket {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Basket index, so you can have more than one basket
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+
+        /// Token mint to lock, as MINT for all of your tokens or MINT:AMOUNT in base units.
+        /// Repeat it for every mint in the basket
+        #[arg(short, long, required = true, value_parser = parse_basket_mint)]
+        mint: Vec<(Pubkey, Option<u64>)>,
+
+        /// Number of slots to lock the basket for
+        #[arg(short, long)]
+        slots_to_lock: Option<u64>,
+
+        /// Lock until a wall-clock time, in RFC3339 (e.g. 2026-04-28T00:00:00Z)
+        #[arg(short, long)]
+        until: Option<String>,
+
+        /// Lock for a wall-clock duration from now (e.g. 180d, 12h)
+        #[arg(short, long)]
+        duration: Option<String>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Refuse locks longer than this (e.g. 365d, 2y) unless --force is given
+        #[arg(long, env = "MAX_LOCK_DURATION", default_value = "365d", value_parser = humantime::parse_duration)]
+        max_duration: Duration,
+
+        /// Refuse locks of more than this fraction of your balance (0 to 1) unless --force is given
+        #[arg(long, env = "MAX_LOCK_FRACTION", default_value_t = 1.0, value_parser = parse_fraction)]
+        max_fraction: f64,
+
+        /// Lock even if it goes over --max-duration or --max-fraction
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Add more tokens to a basket, new mints are added to it. The lock is not changed
+    DepositBasket {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Basket index
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+
+        /// Token mint to deposit, as MINT for all of your tokens or MINT:AMOUNT in base units.
+        /// Repeat it for every mint
+        #[arg(short, long, required = true, value_parser = parse_basket_mint)]
+        mint: Vec<(Pubkey, Option<u64>)>,
+    },
+
+    /// Empty an unlocked basket, every mint goes back to you and the basket is closed
+    EmptyBasket {
+        /// Path to the Solana keypair file
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Basket index
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+    },
+
+    /// View a basket and how much of each mint is in it
+    ViewBasket {
+        /// Wallet that locked the basket
+        #[arg(short, long, env = "WALLET")]
+        wallet: String,
+
+        /// Basket index
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+    },
+
+    /// How much of a mint is locked right now, over every wallet's vaults
+    Stats {
+        /// Token mint address, or "native" for SOL
+        #[arg(short, long)]
+        mint: String,
+    },
+
+    /// Sign a proof-of-lock certificate for a vault, anyone can check it with `verify`
+    Prove {
+        /// Path to the Solana keypair file, has to be the vault admin
+        #[arg(short, long, env = "KEYPAIR")]
+        keypair: PathBuf,
+
+        /// Token mint address, or "native" for SOL
         #[arg(short, long)]
         mint: String,
+
+        /// Vault index, leave it out for the original (v1) vault
+        #[arg(short, long)]
+        index: Option<u64>,
+
+        /// Admin that locked the vault, only needed if the vault was transferred
+        #[arg(short, long)]
+        admin: Option<String>,
+
+        /// Where to write the certificate JSON, printed if left out
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Check a proof-of-lock certificate, and that the vault is still locked on-chain
+    Verify {
+        /// Path to the certificate JSON
+        #[arg(short, long)]
+        certificate: PathBuf,
+
+        /// Only check the signature and the certificate itself, don't look at the chain
+        #[arg(long)]
+        offline: bool,
     },
 }
 
@@ -76,134 +529,1139 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     let rpc_client = RpcClient::new(cli.rpc.clone());
+    let fees = FeeOptions {
+        priority_fee: cli.priority_fee_micro_lamports,
+        cu_limit: cli.cu_limit,
+    };
 
     // Match on the subcommand
     match &cli.command {
-        Commands::View { wallet } => {
+        Commands::View {
+            wallet,
+            index,
+            output,
+        } => {
+            let wallet_pubkey =
+                Pubkey::from_str(wallet).map_err(|e| anyhow!("Could not read wallet: {}", e))?;
+
+            // Anything extra would break the json and csv output
+            if *output == OutputFormat::Table {
+                println!("\n=== Viewing vaults for wallet: {} ===", wallet_pubkey);
+                println!("RPC address: {}", cli.rpc);
+            }
+
+            view_vaults(&rpc_client, &wallet_pubkey, *index, *output)
+        }
+
+        Commands::Status {
+            wallet,
+            mint,
+            index,
+            watch,
+            interval,
+        } => {
             let wallet_pubkey =
                 Pubkey::from_str(wallet).map_err(|e| anyhow!("Could not read wallet: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let (vault, _) = find_vault_address(&wallet_pubkey, &mint_pubkey, *index);
 
-            println!("\n=== Viewing vaults for wallet: {} ===", wallet_pubkey);
+            println!("\n=== Vault status ===");
             println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+
+            let watch = watch.then(|| Duration::from_secs(*interval));
+            vault_status(&rpc_client, &vault, watch)
+        }
 
-            view_vaults(&rpc_client, &wallet_pubkey)
+        Commands::WatchAndEmpty {
+            keypair,
+            config,
+            wallet,
+            poll_interval,
+            max_attempts,
+            backoff,
+            max_backoff,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+
+            // Everything goes to stdout as JSON lines, so no header here
+            let vaults = match (config, wallet) {
+                (Some(config), _) => WatchConfig::load(config)?,
+                (None, Some(wallet)) => {
+                    let wallet_pubkey = Pubkey::from_str(wallet)
+                        .map_err(|e| anyhow!("Could not read wallet: {}", e))?;
+                    fetch_vaults(&rpc_client, &wallet_pubkey, None)?
+                        .into_keys()
+                        .collect()
+                }
+                (None, None) => return Err(anyhow!("One of --config or --wallet is required")),
+            };
+
+            let retry = RetryPolicy {
+                max_attempts: (*max_attempts).max(1),
+                initial_backoff: Duration::from_secs(*backoff),
+                max_backoff: Duration::from_secs(*max_backoff),
+            };
+
+            watch_and_empty(
+                &rpc_client,
+                &keypair,
+                vaults,
+                Duration::from_secs(*poll_interval),
+                retry,
+                &fees,
+            )
         }
 
         Commands::Lock {
             keypair,
             mint,
+            sol,
+            index,
             tokens_to_lock,
             slots_to_lock,
+            until,
+            duration,
+            vest_slots,
+            cliff_slots,
+            beneficiary,
+            label,
+            dry_run,
+            yes,
+            max_duration,
+            max_fraction,
+            force,
         } => {
             let keypair =
                 read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
-            let mint_pubkey =
-                Pubkey::from_str(mint).map_err(|e| anyhow!("Could not read mint: {}", e))?;
+            let (mint_pubkey, tokens_to_lock) = mint_and_amount(mint, *sol, *tokens_to_lock)?;
+            let beneficiary_pubkey = beneficiary
+                .as_deref()
+                .map(Pubkey::from_str)
+                .transpose()
+                .map_err(|e| anyhow!("Could not read beneficiary: {}", e))?;
+            let lock_period = match vest_slots {
+                Some(slots_to_vest) => LockPeriod::LinearVesting {
+                    cliff_slots: cliff_slots.unwrap_or_default(),
+                    slots_to_vest: *slots_to_vest,
+                },
+                None => LockPeriod::parse(*slots_to_lock, until, duration)?,
+            };
 
             println!("\n=== Locking vault ===");
             println!("RPC address: {}", cli.rpc);
             println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
             println!(
                 "Tokens to lock: {}",
                 tokens_to_lock.map_or("All".to_string(), |a| a.to_string())
             );
-            println!("Slots to lock: {}", slots_to_lock);
+            println!("{}", lock_period);
+            if let Some(beneficiary_pubkey) = beneficiary_pubkey {
+                println!("Beneficiary: {}", beneficiary_pubkey);
+            }
+            if let Some(label) = label {
+                println!("Label: {}", label);
+            }
+
+            // Locks can't be undone, so this is the last chance to catch a typo
+            let summary = LockSummary::fetch(
+                &rpc_client,
+                &keypair.pubkey(),
+                &mint_pubkey,
+                tokens_to_lock,
+                lock_period,
+            )?;
+            println!("\n{}", summary);
+
+            if !force {
+                let guards = LockGuards {
+                    max_duration: *max_duration,
+                    max_fraction: *max_fraction,
+                };
+                guards.check(&summary)?;
+            }
+
+            if !yes && !dry_run && !confirm("Lock these tokens? This can't be undone")? {
+                println!("Lock cancelled");
+                return Ok(());
+            }
+
+            let ix_data = lock_period.ix_data(tokens_to_lock);
+            let ix_data = match beneficiary_pubkey {
+                Some(beneficiary) => ix_data.with_beneficiary(beneficiary.to_bytes()),
+                None => ix_data,
+            };
+            let ix_data = match label {
+                Some(label) => ix_data.with_label(*label),
+                None => ix_data,
+            };
 
             lock_vault(
                 &rpc_client,
                 &keypair,
                 &mint_pubkey,
-                *tokens_to_lock,
-                *slots_to_lock,
+                *index,
+                ix_data,
+                *dry_run,
+                &fees,
             )
         }
 
-        Commands::Empty { keypair, mint } => {
+        Commands::Deposit {
+            keypair,
+            mint,
+            sol,
+            index,
+            tokens_to_deposit,
+            admin,
+        } => {
             let keypair =
                 read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
-            let mint_pubkey =
-                Pubkey::from_str(mint).map_err(|e| anyhow!("Could not read mint: {}", e))?;
+            let (mint_pubkey, tokens_to_deposit) = mint_and_amount(mint, *sol, *tokens_to_deposit)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
 
-            println!("\n=== Emptying vault ===");
+            println!("\n=== Depositing into vault ===");
             println!("RPC address: {}", cli.rpc);
             println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+            println!(
+                "Tokens to deposit: {}",
+                tokens_to_deposit.map_or("All".to_string(), |a| a.to_string())
+            );
 
-            empty_vault(&rpc_client, &keypair, &mint_pubkey)
+            deposit_vault(
+                &rpc_client,
+                &keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                tokens_to_deposit,
+                &fees,
+            )
         }
-    }
-}
 
-pub fn view_vaults(rpc_client: &RpcClient, wallet: &Pubkey) -> Result<()> {
-    let program_id = id();
-    let config: RpcProgramAccountsConfig = RpcProgramAccountsConfig {
-        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-            3, // Offset to Admin pubkey
-            wallet.to_bytes().to_vec(),
-        ))]),
-        account_config: RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::Base64),
-            data_slice: None,
-            commitment: None,
-            min_context_slot: None,
-        },
-        with_context: None,
-        sort_results: None,
-    };
-
-    let results = rpc_client
-        .get_program_accounts_with_config(&program_id, config)
-        .map_err(|e| anyhow!("Could not fetch accounts {}", e))?;
-
-    if results.is_empty() {
-        println!("No vaults found");
-        return Ok(());
-    }
+        Commands::Extend {
+            keypair,
+            mint,
+            index,
+            slots_to_add,
+            until,
+            duration,
+            admin,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
 
-    for (pubkey, account) in results {
-        let vault_account = deserialize_vault(&account.data)
-            .map_err(|e| anyhow!("Could not deserialize account {}", e))?;
+            println!("\n=== Extending vault lock ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
 
-        let mint = Pubkey::new_from_array(*vault_account.mint());
-        let vault_ata = get_associated_token_address(&pubkey, &mint);
-        let vault_ata_account_raw = rpc_client.get_account(&vault_ata)?;
+            let ix_data = extend_lock_ix_data(
+                &rpc_client,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                *slots_to_add,
+                until,
+                duration,
+            )?;
 
-        let vault_ata_account =
-            spl_token_interface::state::Account::unpack(&vault_ata_account_raw.data)?;
+            extend_lock(
+                &rpc_client,
+                &keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                &ix_data,
+                &fees,
+            )
+        }
 
-        println!("\n{}", pubkey);
-        println!("Tokens Locked {}\n", vault_ata_account.amount);
-        println!("{}\n", vault_account);
-    }
+        Commands::Withdraw {
+            keypair,
+            mint,
+            index,
+            admin,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
 
-    Ok(())
-}
+            println!("\n=== Withdrawing vested tokens ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+
+            withdraw(
+                &rpc_client,
+                &keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                &fees,
+            )
+        }
+
+        Commands::Label {
+            keypair,
+            mint,
+            index,
+            label,
+            admin,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
+
+            println!("\n=== Updating vault label ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+            println!("Label: {}", label);
+
+            update_label(
+                &rpc_client,
+                &keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                *label,
+                &fees,
+            )
+        }
+
+        Commands::Empty {
+            keypair,
+            mint,
+            index,
+            admin,
+            dry_run,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
+
+            println!("\n=== Emptying vault ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+
+            empty_vault(
+                &rpc_client,
+                &keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                *dry_run,
+                &fees,
+            )
+        }
+
+        Commands::Crank { keypair, vault } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let vault_pubkey =
+                Pubkey::from_str(vault).map_err(|e| anyhow!("Could not read vault: {}", e))?;
+
+            println!("\n=== Cranking vault ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Vault: {}", vault_pubkey);
+
+            crank_empty(&rpc_client, &keypair, &vault_pubkey, &fees)
+        }
+
+        Commands::TransferAdmin {
+            keypair,
+            new_keypair,
+            mint,
+            index,
+            admin,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let new_keypair = read_keypair_file(new_keypair)
+                .map_err(|e| anyhow!("Could not read new keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
+
+            println!("\n=== Transferring vault admin ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Mint: {}", mint_pubkey);
+            println!("{}", VaultIndex(*index));
+            println!("New admin: {}", new_keypair.pubkey());
+
+            transfer_admin(
+                &rpc_client,
+                &keypair,
+                &new_keypair,
+                &admin_pubkey,
+                &mint_pubkey,
+                *index,
+                &fees,
+            )
+        }
+
+        Commands::LockBasket {
+            keypair,
+            index,
+            mint,
+            slots_to_lock,
+            until,
+            duration,
+            yes,
+            max_duration,
+            max_fraction,
+            force,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let lock_period = LockPeriod::parse(*slots_to_lock, until, duration)?;
+            let ix_data = create_basket_ix_data(lock_period, *index)?;
+
+            println!("\n=== Locking basket ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Basket index: {}", index);
+            println!("{}", lock_period);
+
+            // Same checks as `lock`, for every mint
+            let guards = LockGuards {
+                max_duration: *max_duration,
+                max_fraction: *max_fraction,
+            };
+            for (mint_pubkey, tokens_to_lock) in mint {
+                let summary = LockSummary::fetch(
+                    &rpc_client,
+                    &keypair.pubkey(),
+                    mint_pubkey,
+                    *tokens_to_lock,
+                    lock_period,
+                )?;
+                println!("\n{}", summary);
+
+                if !force {
+                    guards.check(&summary)?;
+                }
+            }
+
+            if !yes && !confirm("Lock these tokens? This can't be undone")? {
+                println!("Lock cancelled");
+                return Ok(());
+            }
+
+            lock_basket(&rpc_client, &keypair, ix_data, mint, &fees)
+        }
+
+        Commands::DepositBasket {
+            keypair,
+            index,
+            mint,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+
+            println!("\n=== Depositing into basket ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Basket index: {}", index);
+            for (mint_pubkey, tokens_to_deposit) in mint {
+                println!(
+                    "Mint: {} Tokens to deposit: {}",
+                    mint_pubkey,
+                    tokens_to_deposit.map_or("All".to_string(), |a| a.to_string())
+                );
+            }
+
+            deposit_basket(&rpc_client, &keypair, *index, mint, &fees)
+        }
+
+        Commands::EmptyBasket { keypair, index } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+
+            println!("\n=== Emptying basket ===");
+            println!("RPC address: {}", cli.rpc);
+            println!("Basket index: {}", index);
+
+            empty_basket(&rpc_client, &keypair, *index, &fees)
+        }
+
+        Commands::ViewBasket { wallet, index } => {
+            let wallet_pubkey =
+                Pubkey::from_str(wallet).map_err(|e| anyhow!("Could not read wallet: {}", e))?;
+
+            view_basket(&rpc_client, &wallet_pubkey, *index)
+        }
+
+        Commands::Stats { mint } => {
+            let mint_pubkey = parse_mint(mint)?;
+
+            println!("\n=== Mint stats ===");
+            println!("RPC address: {}", cli.rpc);
+
+            view_mint_stats(&rpc_client, &mint_pubkey)
+        }
+
+        Commands::Prove {
+            keypair,
+            mint,
+            index,
+            admin,
+            out,
+        } => {
+            let keypair =
+                read_keypair_file(keypair).map_err(|e| anyhow!("Could not read keypair: {}", e))?;
+            let mint_pubkey = parse_mint(mint)?;
+            let admin_pubkey = parse_admin(admin, &keypair)?;
+
+            let certificate = prove(&rpc_client, &keypair, &admin_pubkey, &mint_pubkey, *index)?;
+            let json = serde_json::to_string_pretty(&certificate)?;
+
+            // Without --out stdout is only the JSON, so it can be piped straight into a file
+            match out {
+                Some(out) => {
+                    std::fs::write(out, json).map_err(|e| {
+                        anyhow!("Could not write certificate {}: {}", out.display(), e)
+                    })?;
+
+                    println!("\n=== Proof of lock ===");
+                    println!("RPC address: {}", cli.rpc);
+                    println!("Vault: {}", certificate.vault);
+                    println!("Slot: {}", certificate.slot);
+                    println!("Tokens locked: {}", certificate.vault_token_balance_ui);
+                    println!("Certificate written to {}", out.display());
+                }
+                None => println!("{}", json),
+            }
+
+            Ok(())
+        }
+
+        Commands::Verify {
+            certificate,
+            offline,
+        } => {
+            let certificate = read_certificate(certificate)?;
+
+            println!("\n=== Verifying proof of lock ===");
+            let (vault, state) = verify_offline(&certificate)?;
+            println!("Vault: {}", vault);
+            println!("Mint: {}", state.mint);
+            println!(
+                "Signed by admin {} at slot {}",
+                state.admin, certificate.slot
+            );
+            println!("Tokens locked: {}", certificate.vault_token_balance_ui);
+
+            if !*offline {
+                println!("RPC address: {}", cli.rpc);
+                verify_on_chain(&rpc_client, &certificate, &vault, &state)?;
+            }
+
+            println!("Certificate is valid");
+
+            Ok(())
+        }
+    }
+}
+
+/// How long to lock for - either a number of slots, until a unix timestamp or vesting over a number of slots
+#[derive(Debug, Clone, Copy)]
+pub enum LockPeriod {
+    Slots(u64),
+    UnixTimestamp(i64),
+    LinearVesting {
+        cliff_slots: u64,
+        slots_to_vest: u64,
+    },
+}
+
+impl LockPeriod {
+    /// Clap makes sure exactly one of these is set
+    pub fn parse(
+        slots_to_lock: Option<u64>,
+        until: &Option<String>,
+        duration: &Option<String>,
+    ) -> Result<Self> {
+        if let Some(slots_to_lock) = slots_to_lock {
+            return Ok(Self::Slots(slots_to_lock));
+        }
+
+        if let Some(until) = until {
+            let until = DateTime::parse_from_rfc3339(until)
+                .map_err(|e| anyhow!("Could not read --until: {}", e))?;
+            return Ok(Self::UnixTimestamp(until.timestamp()));
+        }
+
+        if let Some(duration) = duration {
+            let duration = humantime::parse_duration(duration)
+                .map_err(|e| anyhow!("Could not read --duration: {}", e))?;
+            let unlock_time = SystemTime::now()
+                .checked_add(duration)
+                .ok_or_else(|| anyhow!("Duration is too long"))?;
+            let unlock_time: DateTime<Utc> = unlock_time.into();
+            return Ok(Self::UnixTimestamp(unlock_time.timestamp()));
+        }
+
+        Err(anyhow!(
+            "One of --slots-to-lock, --until, --duration or --vest-slots is required"
+        ))
+    }
+
+    /// The bump gets filled in by the SDK
+    pub fn ix_data(self, tokens_to_lock: Option<u64>) -> LockVaultIxData {
+        match self {
+            Self::Slots(slots_to_lock) => LockVaultIxData::new(0, slots_to_lock, tokens_to_lock),
+            Self::UnixTimestamp(unlock_timestamp) => {
+                LockVaultIxData::new_unix_timestamp(0, unlock_timestamp, tokens_to_lock)
+            }
+            Self::LinearVesting {
+                cliff_slots,
+                slots_to_vest,
+            } => LockVaultIxData::new_linear_vesting(0, cliff_slots, slots_to_vest, tokens_to_lock),
+        }
+    }
+}
+
+impl std::fmt::Display for LockPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockPeriod::Slots(slots) => write!(f, "Slots to lock: {}", slots),
+            LockPeriod::UnixTimestamp(timestamp) => {
+                let unlock_time = DateTime::<Utc>::from_timestamp(*timestamp, 0)
+                    .map_or(timestamp.to_string(), |t| t.to_rfc3339());
+                write!(f, "Locked until: {}", unlock_time)
+            }
+            LockPeriod::LinearVesting {
+                cliff_slots,
+                slots_to_vest,
+            } => write!(
+                f,
+                "Vesting over: {} slots, cliff: {} slots",
+                slots_to_vest, cliff_slots
+            ),
+        }
+    }
+}
+
+/// Just for printing, `None` is the v1 vault
+pub struct VaultIndex(pub Option<u64>);
+
+impl std::fmt::Display for VaultIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(index) => write!(f, "Vault index: {}", index),
+            None => write!(f, "Vault index: None (v1)"),
+        }
+    }
+}
+
+/// `native` ( or `sol` ) is the wSOL mint, SOL vaults are wSOL vaults
+pub fn parse_mint(mint: &str) -> Result<Pubkey> {
+    if mint.eq_ignore_ascii_case("native") || mint.eq_ignore_ascii_case("sol") {
+        return Ok(native_mint());
+    }
+
+    Pubkey::from_str(mint).map_err(|e| anyhow!("Could not read mint: {}", e))
+}
+
+/// `--sol` is `--mint native` with the amount in SOL instead of lamports. SOL always needs an
+/// amount, "all of it" would leave nothing to pay the fees with
+fn mint_and_amount(
+    mint: &Option<String>,
+    sol: Option<u64>,
+    amount: Option<u64>,
+) -> Result<(Pubkey, Option<u64>)> {
+    let (mint, amount) = match (sol, mint) {
+        (Some(lamports), _) => (native_mint(), Some(lamports)),
+        (None, Some(mint)) => (parse_mint(mint)?, amount),
+        (None, None) => return Err(anyhow!("One of --mint or --sol is required")),
+    };
+
+    if is_native_mint(&mint) && amount.is_none() {
+        return Err(anyhow!(
+            "SOL needs an amount, use --sol or give the amount in lamports"
+        ));
+    }
+
+    Ok((mint, amount))
+}
+
+/// For clap, `--sol 1.5` is 1,500,000,000 lamports
+fn parse_sol(value: &str) -> Result<u64, String> {
+    parse_ui_amount(value, spl_token_interface::native_mint::DECIMALS).map_err(|e| e.to_string())
+}
+
+/// For clap, labels have the same limits as on-chain - UTF-8 ( which a `&str` always is ) and
+/// at most `VaultLabel::MAX_LEN` bytes
+fn parse_label(value: &str) -> Result<VaultLabel, String> {
+    VaultLabel::new(value.as_bytes()).map_err(|_| {
+        format!(
+            "Label is {} bytes, it can be at most {}",
+            value.len(),
+            VaultLabel::MAX_LEN
+        )
+    })
+}
+
+/// `--admin` defaults to the keypair, it is only different when a beneficiary is signing
+pub fn parse_admin(admin: &Option<String>, keypair: &Keypair) -> Result<Pubkey> {
+    match admin {
+        Some(admin) => Pubkey::from_str(admin).map_err(|e| anyhow!("Could not read admin: {}", e)),
+        None => Ok(keypair.pubkey()),
+    }
+}
+
+/// Sends the transaction, and if the vault program failed, turns the error code into something
+/// a human can read instead of "custom program error: 0x2"
+pub fn send_transaction(rpc_client: &RpcClient, tx: &Transaction, vault: &Pubkey) -> Result<()> {
+    let Err(error) = rpc_client.send_and_confirm_transaction_with_spinner(tx) else {
+        return Ok(());
+    };
+
+    let program_error = error
+        .get_transaction_error()
+        .and_then(|error| program_error(tx, &error));
+
+    match program_error {
+        Some(DiamondHandsError::VaultLocked) => {
+            let message = vault_locked_message(rpc_client, vault)
+                .unwrap_or_else(|_| DiamondHandsError::VaultLocked.to_string());
+            Err(anyhow!(message))
+        }
+        Some(program_error) => Err(anyhow!("{}", program_error)),
+        None => Err(error.into()),
+    }
+}
+
+/// Only custom errors from our program - the token program has its own custom errors
+pub fn program_error(tx: &Transaction, error: &TransactionError) -> Option<DiamondHandsError> {
+    match error {
+        TransactionError::InstructionError(index, instruction_error) => tx
+            .message
+            .instructions
+            .get(*index as usize)
+            .filter(|ix| ix.program_id(&tx.message.account_keys).eq(&id()))
+            .and_then(|_| decode_instruction_error(instruction_error)),
+        _ => None,
+    }
+}
+
+/// "Vault locked" on its own isn't very helpful, so we look up how long is left
+fn vault_locked_message(rpc_client: &RpcClient, vault: &Pubkey) -> Result<String> {
+    let vault_account_raw = rpc_client.get_account(vault)?;
+    let vault_state = VaultState::from_account_data(&vault_account_raw.data)?;
+
+    let Some(unlock_slot) = vault_state.unlock_slot() else {
+        return Ok(format!(
+            "Vault locked until {}",
+            LockPeriod::UnixTimestamp(vault_state.unlock_timestamp)
+                .to_string()
+                .trim_start_matches("Locked until: ")
+        ));
+    };
+    let remaining_slots = unlock_slot.saturating_sub(rpc_client.get_slot()?);
+
+    Ok(format!("Vault locked for {} more slots", remaining_slots))
+}
+
+/// Same as the token accounts, Token-2022 mints can have extensions after the base mint
+pub fn mint_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+    let mint_account = rpc_client
+        .get_account(mint)
+        .map_err(|e| anyhow!("Could not fetch mint {}", e))?;
+    let base = mint_account
+        .data
+        .get(..spl_token_interface::state::Mint::LEN)
+        .ok_or_else(|| anyhow!("Mint data is too short"))?;
+
+    Ok(spl_token_interface::state::Mint::unpack_from_slice(base)?.decimals)
+}
+
+/// The mint's owner tells us if it is a Token or Token-2022 mint
+pub fn get_token_program(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    let mint_account = rpc_client
+        .get_account(mint)
+        .map_err(|e| anyhow!("Could not fetch mint {}", e))?;
+
+    if !is_token_program(&mint_account.owner) {
+        return Err(anyhow!(
+            "Mint is not owned by a token program: {}",
+            mint_account.owner
+        ));
+    }
+
+    Ok(mint_account.owner)
+}
+
+/// Token-2022 accounts can have extensions after the base account, so we only
+/// unpack the base account
+pub fn token_account_amount(data: &[u8]) -> Result<u64> {
+    let base = data
+        .get(..spl_token_interface::state::Account::LEN)
+        .ok_or_else(|| anyhow!("Token account data is too short"))?;
+    let token_account = spl_token_interface::state::Account::unpack_from_slice(base)?;
+
+    Ok(token_account.amount)
+}
 
 pub fn lock_vault(
     rpc_client: &RpcClient,
     keypair: &Keypair,
     mint: &Pubkey,
-    tokens_to_lock: Option<u64>,
-    slots_to_lock: u64,
+    vault_index: Option<u64>,
+    ix_data: LockVaultIxData,
+    dry_run: bool,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let token_program = get_token_program(rpc_client, mint)?;
+    let ixs = match ix_data.tokens_to_lock.as_ref() {
+        // SOL is wrapped first, `mint_and_amount` makes sure there is an amount
+        Some(lamports) if is_native_mint(mint) => {
+            let ixs =
+                lock_sol_with_ix_data_ix(&keypair.pubkey(), vault_index, ix_data, lamports.get());
+            fees.apply(
+                rpc_client,
+                ixs,
+                LOCK_VAULT_COMPUTE_UNITS + WRAP_SOL_COMPUTE_UNITS,
+            )?
+        }
+        _ => {
+            let ixs = lock_vault_with_ix_data_ix(
+                &keypair.pubkey(),
+                mint,
+                vault_index,
+                &token_program,
+                ix_data,
+            );
+            fees.apply(rpc_client, ixs.to_vec(), LOCK_VAULT_COMPUTE_UNITS)?
+        }
+    };
+    let (vault, bump) = find_vault_address(&keypair.pubkey(), mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
+
+    if dry_run {
+        // Locks can't be undone, so show exactly which accounts would be made
+        let admin_token = token_address(&keypair.pubkey(), mint, &token_program);
+        let vault_token = token_address(&vault, mint, &token_program);
+        println!("Vault (PDA): {}", vault);
+        println!("Vault bump: {}", bump);
+        println!("Vault token account (ATA): {}", vault_token);
+
+        return simulate_transaction(
+            rpc_client,
+            &tx,
+            mint,
+            &[
+                ("Your token account", admin_token),
+                ("Vault token account", vault_token),
+            ],
+        );
+    }
+
+    send_transaction(rpc_client, &tx, &vault)
+}
+
+pub fn deposit_vault(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    tokens_to_deposit: Option<u64>,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let token_program = get_token_program(rpc_client, mint)?;
+    let ixs = match tokens_to_deposit {
+        Some(lamports) if is_native_mint(mint) => fees.apply(
+            rpc_client,
+            deposit_sol_ix(&keypair.pubkey(), seed_admin, vault_index, lamports),
+            DEPOSIT_VAULT_COMPUTE_UNITS + WRAP_SOL_COMPUTE_UNITS,
+        )?,
+        _ => {
+            let ix = deposit_vault_ix(
+                &keypair.pubkey(),
+                seed_admin,
+                mint,
+                vault_index,
+                &token_program,
+                tokens_to_deposit,
+            );
+            fees.apply(rpc_client, vec![ix], DEPOSIT_VAULT_COMPUTE_UNITS)?
+        }
+    };
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
+
+    send_transaction(rpc_client, &tx, &vault)
+}
+
+/// `--duration` is added on to the vault's current unlock timestamp, so we have to fetch
+/// the vault first - `--until` and `--slots-to-add` go straight to the program
+pub fn extend_lock_ix_data(
+    rpc_client: &RpcClient,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    slots_to_add: Option<u64>,
+    until: &Option<String>,
+    duration: &Option<String>,
+) -> Result<ExtendLockIxData> {
+    let ix_data = match (slots_to_add, duration) {
+        (Some(slots_to_add), _) => {
+            println!("Slots to add: {}", slots_to_add);
+            ExtendLockIxData::new(slots_to_add)
+        }
+        (None, Some(duration)) => {
+            let duration = humantime::parse_duration(duration)
+                .map_err(|e| anyhow!("Could not read --duration: {}", e))?;
+            let duration =
+                i64::try_from(duration.as_secs()).map_err(|_| anyhow!("Duration is too long"))?;
+
+            let (vault, _) = find_vault_address(admin, mint, vault_index);
+            let vault_account_raw = rpc_client
+                .get_account(&vault)
+                .map_err(|e| anyhow!("Could not fetch vault {}", e))?;
+            let vault_account = deserialize_vault(&vault_account_raw.data)?;
+            if vault_account.lock_mode().ok() != Some(LockMode::UnixTimestamp) {
+                return Err(anyhow!(
+                    "--duration only works with timestamp locks, use --slots-to-add"
+                ));
+            }
+
+            let unlock_timestamp = vault_account
+                .unlock_timestamp()
+                .checked_add(duration)
+                .ok_or_else(|| anyhow!("Duration is too long"))?;
+
+            println!("{}", LockPeriod::UnixTimestamp(unlock_timestamp));
+            ExtendLockIxData::new_unix_timestamp(unlock_timestamp)
+        }
+        (None, None) => {
+            let LockPeriod::UnixTimestamp(unlock_timestamp) =
+                LockPeriod::parse(None, until, &None)?
+            else {
+                return Err(anyhow!(
+                    "One of --slots-to-add, --until or --duration is required"
+                ));
+            };
+
+            println!("{}", LockPeriod::UnixTimestamp(unlock_timestamp));
+            ExtendLockIxData::new_unix_timestamp(unlock_timestamp)
+        }
+    };
+
+    Ok(ix_data)
+}
+
+pub fn extend_lock(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    ix_data: &ExtendLockIxData,
+    fees: &FeeOptions,
+) -> Result<()> {
+    // The admin is also the withdraw authority unless the vault has a beneficiary
+    let ix = extend_lock_as_beneficiary_ix(&keypair.pubkey(), admin, mint, vault_index, ix_data);
+    let ixs = fees.apply(rpc_client, vec![ix], EXTEND_LOCK_COMPUTE_UNITS)?;
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
+
+    send_transaction(rpc_client, &tx, &vault)
+}
+
+pub fn withdraw(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let token_program = get_token_program(rpc_client, mint)?;
+    let ixs = if is_native_mint(mint) {
+        // Comes out as SOL, not wSOL
+        fees.apply(
+            rpc_client,
+            withdraw_sol_ix(&keypair.pubkey(), admin, vault_index),
+            WITHDRAW_COMPUTE_UNITS + UNWRAP_SOL_COMPUTE_UNITS,
+        )?
+    } else {
+        let ixs = if keypair.pubkey().eq(admin) {
+            withdraw_ix(admin, mint, vault_index, &token_program)
+        } else {
+            withdraw_as_beneficiary_ix(&keypair.pubkey(), admin, mint, vault_index, &token_program)
+        };
+        fees.apply(rpc_client, ixs.to_vec(), WITHDRAW_COMPUTE_UNITS)?
+    };
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
+
+    send_transaction(rpc_client, &tx, &vault)
+}
+
+pub fn empty_vault(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    dry_run: bool,
+    fees: &FeeOptions,
 ) -> Result<()> {
-    let ixs = lock_vault_ix(&keypair.pubkey(), mint, slots_to_lock, tokens_to_lock);
+    let token_program = get_token_program(rpc_client, mint)?;
+    let ixs = if is_native_mint(mint) {
+        // Same as withdraw, SOL comes back as SOL
+        fees.apply(
+            rpc_client,
+            empty_sol_vault_ix(&keypair.pubkey(), admin, vault_index),
+            EMPTY_VAULT_COMPUTE_UNITS + UNWRAP_SOL_COMPUTE_UNITS,
+        )?
+    } else {
+        let ixs = if keypair.pubkey().eq(admin) {
+            empty_vault_ix(admin, mint, vault_index, &token_program)
+        } else {
+            empty_vault_as_beneficiary_ix(
+                &keypair.pubkey(),
+                admin,
+                mint,
+                vault_index,
+                &token_program,
+            )
+        };
+        fees.apply(rpc_client, ixs.to_vec(), EMPTY_VAULT_COMPUTE_UNITS)?
+    };
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
 
     let blockhash = rpc_client.get_latest_blockhash()?;
     let tx =
         Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
 
-    rpc_client.send_and_confirm_transaction_with_spinner(&tx)?;
+    if dry_run {
+        return simulate_transaction(
+            rpc_client,
+            &tx,
+            mint,
+            &[
+                (
+                    "Vault token account",
+                    token_address(&vault, mint, &token_program),
+                ),
+                (
+                    "Your token account",
+                    token_address(&keypair.pubkey(), mint, &token_program),
+                ),
+            ],
+        );
+    }
 
-    Ok(())
+    send_transaction(rpc_client, &tx, &vault)
 }
 
-pub fn empty_vault(rpc_client: &RpcClient, keypair: &Keypair, mint: &Pubkey) -> Result<()> {
-    let ixs = empty_vault_ix(&keypair.pubkey(), mint);
+/// Everything comes from the vault itself, so this works for any vault
+pub fn crank_empty(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    vault: &Pubkey,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let vault_account_raw = rpc_client
+        .get_account(vault)
+        .map_err(|e| anyhow!("Could not fetch vault {}", e))?;
+    let state = VaultState::from_account_data(&vault_account_raw.data)?;
+    let token_program = get_token_program(rpc_client, &state.mint)?;
+
+    println!("Tokens go to: {}", state.withdraw_authority());
+    if state.is_native_sol() {
+        // Only the owner can close their token account
+        println!("SOL vault, it lands as wSOL - the owner can unwrap it with `spl-token unwrap`");
+    }
+
+    let ixs = crank_empty_ix(
+        &keypair.pubkey(),
+        &state.withdraw_authority(),
+        &state.seed_admin,
+        &state.mint,
+        state.vault_index,
+        &token_program,
+    );
+    let ixs = fees.apply(rpc_client, ixs.to_vec(), CRANK_EMPTY_COMPUTE_UNITS)?;
 
     let blockhash = rpc_client.get_latest_blockhash()?;
     let tx =
         Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
 
-    rpc_client.send_and_confirm_transaction_with_spinner(&tx)?;
+    send_transaction(rpc_client, &tx, vault)
+}
+
+/// `seed_admin` is the admin that locked the vault, the vault address is derived from it
+pub fn transfer_admin(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    new_keypair: &Keypair,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let ix = transfer_admin_ix(
+        &keypair.pubkey(),
+        &new_keypair.pubkey(),
+        seed_admin,
+        mint,
+        vault_index,
+    );
+    let ixs = fees.apply(rpc_client, vec![ix], TRANSFER_ADMIN_COMPUTE_UNITS)?;
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&keypair.pubkey()),
+        &[&keypair, &new_keypair],
+        blockhash,
+    );
+
+    send_transaction(rpc_client, &tx, &vault)
+}
+
+pub fn update_label(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    label: VaultLabel,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let ix = update_label_ix(&keypair.pubkey(), seed_admin, mint, vault_index, label);
+    let ixs = fees.apply(rpc_client, vec![ix], UPDATE_LABEL_COMPUTE_UNITS)?;
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&keypair.pubkey()), &[&keypair], blockhash);
 
-    Ok(())
+    send_transaction(rpc_client, &tx, &vault)
 }
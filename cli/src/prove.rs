@@ -0,0 +1,444 @@
+use std::{path::Path, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::{Keypair, Pubkey};
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solcat_diamond_hands_sdk::{find_vault_address, id, token_address, VaultState};
+
+use crate::{get_token_program, token_account_amount, view::format_ui_amount};
+
+/// Bumped if `ProofOfLock::message` ever changes, old certificates then fail to verify instead
+/// of verifying against the wrong bytes
+const CERTIFICATE_VERSION: u8 = 1;
+
+/// Goes in front of everything that is signed, so a certificate signature can never be passed
+/// off as a transaction ( or anything else the admin key signs )
+const CERTIFICATE_DOMAIN: &[u8] = b"solcat-diamond-hands proof-of-lock";
+
+/// A vault as it was at `slot`, signed by its admin. A Solscan link and pasted CLI output need
+/// you to trust whoever posted them, this can be checked by anyone - offline for the signature
+/// and the vault address, and against an RPC for whether the vault is still locked.
+///
+/// Only `vault_data` and the fields in `message` are signed, everything else is there to be read
+/// and `verify` checks it matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofOfLock {
+    pub version: u8,
+    pub program_id: String,
+    pub vault: String,
+    /// The slot the vault and its token account were read at
+    pub slot: u64,
+    pub vault_token: String,
+    /// In base units, `vault_token_balance_ui` has the mint decimals applied
+    pub vault_token_balance: u64,
+    pub vault_token_balance_ui: String,
+    pub mint: String,
+    /// The admin at `slot`, and the key that signed
+    pub admin: String,
+    /// The raw vault account, base64
+    pub vault_data: String,
+    /// `vault_data` decoded
+    pub state: VaultState,
+    /// ed25519, base58 - over `ProofOfLock::message`
+    pub signature: String,
+}
+
+impl ProofOfLock {
+    /// The canonical encoding that gets signed - the domain, then the fixed size fields and
+    /// then the vault account, length first. Nothing from the JSON is signed as is, so it can
+    /// be pretty printed or reordered without breaking the signature
+    pub fn message(
+        program_id: &Pubkey,
+        vault: &Pubkey,
+        slot: u64,
+        vault_token: &Pubkey,
+        vault_token_balance: u64,
+        vault_data: &[u8],
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(CERTIFICATE_DOMAIN.len() + 125 + vault_data.len());
+        message.extend_from_slice(CERTIFICATE_DOMAIN);
+        message.push(CERTIFICATE_VERSION);
+        message.extend_from_slice(program_id.as_ref());
+        message.extend_from_slice(vault.as_ref());
+        message.extend_from_slice(&slot.to_le_bytes());
+        message.extend_from_slice(vault_token.as_ref());
+        message.extend_from_slice(&vault_token_balance.to_le_bytes());
+        message.extend_from_slice(&(vault_data.len() as u32).to_le_bytes());
+        message.extend_from_slice(vault_data);
+
+        message
+    }
+
+    /// Signs `vault_data` as read at `slot`, the readable fields are all filled in from it.
+    /// Doesn't check `keypair` is the admin - `verify_offline` will
+    pub fn sign(
+        keypair: &Keypair,
+        vault: &Pubkey,
+        slot: u64,
+        vault_token_balance: u64,
+        vault_data: &[u8],
+    ) -> Result<Self> {
+        let program_id = id();
+        let state = VaultState::from_account_data(vault_data)?;
+        let message = ProofOfLock::message(
+            &program_id,
+            vault,
+            slot,
+            &state.vault_token,
+            vault_token_balance,
+            vault_data,
+        );
+        let signature = keypair.sign_message(&message);
+
+        Ok(ProofOfLock {
+            version: CERTIFICATE_VERSION,
+            program_id: program_id.to_string(),
+            vault: vault.to_string(),
+            slot,
+            vault_token: state.vault_token.to_string(),
+            vault_token_balance,
+            vault_token_balance_ui: format_ui_amount(vault_token_balance, state.mint_decimals),
+            mint: state.mint.to_string(),
+            admin: state.admin.to_string(),
+            vault_data: STANDARD.encode(vault_data),
+            state,
+            signature: signature.to_string(),
+        })
+    }
+}
+
+/// Reads the vault and its token account at the same slot and signs them. Only the vault's
+/// current admin can prove a lock, that is who the certificate vouches for
+pub fn prove(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+) -> Result<ProofOfLock> {
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+    // The vault token account is always the vault's ATA, so we don't need the vault to find it
+    let token_program = get_token_program(rpc_client, mint)?;
+    let vault_token = token_address(&vault, mint, &token_program);
+
+    // Both in one request so they are from the same slot
+    let response = rpc_client
+        .get_multiple_accounts_with_commitment(&[vault, vault_token], rpc_client.commitment())
+        .map_err(|e| anyhow!("Could not fetch vault {}: {}", vault, e))?;
+    let slot = response.context.slot;
+    let [Some(vault_account), Some(vault_token_account)] = response.value.as_slice() else {
+        return Err(anyhow!("Vault or vault token account does not exist"));
+    };
+    if vault_account.owner.ne(&id()) {
+        return Err(anyhow!("{} is not a vault account", vault));
+    }
+
+    // Checked against the same read that gets signed
+    let state = VaultState::from_account_data(&vault_account.data)?;
+    if state.admin.ne(&keypair.pubkey()) {
+        return Err(anyhow!(
+            "Only the vault admin can prove the lock, the admin is {}",
+            state.admin
+        ));
+    }
+    if state.vault_token.ne(&vault_token) {
+        return Err(anyhow!(
+            "Vault token account is not the vault's ATA {} != {}",
+            state.vault_token,
+            vault_token
+        ));
+    }
+    let vault_token_balance = token_account_amount(&vault_token_account.data)?;
+
+    ProofOfLock::sign(
+        keypair,
+        &vault,
+        slot,
+        vault_token_balance,
+        &vault_account.data,
+    )
+}
+
+/// Everything that can be checked without an RPC - the signature, that the readable fields
+/// match what was signed, and that the vault address really is the PDA for that vault.
+/// Returns the vault address and the signed state
+pub fn verify_offline(certificate: &ProofOfLock) -> Result<(Pubkey, VaultState)> {
+    if certificate.version != CERTIFICATE_VERSION {
+        return Err(anyhow!(
+            "Unsupported certificate version {}, expected {}",
+            certificate.version,
+            CERTIFICATE_VERSION
+        ));
+    }
+
+    let program_id = parse_pubkey("program_id", &certificate.program_id)?;
+    if program_id.ne(&id()) {
+        return Err(anyhow!(
+            "Certificate is for a different program {}, expected {}",
+            program_id,
+            id()
+        ));
+    }
+
+    let vault = parse_pubkey("vault", &certificate.vault)?;
+    let vault_token = parse_pubkey("vault_token", &certificate.vault_token)?;
+    let vault_data = STANDARD
+        .decode(&certificate.vault_data)
+        .map_err(|e| anyhow!("Could not decode vault_data: {}", e))?;
+    let state = VaultState::from_account_data(&vault_data)?;
+
+    // ----------------------- Signature -----------------------
+    let signature = Signature::from_str(&certificate.signature)
+        .map_err(|e| anyhow!("Could not read signature: {}", e))?;
+    let message = ProofOfLock::message(
+        &program_id,
+        &vault,
+        certificate.slot,
+        &vault_token,
+        certificate.vault_token_balance,
+        &vault_data,
+    );
+    if !signature.verify(state.admin.as_ref(), &message) {
+        return Err(anyhow!(
+            "Signature does not match, it was not signed by the vault admin {}",
+            state.admin
+        ));
+    }
+
+    // ----------------------- Readable fields -----------------------
+    // Not signed, so they have to match the signed data or they could say anything
+    let matches = certificate.state == state
+        && certificate.mint == state.mint.to_string()
+        && certificate.admin == state.admin.to_string()
+        && vault_token.eq(&state.vault_token)
+        && certificate.vault_token_balance_ui
+            == format_ui_amount(certificate.vault_token_balance, state.mint_decimals);
+    if !matches {
+        return Err(anyhow!(
+            "Certificate fields do not match the signed vault data"
+        ));
+    }
+
+    // ----------------------- Vault address -----------------------
+    let (expected_vault, _) = find_vault_address(&state.seed_admin, &state.mint, state.vault_index);
+    if vault.ne(&expected_vault) {
+        return Err(anyhow!(
+            "Vault address does not match its admin and mint {} != {}",
+            vault,
+            expected_vault
+        ));
+    }
+
+    Ok((vault, state))
+}
+
+/// The certificate is only about the slot it was made at - this checks the vault still exists
+/// and nothing has happened since that a lock can't do, see `VaultState::check_still_locked`
+pub fn verify_on_chain(
+    rpc_client: &RpcClient,
+    certificate: &ProofOfLock,
+    vault: &Pubkey,
+    certified: &VaultState,
+) -> Result<()> {
+    let response = rpc_client
+        .get_multiple_accounts_with_commitment(
+            &[*vault, certified.vault_token],
+            rpc_client.commitment(),
+        )
+        .map_err(|e| anyhow!("Could not fetch vault {}: {}", vault, e))?;
+    let slot = response.context.slot;
+    if slot < certificate.slot {
+        return Err(anyhow!(
+            "RPC is at slot {}, behind the certificate slot {}",
+            slot,
+            certificate.slot
+        ));
+    }
+
+    let [vault_account, vault_token_account] = response.value.as_slice() else {
+        return Err(anyhow!("RPC returned the wrong number of accounts"));
+    };
+    let vault_data = vault_account
+        .as_ref()
+        .filter(|a| a.owner.eq(&id()))
+        .map(|a| a.data.as_slice());
+    let vault_token_balance = match vault_token_account {
+        Some(account) => token_account_amount(&account.data)?,
+        None => 0,
+    };
+    let state = VaultState::check_still_locked(
+        certified,
+        certificate.vault_token_balance,
+        vault_data,
+        vault_token_balance,
+    )?;
+
+    println!("On-chain at slot {}:", slot);
+    println!(
+        "├─ Tokens locked: {}",
+        format_ui_amount(vault_token_balance, state.mint_decimals)
+    );
+    if state.admin.ne(&certified.admin) {
+        println!("├─ Admin has since been transferred to {}", state.admin);
+    }
+    match state.unlock_slot() {
+        Some(unlock_slot) => println!("└─ Unlock slot: {}", unlock_slot),
+        None => println!("└─ Unlock timestamp: {}", state.unlock_timestamp),
+    }
+
+    Ok(())
+}
+
+pub fn read_certificate(path: &Path) -> Result<ProofOfLock> {
+    let certificate = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read certificate {}: {}", path.display(), e))?;
+
+    serde_json::from_str(&certificate).map_err(|e| anyhow!("Could not parse certificate: {}", e))
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| anyhow!("Could not read {}: {}", field, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use solcat_diamond_hands_sdk::{
+        accounts::vault::Vault,
+        utils::{DataLen, Discriminator},
+    };
+
+    use super::*;
+
+    const SLOT: u64 = 1_000;
+    const BALANCE: u64 = 5_000_000;
+
+    /// A v1 vault that `admin` locked, with just enough set for the checks to care about
+    fn vault_data(admin: &Pubkey, mint: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; Vault::LEN];
+        data[0] = 1;
+        data[1] = Vault::DISCRIMINATOR;
+        data[Vault::ADMIN_OFFSET..Vault::ADMIN_OFFSET + 32].copy_from_slice(admin.as_ref());
+        data[Vault::MINT_OFFSET..Vault::MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+
+        data
+    }
+
+    fn certificate(keypair: &Keypair) -> Result<ProofOfLock> {
+        let mint = Pubkey::new_unique();
+        let (vault, _) = find_vault_address(&keypair.pubkey(), &mint, None);
+
+        ProofOfLock::sign(
+            keypair,
+            &vault,
+            SLOT,
+            BALANCE,
+            &vault_data(&keypair.pubkey(), &mint),
+        )
+    }
+
+    #[test]
+    fn test_proof_of_lock_message() {
+        let program_id = id();
+        let vault = Pubkey::new_unique();
+        let vault_token = Pubkey::new_unique();
+        let data = [7u8; 10];
+        let message = ProofOfLock::message(&program_id, &vault, SLOT, &vault_token, BALANCE, &data);
+
+        // Domain and version, then the fixed size fields, then the data length first
+        let (domain, rest) = message.split_at(CERTIFICATE_DOMAIN.len());
+        assert_eq!(domain, CERTIFICATE_DOMAIN);
+        assert_eq!(rest[0], CERTIFICATE_VERSION);
+        assert_eq!(&rest[1..33], program_id.as_ref());
+        assert_eq!(&rest[33..65], vault.as_ref());
+        assert_eq!(rest[65..73], SLOT.to_le_bytes());
+        assert_eq!(&rest[73..105], vault_token.as_ref());
+        assert_eq!(rest[105..113], BALANCE.to_le_bytes());
+        assert_eq!(rest[113..117], 10u32.to_le_bytes());
+        assert_eq!(&rest[117..], &data);
+
+        // Every field ends up in it
+        let changed = [
+            ProofOfLock::message(&program_id, &vault, SLOT + 1, &vault_token, BALANCE, &data),
+            ProofOfLock::message(&program_id, &vault, SLOT, &vault, BALANCE, &data),
+            ProofOfLock::message(&program_id, &vault, SLOT, &vault_token, BALANCE + 1, &data),
+            ProofOfLock::message(&program_id, &vault, SLOT, &vault_token, BALANCE, &data[1..]),
+            ProofOfLock::message(&vault, &vault, SLOT, &vault_token, BALANCE, &data),
+        ];
+        assert!(changed.iter().all(|changed| changed.ne(&message)));
+    }
+
+    #[test]
+    fn test_prove_and_verify_offline() -> Result<()> {
+        let keypair = Keypair::new();
+        let certificate = certificate(&keypair)?;
+
+        let (vault, state) = verify_offline(&certificate)?;
+        assert_eq!(vault.to_string(), certificate.vault);
+        assert_eq!(state.admin, keypair.pubkey());
+        assert_eq!(certificate.vault_token_balance_ui, "5000000");
+
+        // Still verifies after a trip through JSON
+        let json = serde_json::to_string_pretty(&certificate)?;
+        let certificate: ProofOfLock = serde_json::from_str(&json)?;
+        assert!(verify_offline(&certificate).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_offline_rejects_tampering() -> Result<()> {
+        let keypair = Keypair::new();
+        let certificate = certificate(&keypair)?;
+
+        // Longer lock in the signed data
+        let mut tampered = certificate.clone();
+        let mut data = STANDARD.decode(&tampered.vault_data)?;
+        data[Vault::LOCK_MODE_OFFSET - 1] = 1;
+        tampered.vault_data = STANDARD.encode(&data);
+        assert!(verify_offline(&tampered).is_err());
+
+        // Longer lock in the readable state only
+        let mut tampered = certificate.clone();
+        tampered.state.slots_locked += 1;
+        assert!(verify_offline(&tampered).is_err());
+
+        let mut tampered = certificate.clone();
+        tampered.slot += 1;
+        assert!(verify_offline(&tampered).is_err());
+
+        // Balance, with and without the ui amount to match
+        let mut tampered = certificate.clone();
+        tampered.vault_token_balance += 1;
+        assert!(verify_offline(&tampered).is_err());
+        tampered.vault_token_balance_ui = "5000001".to_string();
+        assert!(verify_offline(&tampered).is_err());
+
+        let mut tampered = certificate.clone();
+        tampered.vault_token_balance_ui = "5000001".to_string();
+        assert!(verify_offline(&tampered).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_offline_rejects_wrong_signer_and_program() -> Result<()> {
+        let admin = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let (vault, _) = find_vault_address(&admin.pubkey(), &mint, None);
+        let data = vault_data(&admin.pubkey(), &mint);
+
+        // Everything else is right, but it isn't the admin's signature
+        let certificate = ProofOfLock::sign(&Keypair::new(), &vault, SLOT, BALANCE, &data)?;
+        assert!(verify_offline(&certificate).is_err());
+
+        let mut certificate = ProofOfLock::sign(&admin, &vault, SLOT, BALANCE, &data)?;
+        certificate.program_id = Pubkey::new_unique().to_string();
+        assert!(verify_offline(&certificate).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use solana_keypair::Pubkey;
+use solana_transaction::{Transaction, TransactionError};
+
+use crate::{mint_decimals, program_error, token_account_amount, view::format_ui_amount};
+
+/// A token account to show the before and after of, e.g. ("Vault token account", vault_ata)
+pub type TokenAccountLabel<'a> = (&'a str, Pubkey);
+
+/// `--dry-run` - runs the transaction through `simulateTransaction` and prints what would have
+/// happened, nothing is sent. `token_accounts` are read before and after, that is the token
+/// movement. They all have to be for `mint`
+pub fn simulate_transaction(
+    rpc_client: &RpcClient,
+    tx: &Transaction,
+    mint: &Pubkey,
+    token_accounts: &[TokenAccountLabel],
+) -> Result<()> {
+    let decimals = mint_decimals(rpc_client, mint)?;
+    let before = token_accounts
+        .iter()
+        .map(|(_, address)| token_amount_before(rpc_client, address))
+        .collect::<Result<Vec<_>>>()?;
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: token_accounts
+                .iter()
+                .map(|(_, address)| address.to_string())
+                .collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(tx, config)
+        .map_err(|e| anyhow!("Could not simulate transaction {}", e))?
+        .value;
+
+    println!("\n=== Dry run, nothing was sent ===");
+    match result.err {
+        None => println!("Result: Success"),
+        Some(error) => {
+            let error = TransactionError::from(error);
+            match program_error(tx, &error) {
+                Some(program_error) => println!(
+                    "Result: Failed - {} ( custom program error: {:#x} )",
+                    program_error, program_error as u32
+                ),
+                None => println!("Result: Failed - {}", error),
+            }
+        }
+    }
+    println!(
+        "Compute units consumed: {}",
+        result
+            .units_consumed
+            .map_or("Unknown".to_string(), |units| units.to_string())
+    );
+
+    // A failed simulation doesn't hand back any accounts, nothing would have moved
+    if let Some(accounts) = result.accounts {
+        println!("Token movement:");
+        for (((label, address), before), after) in token_accounts.iter().zip(before).zip(accounts) {
+            // Closed accounts can come back with no data instead of not at all
+            let after = after
+                .and_then(|account| account.data.decode())
+                .and_then(|data| token_account_amount(&data).ok());
+            println!(
+                "├─ {} ({}): {} -> {}",
+                label,
+                address,
+                format_amount(before, decimals),
+                format_amount(after, decimals)
+            );
+        }
+    }
+
+    println!("Logs:");
+    for log in result.logs.unwrap_or_default() {
+        println!("  {}", log);
+    }
+
+    Ok(())
+}
+
+/// `None` if the account doesn't exist ( yet, or anymore )
+fn token_amount_before(rpc_client: &RpcClient, address: &Pubkey) -> Result<Option<u64>> {
+    let account = rpc_client
+        .get_account_with_commitment(address, rpc_client.commitment())
+        .map_err(|e| anyhow!("Could not fetch token account {}", e))?
+        .value;
+
+    account
+        .map(|account| token_account_amount(&account.data))
+        .transpose()
+}
+
+fn format_amount(amount: Option<u64>, decimals: u8) -> String {
+    amount.map_or("no account".to_string(), |amount| {
+        format_ui_amount(amount, decimals)
+    })
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::Pubkey;
+use solcat_diamond_hands_sdk::{id, mint_stats_address, MintStatsState};
+
+use crate::{
+    status::format_time_left,
+    view::{average_slot_time, format_ui_amount},
+};
+
+/// How much of a mint is locked right now, across every wallet - one account fetch instead of
+/// finding and fetching every vault
+pub fn view_mint_stats(rpc_client: &RpcClient, mint: &Pubkey) -> Result<()> {
+    let (mint_stats, _) = mint_stats_address(mint);
+    let account = rpc_client
+        .get_account_with_commitment(&mint_stats, rpc_client.commitment())
+        .map_err(|e| anyhow!("Could not fetch mint stats {}", e))?
+        .value;
+
+    // The first counted lock creates the account - locks from clients that don't pass it are
+    // never counted, so no account means no counted locks, not necessarily no vaults
+    let Some(account) = account.filter(|account| account.owner.eq(&id())) else {
+        println!("No vaults have been counted for {} yet", mint);
+        return Ok(());
+    };
+    let stats = MintStatsState::from_account_data(&account.data)?;
+
+    let average_lock_slots = stats.average_lock_slots();
+    let slot_time = average_slot_time(rpc_client)?;
+    let average_lock_time =
+        Duration::from_millis((slot_time.as_millis() as u64).saturating_mul(average_lock_slots));
+
+    println!("Mint stats: {}", mint_stats);
+    println!("├─ Mint: {}", stats.mint);
+    println!(
+        "├─ Tokens locked: {}",
+        format_ui_amount(stats.tokens_locked, stats.mint_decimals)
+    );
+    println!("├─ Active vaults: {}", stats.active_vaults);
+    println!("├─ Lifetime locks: {}", stats.lifetime_locks);
+    println!(
+        "└─ Average lock: {} slots (~{}, weighted by tokens locked)",
+        average_lock_slots,
+        format_time_left(average_lock_time)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::Pubkey;
+use solcat_diamond_hands_sdk::{accounts::vault::LockMode, VaultState};
+
+use crate::view::{fetch_vault_state, vault_view, ClusterClock};
+
+/// Shows how long is left on a vault. With `watch` it keeps going, printing a line every
+/// `watch` until the vault can be emptied ( or for vesting, until it is fully vested )
+pub fn vault_status(rpc_client: &RpcClient, vault: &Pubkey, watch: Option<Duration>) -> Result<()> {
+    let clock = ClusterClock::fetch(rpc_client)?;
+    let state = fetch_vault_state(rpc_client, vault)?;
+    let view = vault_view(rpc_client, vault, &state, &clock)?;
+
+    println!("\n{}", view);
+    println!(
+        "Slot time: {}ms (from recent performance samples)",
+        clock.slot_time.as_millis()
+    );
+    println!("\n{}", status_line(rpc_client, &state, &clock)?);
+
+    let Some(interval) = watch else {
+        return Ok(());
+    };
+
+    let mut unlockable = view.unlockable;
+    while !unlockable {
+        std::thread::sleep(interval);
+
+        // Re-fetched each time, an `ExtendLock` in the meantime moves the unlock
+        let clock = ClusterClock::fetch(rpc_client)?;
+        let state = fetch_vault_state(rpc_client, vault)?;
+        unlockable = is_unlockable(&state, &clock);
+
+        println!("{}", status_line(rpc_client, &state, &clock)?);
+    }
+
+    Ok(())
+}
+
+fn is_unlockable(state: &VaultState, clock: &ClusterClock) -> bool {
+    state.is_unlockable(clock.slot) || state.is_unlockable_at_timestamp(clock.unix_timestamp)
+}
+
+/// One line, so `--watch` output reads fine in a terminal and in a log file
+fn status_line(rpc_client: &RpcClient, state: &VaultState, clock: &ClusterClock) -> Result<String> {
+    let now = DateTime::<Utc>::from_timestamp(clock.unix_timestamp, 0)
+        .map_or(clock.unix_timestamp.to_string(), |now| now.to_rfc3339());
+
+    if is_unlockable(state, clock) {
+        let status = match state.lock_mode {
+            LockMode::LinearVesting => "Fully vested, withdraw or empty it now",
+            _ => "Unlocked, empty it now",
+        };
+        return Ok(format!("[{}] Slot {}: {}", now, clock.slot, status));
+    }
+
+    let Some(unlock_slot) = state.unlock_slot() else {
+        // Timestamp locks are exact, no slot estimate needed
+        let seconds_left = state.unlock_timestamp.saturating_sub(clock.unix_timestamp);
+        let unlock_time = DateTime::<Utc>::from_timestamp(state.unlock_timestamp, 0)
+            .map_or(state.unlock_timestamp.to_string(), |t| t.to_rfc3339());
+
+        return Ok(format!(
+            "[{}] Slot {}: unlocks at {} (in {})",
+            now,
+            clock.slot,
+            unlock_time,
+            format_time_left(Duration::from_secs(seconds_left.max(0) as u64))
+        ));
+    };
+
+    let remaining_slots = unlock_slot.saturating_sub(clock.slot);
+    let unlock_time = clock
+        .estimate_slot_time(unlock_slot)
+        .map_or("unknown".to_string(), |t| t.to_rfc3339());
+
+    // Epochs aren't always 432,000 slots ( e.g. a local validator ), so ask the RPC
+    let epoch_info = rpc_client
+        .get_epoch_info()
+        .map_err(|e| anyhow!("Could not fetch epoch info {}", e))?;
+    let epochs_left = remaining_slots as f64 / epoch_info.slots_in_epoch as f64;
+    let unlock_epoch =
+        epoch_info.epoch + (epoch_info.slot_index + remaining_slots) / epoch_info.slots_in_epoch;
+
+    Ok(format!(
+        "[{}] Slot {}: {} slots ({:.3} epochs) left, unlocks in epoch {} around {} (in {})",
+        now,
+        clock.slot,
+        remaining_slots,
+        epochs_left,
+        unlock_epoch,
+        unlock_time,
+        format_time_left(clock.time_until_slot(unlock_slot))
+    ))
+}
+
+/// Rounded to the second, humantime would happily print the nanoseconds
+pub fn format_time_left(time_left: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(time_left.as_secs())).to_string()
+}
@@ -0,0 +1,426 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_keypair::Pubkey;
+use solana_program::sysvar;
+use solcat_diamond_hands_sdk::{
+    accounts::{
+        vault::{LockMode, Vault},
+        vault_extension::VaultExtension,
+    },
+    client::vaults_by_pubkey_config,
+    clock_unix_timestamp, id, native_mint, VaultAccount, VaultState,
+};
+
+use crate::token_account_amount;
+
+/// What we fall back to if the RPC has no performance samples, e.g. a fresh local validator
+const DEFAULT_SLOT_TIME: Duration = Duration::from_millis(400);
+
+/// `table` is for people, `json` and `csv` are for piping into other tools
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+/// One vault, flattened out with everything already formatted - pubkeys are base58 and
+/// token amounts are in UI units ( `mint_decimals` applied )
+#[derive(Serialize, Debug, Clone)]
+pub struct VaultView {
+    pub address: String,
+    /// `None` if the vault was never named
+    pub label: Option<String>,
+    pub vault_index: Option<u64>,
+    pub admin: String,
+    /// Only different from `admin` after a `TransferAdmin`, this is the admin to pass as --admin
+    pub seed_admin: String,
+    pub beneficiary: Option<String>,
+    pub mint: String,
+    pub vault_token: String,
+    pub lock_mode: String,
+    pub tokens_locked: u64,
+    pub tokens_locked_ui: String,
+    pub mint_decimals: u8,
+    pub start_slot: u64,
+    pub slots_locked: u64,
+    pub cliff_slots: u64,
+    pub tokens_claimed: u64,
+    /// `None` for timestamp locks, they don't unlock at a set slot
+    pub unlock_slot: Option<u64>,
+    pub remaining_slots: Option<u64>,
+    /// RFC3339 - exact for timestamp locks, estimated from the recent slot times otherwise
+    pub estimated_unlock: Option<String>,
+    pub unlockable: bool,
+}
+
+impl VaultView {
+    pub fn new(
+        address: &Pubkey,
+        state: &VaultState,
+        tokens_locked: u64,
+        clock: &ClusterClock,
+    ) -> Self {
+        let unlock_slot = state.unlock_slot();
+        let remaining_slots = unlock_slot.map(|unlock_slot| unlock_slot.saturating_sub(clock.slot));
+
+        let (estimated_unlock, unlockable) = match unlock_slot {
+            Some(unlock_slot) => (
+                clock.estimate_slot_time(unlock_slot),
+                state.is_unlockable(clock.slot),
+            ),
+            None => (
+                DateTime::<Utc>::from_timestamp(state.unlock_timestamp, 0),
+                state.is_unlockable_at_timestamp(clock.unix_timestamp),
+            ),
+        };
+
+        Self {
+            address: address.to_string(),
+            label: state
+                .label
+                .as_str()
+                .filter(|label| !label.is_empty())
+                .map(str::to_string),
+            vault_index: state.vault_index,
+            admin: state.admin.to_string(),
+            seed_admin: state.seed_admin.to_string(),
+            beneficiary: state.beneficiary.map(|beneficiary| beneficiary.to_string()),
+            mint: state.mint.to_string(),
+            vault_token: state.vault_token.to_string(),
+            lock_mode: state.lock_mode.to_string(),
+            tokens_locked,
+            tokens_locked_ui: format_ui_amount(tokens_locked, state.mint_decimals),
+            mint_decimals: state.mint_decimals,
+            start_slot: state.start_slot,
+            slots_locked: state.slots_locked,
+            cliff_slots: state.cliff_slots,
+            tokens_claimed: state.tokens_claimed,
+            unlock_slot,
+            remaining_slots,
+            estimated_unlock: estimated_unlock.map(|unlock| unlock.to_rfc3339()),
+            unlockable,
+        }
+    }
+
+    const CSV_HEADER: &'static str = "address,label,vault_index,admin,seed_admin,beneficiary,mint,\
+        vault_token,lock_mode,tokens_locked,tokens_locked_ui,mint_decimals,start_slot,slots_locked,\
+        cliff_slots,tokens_claimed,unlock_slot,remaining_slots,estimated_unlock,unlockable";
+
+    /// The label is the only value that can have a comma or a quote in it, so it is the only one
+    /// that gets escaped
+    fn to_csv_row(&self) -> String {
+        fn optional<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map_or(String::new(), T::to_string)
+        }
+
+        [
+            self.address.clone(),
+            self.label.as_deref().map_or(String::new(), csv_escape),
+            optional(&self.vault_index),
+            self.admin.clone(),
+            self.seed_admin.clone(),
+            optional(&self.beneficiary),
+            self.mint.clone(),
+            self.vault_token.clone(),
+            self.lock_mode.clone(),
+            self.tokens_locked.to_string(),
+            self.tokens_locked_ui.clone(),
+            self.mint_decimals.to_string(),
+            self.start_slot.to_string(),
+            self.slots_locked.to_string(),
+            self.cliff_slots.to_string(),
+            self.tokens_claimed.to_string(),
+            optional(&self.unlock_slot),
+            optional(&self.remaining_slots),
+            optional(&self.estimated_unlock),
+            self.unlockable.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+impl std::fmt::Display for VaultView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let none = || "None".to_string();
+
+        writeln!(f, "{}", self.address)?;
+        if let Some(label) = &self.label {
+            writeln!(f, "├─ Label: {}", label)?;
+        }
+        writeln!(
+            f,
+            "├─ Vault Index: {}",
+            self.vault_index
+                .map_or("None (v1)".to_string(), |i| i.to_string())
+        )?;
+        writeln!(f, "├─ Admin: {}", self.admin)?;
+        if self.seed_admin != self.admin {
+            writeln!(f, "├─ Seed Admin: {}", self.seed_admin)?;
+        }
+        writeln!(
+            f,
+            "├─ Beneficiary: {}",
+            self.beneficiary.clone().unwrap_or_else(none)
+        )?;
+        if self.mint == native_mint().to_string() {
+            writeln!(f, "├─ Mint: {} (native SOL)", self.mint)?;
+        } else {
+            writeln!(f, "├─ Mint: {}", self.mint)?;
+        }
+        writeln!(f, "├─ Vault Token Account: {}", self.vault_token)?;
+        writeln!(
+            f,
+            "├─ Tokens Locked: {} ({} base units)",
+            self.tokens_locked_ui, self.tokens_locked
+        )?;
+        writeln!(f, "├─ Lock Mode: {}", self.lock_mode)?;
+        writeln!(f, "├─ Start Slot: {}", self.start_slot)?;
+        if self.lock_mode == LockMode::LinearVesting.to_string() {
+            writeln!(f, "├─ Cliff Slots: {}", self.cliff_slots)?;
+            writeln!(f, "├─ Tokens Claimed: {}", self.tokens_claimed)?;
+        }
+        if let (Some(unlock_slot), Some(remaining_slots)) = (self.unlock_slot, self.remaining_slots)
+        {
+            writeln!(f, "├─ Unlock Slot: {}", unlock_slot)?;
+            writeln!(
+                f,
+                "├─ Remaining Slots: {} ({:.3} epochs)",
+                remaining_slots,
+                remaining_slots as f64 / 432_000.0
+            )?;
+        }
+        writeln!(
+            f,
+            "├─ Unlock Time: {}",
+            self.estimated_unlock.clone().unwrap_or_else(none)
+        )?;
+        write!(f, "└─ Unlockable: {}", self.unlockable)
+    }
+}
+
+/// Quoted if it has to be, with any quotes doubled up
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Where the cluster is right now, and how fast it is moving - used to turn slots into times
+pub struct ClusterClock {
+    pub slot: u64,
+    /// From the `Clock` sysvar, this is what timestamp locks are checked against
+    pub unix_timestamp: i64,
+    pub slot_time: Duration,
+}
+
+impl ClusterClock {
+    pub fn fetch(rpc_client: &RpcClient) -> Result<Self> {
+        let clock_account = rpc_client
+            .get_account(&sysvar::clock::ID)
+            .map_err(|e| anyhow!("Could not fetch the clock {}", e))?;
+
+        Ok(Self {
+            slot: rpc_client.get_slot()?,
+            unix_timestamp: clock_unix_timestamp(&clock_account.data)?,
+            slot_time: average_slot_time(rpc_client)?,
+        })
+    }
+
+    /// How long until `slot` at the current slot time, zero if it has already passed
+    pub fn time_until_slot(&self, slot: u64) -> Duration {
+        let slots = slot.saturating_sub(self.slot);
+        let millis = (self.slot_time.as_millis() as u64).saturating_mul(slots);
+
+        Duration::from_millis(millis)
+    }
+
+    /// Slots aren't a fixed length, so this is only ever an estimate - it works for past slots too
+    pub fn estimate_slot_time(&self, slot: u64) -> Option<DateTime<Utc>> {
+        let slots = i64::try_from(slot.abs_diff(self.slot)).ok()?;
+        let millis = slots.checked_mul(i64::try_from(self.slot_time.as_millis()).ok()?)?;
+        let offset = chrono::Duration::try_milliseconds(millis)?;
+        let now = DateTime::<Utc>::from_timestamp(self.unix_timestamp, 0)?;
+
+        if slot >= self.slot {
+            now.checked_add_signed(offset)
+        } else {
+            now.checked_sub_signed(offset)
+        }
+    }
+}
+
+/// Averaged over the last hour of performance samples ( each sample is about a minute )
+pub fn average_slot_time(rpc_client: &RpcClient) -> Result<Duration> {
+    let samples = rpc_client
+        .get_recent_performance_samples(Some(60))
+        .map_err(|e| anyhow!("Could not fetch performance samples {}", e))?;
+
+    let slots: u64 = samples.iter().map(|sample| sample.num_slots).sum();
+    let seconds: u64 = samples
+        .iter()
+        .map(|sample| sample.sample_period_secs as u64)
+        .sum();
+
+    if slots == 0 {
+        return Ok(DEFAULT_SLOT_TIME);
+    }
+
+    Ok(Duration::from_secs(seconds) / slots as u32)
+}
+
+/// Base units to UI units, e.g. `1_500_000` with 6 decimals is `1.5`. Done on the string so
+/// there is no rounding, unlike `f64`
+pub fn format_ui_amount(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// UI units to base units, the other way around from `format_ui_amount` - `1.5` with 9 decimals
+/// is `1_500_000_000`. More decimals than the mint has is an error, not a rounding
+pub fn parse_ui_amount(amount: &str, decimals: u8) -> Result<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if fraction.len() > decimals as usize {
+        return Err(anyhow!(
+            "{} has more than {} decimal places",
+            amount,
+            decimals
+        ));
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("{} is not an amount", amount));
+    }
+
+    digits
+        .parse()
+        .map_err(|_| anyhow!("{} is too large", amount))
+}
+
+/// Shows the vaults the wallet locked, as well as any vaults locked for it as the beneficiary
+pub fn view_vaults(
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    index: Option<u64>,
+    output: OutputFormat,
+) -> Result<()> {
+    let views = fetch_vault_views(rpc_client, wallet, index)?;
+
+    match output {
+        OutputFormat::Table => {
+            if views.is_empty() {
+                println!("No vaults found");
+            }
+
+            for view in views {
+                println!("\n{}", view);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&views)?),
+        OutputFormat::Csv => {
+            println!("{}", VaultView::CSV_HEADER);
+            for view in views {
+                println!("{}", view.to_csv_row());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn fetch_vault_views(
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    index: Option<u64>,
+) -> Result<Vec<VaultView>> {
+    let vaults = fetch_vaults(rpc_client, wallet, index)?;
+
+    if vaults.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let clock = ClusterClock::fetch(rpc_client)?;
+    let mut views = Vec::with_capacity(vaults.len());
+
+    for (address, state) in vaults {
+        views.push(vault_view(rpc_client, &address, &state, &clock)?);
+    }
+
+    Ok(views)
+}
+
+/// The vaults the wallet is the admin or the beneficiary of, by address
+pub fn fetch_vaults(
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    index: Option<u64>,
+) -> Result<BTreeMap<Pubkey, VaultState>> {
+    // Keyed by address, a wallet can be both the admin and the beneficiary of the same vault
+    let mut vaults = BTreeMap::new();
+
+    // Once for the vaults the wallet locked, and once for the ones locked for it as the
+    // beneficiary - the same queries as `VaultClient::get_vaults_by_admin` and
+    // `get_vaults_by_beneficiary`
+    for offset in [Vault::ADMIN_OFFSET, VaultExtension::BENEFICIARY_OFFSET] {
+        let accounts = rpc_client
+            .get_program_accounts_with_config(&id(), vaults_by_pubkey_config(offset, wallet))
+            .map_err(|e| anyhow!("Could not fetch accounts {}", e))?;
+
+        for (address, account) in accounts {
+            // Baskets have their own `view-basket` and mint stats `stats`
+            let Some(vault) = VaultAccount::from_program_account(address, &account.data)
+                .map_err(|e| anyhow!("Could not deserialize account {}", e))?
+            else {
+                continue;
+            };
+
+            if index.is_some() && vault.state.vault_index != index {
+                continue;
+            }
+
+            vaults.insert(address, vault.state);
+        }
+    }
+
+    Ok(vaults)
+}
+
+/// Just the one vault, by address
+pub fn fetch_vault_state(rpc_client: &RpcClient, vault: &Pubkey) -> Result<VaultState> {
+    let vault_account = rpc_client
+        .get_account(vault)
+        .map_err(|e| anyhow!("Could not fetch vault {}: {}", vault, e))?;
+
+    VaultState::from_account_data(&vault_account.data)
+        .map_err(|e| anyhow!("Could not deserialize account {}", e))
+}
+
+/// Fetches the vault's token account for the amount locked
+pub fn vault_view(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    state: &VaultState,
+    clock: &ClusterClock,
+) -> Result<VaultView> {
+    let vault_ata_account_raw = rpc_client.get_account(&state.vault_token)?;
+    let tokens_locked = token_account_amount(&vault_ata_account_raw.data)?;
+
+    Ok(VaultView::new(address, state, tokens_locked, clock))
+}
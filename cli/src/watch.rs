@@ -0,0 +1,309 @@
+use std::{collections::BTreeMap, path::Path, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_keypair::{Keypair, Pubkey};
+use solana_program::sysvar;
+use solana_signer::Signer;
+use solana_transaction::{Transaction, TransactionError};
+use solcat_diamond_hands_sdk::{
+    clock_unix_timestamp,
+    compute_budget::{
+        CRANK_EMPTY_COMPUTE_UNITS, EMPTY_VAULT_COMPUTE_UNITS, UNWRAP_SOL_COMPUTE_UNITS,
+    },
+    crank_empty_ix, decode_instruction_error, empty_vault_as_beneficiary_ix,
+    errors::DiamondHandsError,
+    native_sol::empty_sol_vault_ix,
+    VaultState,
+};
+
+use crate::{fees::FeeOptions, get_token_program};
+
+/// The `--config` file, just the vaults to watch:
+///
+/// `{ "vaults": ["<VAULT_ADDRESS>", "<VAULT_ADDRESS>"] }`
+#[derive(Deserialize, Debug)]
+pub struct WatchConfig {
+    pub vaults: Vec<String>,
+}
+
+impl WatchConfig {
+    pub fn load(path: &Path) -> Result<Vec<Pubkey>> {
+        let config = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Could not read config {}: {}", path.display(), e))?;
+        let config: WatchConfig = serde_json::from_str(&config)
+            .map_err(|e| anyhow!("Could not parse config {}: {}", path.display(), e))?;
+
+        config
+            .vaults
+            .iter()
+            .map(|vault| {
+                Pubkey::from_str(vault)
+                    .map_err(|e| anyhow!("Could not read vault {}: {}", vault, e))
+            })
+            .collect()
+    }
+}
+
+/// How hard to try once a vault is unlocked, the backoff doubles after every failed attempt
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+/// One JSON object per line, so the output can go straight into a log pipeline
+#[derive(Serialize, Debug)]
+struct LogLine {
+    timestamp: String,
+    event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vault: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlock_slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlock_timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_in_ms: Option<u64>,
+}
+
+impl LogLine {
+    fn new(event: &'static str, vault: Option<&Pubkey>) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339(),
+            event,
+            vault: vault.map(Pubkey::to_string),
+            attempt: None,
+            unlock_slot: None,
+            unlock_timestamp: None,
+            signature: None,
+            error: None,
+            retry_in_ms: None,
+        }
+    }
+
+    fn print(&self) {
+        // Serializing strings and numbers can't fail
+        println!("{}", serde_json::to_string(self).unwrap_or_default());
+    }
+}
+
+/// Polls every vault until it unlocks, then empties it. Runs until every vault has been
+/// emptied or given up on. RPC hiccups while polling are logged and tried again next poll,
+/// so it can be left running
+pub fn watch_and_empty(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    vaults: Vec<Pubkey>,
+    poll_interval: Duration,
+    retry: RetryPolicy,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let mut pending = BTreeMap::new();
+
+    for vault in vaults {
+        let state = match fetch_vault(rpc_client, &vault) {
+            Ok(Some(state)) => state,
+            Ok(None) => {
+                LogLine::new("closed", Some(&vault)).print();
+                continue;
+            }
+            Err(e) => {
+                log_error("skipped", &vault, e);
+                continue;
+            }
+        };
+
+        let mut log = LogLine::new("watching", Some(&vault));
+        log.unlock_slot = state.unlock_slot();
+        log.unlock_timestamp = state
+            .unlock_slot()
+            .is_none()
+            .then_some(state.unlock_timestamp);
+        log.print();
+
+        pending.insert(vault, state);
+    }
+
+    while !pending.is_empty() {
+        match poll(rpc_client, keypair, &mut pending, retry, fees) {
+            Ok(()) if pending.is_empty() => break,
+            Ok(()) => {}
+            Err(e) => {
+                let mut log = LogLine::new("poll_failed", None);
+                log.error = Some(e.to_string());
+                log.print();
+            }
+        }
+
+        std::thread::sleep(poll_interval);
+    }
+
+    LogLine::new("done", None).print();
+    Ok(())
+}
+
+/// Checks every pending vault once, and empties the ones that have unlocked
+fn poll(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    pending: &mut BTreeMap<Pubkey, VaultState>,
+    retry: RetryPolicy,
+    fees: &FeeOptions,
+) -> Result<()> {
+    let slot = rpc_client.get_slot()?;
+    let clock_account = rpc_client.get_account(&sysvar::clock::ID)?;
+    let unix_timestamp = clock_unix_timestamp(&clock_account.data)?;
+
+    let vaults: Vec<Pubkey> = pending.keys().copied().collect();
+    for vault in vaults {
+        // Re-fetched every time, an `ExtendLock` in the meantime moves the unlock
+        let Some(state) = fetch_vault(rpc_client, &vault)? else {
+            // Someone else got to it first
+            LogLine::new("closed", Some(&vault)).print();
+            pending.remove(&vault);
+            continue;
+        };
+        pending.insert(vault, state);
+
+        if !state.is_unlockable(slot) && !state.is_unlockable_at_timestamp(unix_timestamp) {
+            continue;
+        }
+
+        match empty_with_retry(rpc_client, keypair, &vault, &state, retry, fees) {
+            Ok(signature) => {
+                let mut log = LogLine::new("emptied", Some(&vault));
+                log.signature = Some(signature);
+                log.print();
+            }
+            Err(e) => log_error("gave_up", &vault, e),
+        }
+        pending.remove(&vault);
+    }
+
+    Ok(())
+}
+
+/// `None` once the vault has been emptied, the account is closed
+fn fetch_vault(rpc_client: &RpcClient, vault: &Pubkey) -> Result<Option<VaultState>> {
+    let vault_account = rpc_client
+        .get_account_with_commitment(vault, rpc_client.commitment())
+        .map_err(|e| anyhow!("Could not fetch vault {}: {}", vault, e))?
+        .value;
+
+    vault_account
+        .map(|account| VaultState::from_account_data(&account.data))
+        .transpose()
+}
+
+fn empty_with_retry(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    vault: &Pubkey,
+    state: &VaultState,
+    retry: RetryPolicy,
+    fees: &FeeOptions,
+) -> Result<String> {
+    let token_program = get_token_program(rpc_client, &state.mint)?;
+    let is_authority = state.withdraw_authority() == keypair.pubkey();
+    let (ixs, cu_limit) = if is_authority && state.is_native_sol() {
+        // Our own SOL vault, unwrapped on the way out
+        let ixs = empty_sol_vault_ix(&keypair.pubkey(), &state.seed_admin, state.vault_index);
+        (ixs, EMPTY_VAULT_COMPUTE_UNITS + UNWRAP_SOL_COMPUTE_UNITS)
+    } else if is_authority {
+        // The same as `empty_vault_ix` when the keypair is the admin that locked the vault, this
+        // one also covers beneficiaries and transferred vaults
+        let ixs = empty_vault_as_beneficiary_ix(
+            &keypair.pubkey(),
+            &state.seed_admin,
+            &state.mint,
+            state.vault_index,
+            &token_program,
+        );
+        (ixs.to_vec(), EMPTY_VAULT_COMPUTE_UNITS)
+    } else {
+        // Someone else's vault, the tokens still go to them - we just pay the fee. We can't
+        // unwrap SOL for them, so SOL vaults end up as wSOL in their token account
+        let ixs = crank_empty_ix(
+            &keypair.pubkey(),
+            &state.withdraw_authority(),
+            &state.seed_admin,
+            &state.mint,
+            state.vault_index,
+            &token_program,
+        );
+        (ixs.to_vec(), CRANK_EMPTY_COMPUTE_UNITS)
+    };
+
+    let mut backoff = retry.initial_backoff;
+    for attempt in 1..=retry.max_attempts {
+        // A fresh blockhash every attempt, an expired one is the usual reason to be here again.
+        // With `auto` the priority fee is looked up again too, congestion is the other reason
+        let result = fees
+            .compute_budget(rpc_client, &ixs, cu_limit)
+            .and_then(|compute_budget| {
+                let ixs = compute_budget.prepend(ixs.iter().cloned());
+                let blockhash = rpc_client.get_latest_blockhash()?;
+                let tx = Transaction::new_signed_with_payer(
+                    &ixs,
+                    Some(&keypair.pubkey()),
+                    &[keypair],
+                    blockhash,
+                );
+                rpc_client.send_and_confirm_transaction(&tx)
+            });
+
+        let mut log = LogLine::new("attempt", Some(vault));
+        log.attempt = Some(attempt);
+
+        let error = match result {
+            Ok(signature) => {
+                log.signature = Some(signature.to_string());
+                log.print();
+                return Ok(signature.to_string());
+            }
+            Err(error) => error,
+        };
+
+        let retry_in = (is_retryable(&error) && attempt < retry.max_attempts).then_some(backoff);
+        log.error = Some(error.to_string());
+        log.retry_in_ms = retry_in.map(|retry_in| retry_in.as_millis() as u64);
+        log.print();
+
+        let Some(retry_in) = retry_in else {
+            return Err(error.into());
+        };
+        std::thread::sleep(retry_in);
+        backoff = (backoff * 2).min(retry.max_backoff);
+    }
+
+    Err(anyhow!("No attempts left"))
+}
+
+/// Expired blockhashes and RPC errors are worth another go. A `VaultLocked` can happen too if
+/// the RPC we polled is a little ahead of the leader. Anything else will just fail again
+fn is_retryable(error: &ClientError) -> bool {
+    match error.get_transaction_error() {
+        None | Some(TransactionError::BlockhashNotFound) => true,
+        Some(TransactionError::InstructionError(_, instruction_error)) => matches!(
+            decode_instruction_error(&instruction_error),
+            Some(DiamondHandsError::VaultLocked)
+        ),
+        Some(_) => false,
+    }
+}
+
+fn log_error(event: &'static str, vault: &Pubkey, error: anyhow::Error) {
+    let mut log = LogLine::new(event, Some(vault));
+    log.error = Some(error.to_string());
+    log.print();
+}
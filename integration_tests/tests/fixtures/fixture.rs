@@ -9,12 +9,16 @@ use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_system_transaction::{create_account, transfer};
 use solana_transaction::{Instruction, Transaction};
-use solcat_diamond_hands_sdk::{accounts::vault::Vault, id, utils::load_account};
+use solcat_diamond_hands_sdk::{
+    accounts::{vault::Vault, vault_extension::VaultExtension},
+    id, mint_stats_address, BasketState, MintStatsState, VaultState,
+};
 use spl_associated_token_account_interface::{
-    address::get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    address::{get_associated_token_address, get_associated_token_address_with_program_id},
+    instruction::create_associated_token_account_idempotent,
 };
 use spl_token_interface::{
-    instruction::{initialize_mint2, transfer_checked},
+    instruction::{initialize_account3, initialize_mint2, transfer_checked},
     state::{Account, Mint},
 };
 use std::fmt::{Debug, Formatter};
@@ -124,8 +128,12 @@ impl TestBuilder {
             .await?
             .ok_or(BanksClientError::ClientError("failed to get token account"))?;
 
-        let account_info = Account::unpack(&account.data)
-            .map_err(|_e| BanksClientError::ClientError("failed to unpack"))?;
+        // Token-2022 accounts can have extensions tacked on, so only unpack the base account
+        let account_info = account
+            .data
+            .get(..Account::LEN)
+            .and_then(|data| Account::unpack_from_slice(data).ok())
+            .ok_or(BanksClientError::ClientError("failed to unpack"))?;
 
         Ok(account_info)
     }
@@ -154,7 +162,25 @@ impl TestBuilder {
     ) -> Result<()> {
         let blockhash = self.context.banks_client.get_latest_blockhash().await?;
 
-        let mint_to_ix = if token_program.eq(&spl_token_interface::id()) {
+        let mint_to_ix = if token_program.eq(&spl_token_2022_interface::id()) {
+            vec![
+                create_associated_token_account_idempotent(
+                    &self.context.payer.pubkey(),
+                    to,
+                    mint,
+                    token_program,
+                ),
+                spl_token_2022_interface::instruction::mint_to(
+                    token_program,
+                    mint,
+                    &get_associated_token_address_with_program_id(to, mint, token_program),
+                    &self.context.payer.pubkey(),
+                    &[],
+                    amount,
+                )
+                .map_err(|_e| BanksClientError::ClientError("failed to mint to"))?,
+            ]
+        } else if token_program.eq(&spl_token_interface::id()) {
             vec![
                 create_associated_token_account_idempotent(
                     &self.context.payer.pubkey(),
@@ -199,6 +225,15 @@ impl TestBuilder {
     }
 
     pub async fn create_mint(&mut self, mint: &Keypair) -> Result<()> {
+        self.create_mint_with_token_program(mint, &spl_token_interface::id())
+            .await
+    }
+
+    pub async fn create_mint_with_token_program(
+        &mut self,
+        mint: &Keypair,
+        token_program: &Pubkey,
+    ) -> Result<()> {
         let blockhash = self.context.banks_client.get_latest_blockhash().await?;
         let rent = self.context.banks_client.get_rent().await?;
         let min_rent = rent.minimum_balance(Mint::LEN);
@@ -209,7 +244,7 @@ impl TestBuilder {
             blockhash,
             min_rent,
             Mint::LEN as u64,
-            &spl_token_interface::id(),
+            token_program,
         );
 
         self.context
@@ -224,13 +259,23 @@ impl TestBuilder {
             .banks_client
             .process_transaction_with_preflight_and_commitment(
                 Transaction::new_signed_with_payer(
-                    &[initialize_mint2(
-                        &spl_token_interface::id(),
-                        &mint.pubkey(),
-                        &self.context.payer.pubkey(),
-                        None,
-                        9,
-                    )?],
+                    &[if token_program.eq(&spl_token_2022_interface::id()) {
+                        spl_token_2022_interface::instruction::initialize_mint2(
+                            token_program,
+                            &mint.pubkey(),
+                            &self.context.payer.pubkey(),
+                            None,
+                            9,
+                        )?
+                    } else {
+                        initialize_mint2(
+                            token_program,
+                            &mint.pubkey(),
+                            &self.context.payer.pubkey(),
+                            None,
+                            9,
+                        )?
+                    }],
                     Some(&self.context.payer.pubkey()),
                     &[&self.context.payer],
                     blockhash,
@@ -242,6 +287,44 @@ impl TestBuilder {
         Ok(())
     }
 
+    /// A plain token account at `token_account`'s address - not an ATA
+    pub async fn create_token_account(
+        &mut self,
+        token_account: &Keypair,
+        mint: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<()> {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let rent = self.context.banks_client.get_rent().await?;
+        let min_rent = rent.minimum_balance(Account::LEN);
+
+        let create_tx = create_account(
+            &self.context.payer,
+            token_account,
+            blockhash,
+            min_rent,
+            Account::LEN as u64,
+            &spl_token_interface::id(),
+        );
+
+        self.context
+            .banks_client
+            .process_transaction_with_preflight_and_commitment(
+                create_tx,
+                CommitmentLevel::Processed,
+            )
+            .await?;
+
+        let ix = initialize_account3(
+            &spl_token_interface::id(),
+            &token_account.pubkey(),
+            mint,
+            owner,
+        )?;
+        self.send_transaction(&[ix], None, &[&self.context.payer.insecure_clone()])
+            .await
+    }
+
     pub async fn get_vault_account(&self, vault: &Pubkey) -> Result<Vault> {
         let vault_account_raw = self
             .context
@@ -251,13 +334,63 @@ impl TestBuilder {
             .ok_or_else(|| anyhow::anyhow!("Vault account not found"))?;
 
         let vault_account = unsafe {
-            load_account::<Vault>(&vault_account_raw.data)
+            Vault::load(&vault_account_raw.data)
                 .map_err(|e| anyhow::anyhow!("Failed to load vault account: {:?}", e))?
         };
 
         Ok(*vault_account)
     }
 
+    pub async fn get_vault_extension(&self, vault: &Pubkey) -> Result<Option<VaultExtension>> {
+        let vault_account_raw = self
+            .context
+            .banks_client
+            .get_account(*vault)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Vault account not found"))?;
+
+        let vault_extension = unsafe {
+            VaultExtension::load(&vault_account_raw.data)
+                .map_err(|e| anyhow::anyhow!("Failed to load vault extension: {:?}", e))?
+        };
+
+        Ok(vault_extension.copied())
+    }
+
+    pub async fn get_vault_state(&self, vault: &Pubkey) -> Result<VaultState> {
+        let vault_account_raw = self
+            .context
+            .banks_client
+            .get_account(*vault)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Vault account not found"))?;
+
+        VaultState::from_account_data(&vault_account_raw.data)
+    }
+
+    pub async fn get_basket_state(&self, basket: &Pubkey) -> Result<BasketState> {
+        let basket_account_raw = self
+            .context
+            .banks_client
+            .get_account(*basket)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Basket account not found"))?;
+
+        BasketState::from_account_data(&basket_account_raw.data)
+    }
+
+    pub async fn get_mint_stats(&self, mint: &Pubkey) -> Result<MintStatsState> {
+        let (mint_stats, _) = mint_stats_address(mint);
+        let mint_stats_account_raw = self
+            .context
+            .banks_client
+            .get_account(mint_stats)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Mint stats account not found"))?;
+
+        MintStatsState::from_account_data(&mint_stats_account_raw.data)
+    }
+
     pub async fn create_ata(&mut self, mint: &Pubkey, owner: &Pubkey) -> Result<()> {
         let blockhash = self.context.banks_client.get_latest_blockhash().await?;
         self.context
@@ -295,6 +428,19 @@ impl TestBuilder {
         Ok(())
     }
 
+    /// Moves `Clock::unix_timestamp` forward without touching the slot
+    pub async fn warp_unix_timestamp_incremental(&mut self, seconds: i64) -> Result<()> {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await?;
+        clock.unix_timestamp = clock.unix_timestamp.checked_add(seconds).unwrap();
+        self.context.set_sysvar(&clock);
+        Ok(())
+    }
+
+    pub async fn get_current_unix_timestamp(&mut self) -> Result<i64> {
+        let clock: Clock = self.context.banks_client.get_sysvar().await?;
+        Ok(clock.unix_timestamp)
+    }
+
     pub async fn get_current_slot(&mut self) -> Result<u64> {
         let clock: Clock = self.context.banks_client.get_sysvar().await?;
         Ok(clock.slot)
@@ -306,6 +452,48 @@ impl TestBuilder {
         payer: Option<&Pubkey>,
         signers: &[&dyn Signer],
     ) -> Result<()> {
+        self.send_transaction_with_logs(instructions, payer, signers)
+            .await
+            .map(|_| ())
+    }
+
+    /// Runs the transaction without committing it and hands back the compute units it used
+    pub async fn simulate_units_consumed(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&dyn Signer],
+    ) -> Result<u64> {
+        let recent_blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            payer.or(Some(&self.context.payer.pubkey())),
+            signers,
+            recent_blockhash,
+        );
+
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .map_err(|e| anyhow!("failed to simulate transaction: {}", e))?;
+        if let Some(Err(e)) = simulation.result {
+            return Err(anyhow!("simulated transaction failed: {}", e));
+        }
+        simulation
+            .simulation_details
+            .map(|details| details.units_consumed)
+            .ok_or_else(|| anyhow!("No simulation details available"))
+    }
+
+    /// Same as `send_transaction`, but hands back the logs - handy for checking events
+    pub async fn send_transaction_with_logs(
+        &mut self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &[&dyn Signer],
+    ) -> Result<Vec<String>> {
         // Fetch latest blockhash
         let recent_blockhash = self
             .context
@@ -386,12 +574,15 @@ impl TestBuilder {
         println!("\n{}", "=".repeat(60));
         println!("TRANSACTION LOGS");
         println!("{}", "=".repeat(60));
-        if let Some(details) = simulation.simulation_details {
-            for log in &details.logs {
-                println!("{}", log);
+        let logs = match simulation.simulation_details {
+            Some(details) => details.logs,
+            None => {
+                println!("No simulation details available");
+                Vec::new()
             }
-        } else {
-            println!("No simulation details available");
+        };
+        for log in &logs {
+            println!("{}", log);
         }
         println!("{}\n", "=".repeat(60));
 
@@ -405,7 +596,7 @@ impl TestBuilder {
         match result {
             Ok(_) => {
                 println!("\n✅ SUCCESS: Transaction processed successfully\n");
-                Ok(())
+                Ok(logs)
             }
             Err(e) => {
                 eprintln!("\n❌ FAILED: Transaction failed");
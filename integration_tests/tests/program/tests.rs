@@ -1,38 +1,70 @@
 #[cfg(test)]
 mod vault_tests {
+    use core::mem::offset_of;
+
     use anyhow::Result;
     use solana_keypair::Keypair;
-    use solana_program::pubkey::Pubkey;
+    use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
     use solana_program_test::tokio;
     use solana_signer::Signer;
     use solcat_diamond_hands_sdk::{
-        accounts::vault::Vault, empty_vault_ix, id, lock_vault_ix, utils::Discriminator,
-        vault_address,
+        accounts::{
+            mint_stats::MintStats,
+            vault::{LockMode, Vault},
+            vault_extension::VaultLabel,
+        },
+        basket::{empty_basket_txs, lock_basket_txs, BasketDeposit, BASKET_MINTS_PER_TRANSACTION},
+        basket_address,
+        compute_budget::{
+            CRANK_EMPTY_COMPUTE_UNITS, DEPOSIT_VAULT_COMPUTE_UNITS, EMPTY_VAULT_COMPUTE_UNITS,
+            EXTEND_LOCK_COMPUTE_UNITS, LOCK_VAULT_COMPUTE_UNITS, TRANSFER_ADMIN_COMPUTE_UNITS,
+            UPDATE_LABEL_COMPUTE_UNITS, WITHDRAW_COMPUTE_UNITS,
+        },
+        crank_empty_ix, deposit_basket_ix, deposit_vault_ix, empty_vault_as_beneficiary_ix,
+        empty_vault_ix,
+        errors::DiamondHandsError,
+        events::{CrankEmptyEvent, EmptyEvent, UpdateLabelEvent},
+        extend_lock_ix, extend_lock_until_ix, find_vault_address, id,
+        instructions::{
+            create_basket::CreateBasketIxData,
+            lock_vault::{LegacyLockVaultIxData, LockVaultIxData},
+        },
+        lock_vault_ix, lock_vault_until_ix, lock_vault_vesting_ix, lock_vault_with_ix_data_ix,
+        mint_stats_address, native_mint,
+        native_sol::{empty_sol_vault_ix, lock_sol_ix},
+        parse_events, token_2022_id, token_address, transfer_admin_ix, update_label_ix,
+        utils::{DataLen, Discriminator},
+        vault_address, withdraw_ix, ComputeBudget, VaultEvent, VaultState,
     };
-    use spl_associated_token_account_interface::address::get_associated_token_address;
 
     use crate::fixtures::fixture::TestBuilder;
 
     pub async fn create_token_and_mint(
         fixture: &mut TestBuilder,
         tokens_to_mint: Option<u64>,
+    ) -> Result<(Pubkey, Pubkey)> {
+        create_token_and_mint_with_program(fixture, tokens_to_mint, &spl_token_interface::id())
+            .await
+    }
+
+    pub async fn create_token_and_mint_with_program(
+        fixture: &mut TestBuilder,
+        tokens_to_mint: Option<u64>,
+        token_program: &Pubkey,
     ) -> Result<(Pubkey, Pubkey)> {
         let mint_keypair = Keypair::new();
         let mint = mint_keypair.pubkey();
         let payer = fixture.context.payer.insecure_clone();
         let tokens_to_mint = tokens_to_mint.unwrap_or(1_000_000);
 
-        fixture.create_mint(&mint_keypair).await?;
         fixture
-            .mint_spl_to(
-                &mint,
-                &payer.pubkey(),
-                tokens_to_mint,
-                &spl_token_interface::id(),
-            )
+            .create_mint_with_token_program(&mint_keypair, token_program)
+            .await?;
+        fixture
+            .mint_spl_to(&mint, &payer.pubkey(), tokens_to_mint, token_program)
             .await?;
 
-        let payer_ata = get_associated_token_address(&payer.pubkey(), &mint);
+        let payer_ata = token_address(&payer.pubkey(), &mint, token_program);
 
         Ok((mint, payer_ata))
     }
@@ -42,22 +74,224 @@ mod vault_tests {
         mint: &Pubkey,
         slots_to_lock: u64,
         tokens_to_lock: Option<u64>,
+    ) -> Result<(Pubkey, Pubkey)> {
+        lock_vault_with_program(
+            fixture,
+            mint,
+            None,
+            &spl_token_interface::id(),
+            slots_to_lock,
+            tokens_to_lock,
+        )
+        .await
+    }
+
+    pub async fn lock_vault_with_index(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        vault_index: u64,
+        slots_to_lock: u64,
+        tokens_to_lock: Option<u64>,
+    ) -> Result<(Pubkey, Pubkey)> {
+        lock_vault_with_program(
+            fixture,
+            mint,
+            Some(vault_index),
+            &spl_token_interface::id(),
+            slots_to_lock,
+            tokens_to_lock,
+        )
+        .await
+    }
+
+    pub async fn lock_vault_with_program(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        vault_index: Option<u64>,
+        token_program: &Pubkey,
+        slots_to_lock: u64,
+        tokens_to_lock: Option<u64>,
+    ) -> Result<(Pubkey, Pubkey)> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ixs = lock_vault_ix(
+            &admin.pubkey(),
+            mint,
+            vault_index,
+            token_program,
+            slots_to_lock,
+            tokens_to_lock,
+        );
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        let (vault, _) = find_vault_address(&admin.pubkey(), mint, vault_index);
+        let vault_ata = token_address(&vault, mint, token_program);
+
+        Ok((vault, vault_ata))
+    }
+
+    pub async fn lock_vault_until(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        unlock_timestamp: i64,
+        tokens_to_lock: Option<u64>,
+    ) -> Result<(Pubkey, Pubkey)> {
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+
+        let ixs = lock_vault_until_ix(
+            &admin.pubkey(),
+            mint,
+            None,
+            &token_program,
+            unlock_timestamp,
+            tokens_to_lock,
+        );
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        let (vault, _) = vault_address(&admin.pubkey(), mint);
+        let vault_ata = token_address(&vault, mint, &token_program);
+
+        Ok((vault, vault_ata))
+    }
+
+    /// A v1 vault locked with whatever options `ix_data` has ( vesting, beneficiary, label )
+    pub async fn lock_vault_with_ix_data(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        ix_data: LockVaultIxData,
     ) -> Result<(Pubkey, Pubkey)> {
         let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
 
-        let ixs = lock_vault_ix(&admin.pubkey(), mint, slots_to_lock, tokens_to_lock);
+        let ixs = lock_vault_with_ix_data_ix(&admin.pubkey(), mint, None, &token_program, ix_data);
         fixture.send_transaction(&ixs, None, &[&admin]).await?;
 
         let (vault, _) = vault_address(&admin.pubkey(), mint);
-        let vault_ata = get_associated_token_address(&vault, mint);
+        let vault_ata = token_address(&vault, mint, &token_program);
 
         Ok((vault, vault_ata))
     }
 
+    pub async fn withdraw(fixture: &mut TestBuilder, mint: &Pubkey) -> Result<()> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ixs = withdraw_ix(&admin.pubkey(), mint, None, &spl_token_interface::id());
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        Ok(())
+    }
+
+    pub async fn deposit_vault(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        tokens_to_deposit: Option<u64>,
+    ) -> Result<()> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ix = deposit_vault_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            mint,
+            None,
+            &spl_token_interface::id(),
+            tokens_to_deposit,
+        );
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+
+        Ok(())
+    }
+
+    pub async fn extend_lock(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        slots_to_add: u64,
+    ) -> Result<()> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ix = extend_lock_ix(&admin.pubkey(), mint, None, slots_to_add);
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+
+        Ok(())
+    }
+
+    pub async fn extend_lock_until(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        unlock_timestamp: i64,
+    ) -> Result<()> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ix = extend_lock_until_ix(&admin.pubkey(), mint, None, unlock_timestamp);
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+
+        Ok(())
+    }
+
+    pub async fn empty_vault_as_beneficiary(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        beneficiary: &Keypair,
+    ) -> Result<()> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ixs = empty_vault_as_beneficiary_ix(
+            &beneficiary.pubkey(),
+            &admin.pubkey(),
+            mint,
+            None,
+            &spl_token_interface::id(),
+        );
+        fixture
+            .send_transaction(&ixs, Some(&beneficiary.pubkey()), &[beneficiary])
+            .await?;
+
+        Ok(())
+    }
+
+    /// `cranker` pays, the tokens go to `authority` - returns the logs for the event
+    pub async fn crank_empty(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        cranker: &Keypair,
+        authority: &Pubkey,
+    ) -> Result<Vec<String>> {
+        let admin = fixture.context.payer.insecure_clone();
+
+        let ixs = crank_empty_ix(
+            &cranker.pubkey(),
+            authority,
+            &admin.pubkey(),
+            mint,
+            None,
+            &spl_token_interface::id(),
+        );
+        fixture
+            .send_transaction_with_logs(&ixs, Some(&cranker.pubkey()), &[cranker])
+            .await
+    }
+
     pub async fn empty_vault(fixture: &mut TestBuilder, mint: &Pubkey) -> Result<()> {
+        empty_vault_with_program(fixture, mint, None, &spl_token_interface::id()).await
+    }
+
+    pub async fn empty_vault_with_index(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        vault_index: u64,
+    ) -> Result<()> {
+        empty_vault_with_program(fixture, mint, Some(vault_index), &spl_token_interface::id()).await
+    }
+
+    pub async fn empty_vault_with_program(
+        fixture: &mut TestBuilder,
+        mint: &Pubkey,
+        vault_index: Option<u64>,
+        token_program: &Pubkey,
+    ) -> Result<()> {
         let admin = fixture.context.payer.insecure_clone();
 
-        let ixs = empty_vault_ix(&admin.pubkey(), mint);
+        let ixs = empty_vault_ix(&admin.pubkey(), mint, vault_index, token_program);
 
         fixture.send_transaction(&ixs, None, &[&admin]).await?;
 
@@ -122,6 +356,36 @@ mod vault_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_vault_state_matches_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let slots_to_lock = 10;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (vault, vault_ata) = lock_vault(&mut fixture, &mint, slots_to_lock, None).await?;
+        let vault_account = fixture.get_vault_account(&vault).await?;
+
+        // The owned SDK version should read exactly the same thing
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.bump, vault_account.bump());
+        assert_eq!(vault_state.start_slot, vault_account.start_slot());
+        assert_eq!(vault_state.slots_locked, vault_account.slots_locked());
+        assert_eq!(vault_state.admin, fixture.context.payer.pubkey());
+        assert_eq!(vault_state.seed_admin, fixture.context.payer.pubkey());
+        assert_eq!(vault_state.mint, mint);
+        assert_eq!(vault_state.vault_token, vault_ata);
+        assert_eq!(vault_state.lock_mode, LockMode::Slot);
+        assert_eq!(vault_state.vault_index, None);
+        assert_eq!(
+            vault_state.unlock_slot(),
+            Some(vault_account.start_slot() + slots_to_lock)
+        );
+        assert!(!vault_state.is_unlockable(vault_account.start_slot()));
+        assert!(vault_state.is_unlockable(vault_account.start_slot() + slots_to_lock));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_lock_and_empty_vault() -> Result<()> {
         let mut fixture = TestBuilder::new().await;
@@ -178,4 +442,1470 @@ mod vault_tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_empty_vault_still_locked_error_code() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        lock_vault(&mut fixture, &mint, 100, None).await?;
+        fixture.warp_slot_incremental(10).await?;
+
+        // Should fail with our own code, not just any error
+        let empty_vault_error = empty_vault(&mut fixture, &mint).await.unwrap_err();
+        assert!(empty_vault_error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::VaultLocked as u32
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_vault_token_2022() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let tokens_to_lock = 400;
+        let slots_to_lock = 10;
+        let token_program = token_2022_id();
+
+        let (mint, admin_ata) =
+            create_token_and_mint_with_program(&mut fixture, Some(tokens_to_mint), &token_program)
+                .await?;
+        let (vault, vault_ata) = lock_vault_with_program(
+            &mut fixture,
+            &mint,
+            None,
+            &token_program,
+            slots_to_lock,
+            Some(tokens_to_lock),
+        )
+        .await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        let vault_ata_account = fixture.get_token_account(&vault_ata).await?;
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint - tokens_to_lock);
+        assert_eq!(vault_ata_account.amount, tokens_to_lock);
+        assert_eq!(*vault_account.vault_token(), vault_ata.to_bytes());
+
+        fixture.warp_slot_incremental(slots_to_lock).await?;
+
+        empty_vault_with_program(&mut fixture, &mint, None, &token_program).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_vault_wrong_token_program() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        // A legacy mint, but we tell the vault it's a Token-2022 mint
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let result =
+            lock_vault_with_program(&mut fixture, &mint, None, &token_2022_id(), 10, None).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_vault_unix_timestamp() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let seconds_to_lock = 60 * 60 * 24;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let unlock_timestamp = fixture.get_current_unix_timestamp().await? + seconds_to_lock;
+        let (vault, vault_ata) =
+            lock_vault_until(&mut fixture, &mint, unlock_timestamp, None).await?;
+
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(vault_account.lock_mode().unwrap(), LockMode::UnixTimestamp);
+        assert_eq!(vault_account.unlock_timestamp(), unlock_timestamp);
+
+        // Plenty of slots, but not enough wall-clock time
+        fixture.warp_slot_incremental(1_000).await?;
+        fixture
+            .warp_unix_timestamp_incremental(seconds_to_lock / 2)
+            .await?;
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+
+        fixture
+            .warp_unix_timestamp_incremental(seconds_to_lock)
+            .await?;
+        empty_vault(&mut fixture, &mint).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_vault_unix_timestamp_in_past() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let unlock_timestamp = fixture.get_current_unix_timestamp().await? - 1;
+        let result = lock_vault_until(&mut fixture, &mint, unlock_timestamp, None).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_vault_legacy_ix_data() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (vault, vault_bump) = vault_address(&admin.pubkey(), &mint);
+
+        // What the web app sends - the original 19 bytes and 7 accounts:
+        // discriminator, vault bump, slots to lock, Some(tokens to lock)
+        let mut data = vec![1, vault_bump];
+        data.extend_from_slice(&10u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&600u64.to_le_bytes());
+        assert_eq!(data.len(), LegacyLockVaultIxData::LEN);
+        assert_eq!(data, unsafe {
+            LegacyLockVaultIxData::new(vault_bump, 10, Some(600)).to_bytes()
+        });
+
+        let mut ixs = lock_vault_ix(&admin.pubkey(), &mint, None, &token_program, 10, None);
+        ixs[1].data = data;
+        ixs[1].accounts.truncate(7);
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        // A slot lock into a v1 vault, nothing newer set
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.lock_mode, LockMode::Slot);
+        assert_eq!(vault_state.slots_locked, 10);
+        assert_eq!(vault_state.vault_index, None);
+        assert_eq!(vault_state.beneficiary, None);
+        assert!(vault_state.label.is_empty());
+        assert_eq!(fixture.get_token_account(&admin_ata).await?.amount, 400);
+
+        fixture.warp_slot_incremental(10).await?;
+        let mut ixs = empty_vault_ix(&admin.pubkey(), &mint, None, &token_program);
+        ixs[1].accounts.truncate(7);
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert_eq!(fixture.get_token_account(&admin_ata).await?.amount, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deposit_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let slots_to_lock = 10;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let (vault, vault_ata) = lock_vault(&mut fixture, &mint, slots_to_lock, Some(600)).await?;
+        let vault_account_before = fixture.get_vault_account(&vault).await?;
+
+        fixture.warp_slot_incremental(5).await?;
+        deposit_vault(&mut fixture, &mint, Some(300)).await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        let vault_ata_account = fixture.get_token_account(&vault_ata).await?;
+        assert_eq!(admin_ata_account.amount, 100);
+        assert_eq!(vault_ata_account.amount, 900);
+
+        // The lock is untouched by the deposit
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(
+            vault_account.start_slot(),
+            vault_account_before.start_slot()
+        );
+        assert_eq!(vault_account.slots_locked(), slots_to_lock);
+
+        // Still locked, deposits don't give you a way out
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+
+        fixture.warp_slot_incremental(slots_to_lock).await?;
+        empty_vault(&mut fixture, &mint).await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deposit_vault_no_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        assert!(deposit_vault(&mut fixture, &mint, Some(100)).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deposit_vault_nothing_to_deposit() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        // Everything is already locked, so there is nothing left for "all of it" either
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        lock_vault(&mut fixture, &mint, 10, None).await?;
+
+        for tokens_to_deposit in [Some(0), None] {
+            let error = deposit_vault(&mut fixture, &mint, tokens_to_deposit)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains(&format!(
+                "custom program error: {:#x}",
+                DiamondHandsError::NothingToDeposit as u32
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extend_lock() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let slots_to_lock = 10;
+        let slots_to_add = 20;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let (vault, _) = lock_vault(&mut fixture, &mint, slots_to_lock, None).await?;
+
+        extend_lock(&mut fixture, &mint, slots_to_add).await?;
+
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(vault_account.slots_locked(), slots_to_lock + slots_to_add);
+
+        // The original lock has passed, but the extension has not
+        fixture.warp_slot_incremental(slots_to_lock + 5).await?;
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+
+        fixture.warp_slot_incremental(slots_to_add).await?;
+        empty_vault(&mut fixture, &mint).await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extend_lock_zero_slots() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        lock_vault(&mut fixture, &mint, 10, None).await?;
+
+        let error = extend_lock(&mut fixture, &mint, 0).await.unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::LockNotExtended as u32
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extend_lock_unix_timestamp() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let seconds_to_lock = 60 * 60 * 24;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let unlock_timestamp = fixture.get_current_unix_timestamp().await? + seconds_to_lock;
+        let (vault, _) = lock_vault_until(&mut fixture, &mint, unlock_timestamp, None).await?;
+
+        // Moving the unlock earlier, or leaving it where it is, is not allowed
+        for not_later in [unlock_timestamp - 1, unlock_timestamp] {
+            let error = extend_lock_until(&mut fixture, &mint, not_later)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains(&format!(
+                "custom program error: {:#x}",
+                DiamondHandsError::LockNotExtended as u32
+            )));
+        }
+
+        let new_unlock_timestamp = unlock_timestamp + seconds_to_lock;
+        extend_lock_until(&mut fixture, &mint, new_unlock_timestamp).await?;
+
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(vault_account.unlock_timestamp(), new_unlock_timestamp);
+
+        fixture
+            .warp_unix_timestamp_incremental(seconds_to_lock + 1)
+            .await?;
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+
+        fixture
+            .warp_unix_timestamp_incremental(seconds_to_lock)
+            .await?;
+        empty_vault(&mut fixture, &mint).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_indexed_vaults() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+
+        // A v1 vault and two indexed vaults for the same admin and mint, each unlocking
+        // at a different time
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let (v1_vault, _) = lock_vault(&mut fixture, &mint, 10, Some(250)).await?;
+        let (vault_0, _) = lock_vault_with_index(&mut fixture, &mint, 0, 20, Some(250)).await?;
+        let (vault_1, vault_1_ata) =
+            lock_vault_with_index(&mut fixture, &mint, 1, 30, Some(250)).await?;
+
+        assert_ne!(v1_vault, vault_0);
+        assert_ne!(vault_0, vault_1);
+
+        let v1_vault_account = fixture.get_vault_account(&v1_vault).await?;
+        assert_eq!(
+            v1_vault_account.discriminator(),
+            Some(&Vault::DISCRIMINATOR)
+        );
+        assert_eq!(v1_vault_account.vault_index(), None);
+
+        let vault_1_account = fixture.get_vault_account(&vault_1).await?;
+        assert_eq!(vault_1_account.vault_index(), Some(1));
+        assert_eq!(vault_1_account.slots_locked(), 30);
+
+        // The same index can't be locked twice
+        assert!(lock_vault_with_index(&mut fixture, &mint, 1, 30, Some(1))
+            .await
+            .is_err());
+
+        fixture.warp_slot_incremental(20).await?;
+        empty_vault(&mut fixture, &mint).await?;
+        empty_vault_with_index(&mut fixture, &mint, 0).await?;
+        assert!(empty_vault_with_index(&mut fixture, &mint, 1)
+            .await
+            .is_err());
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, 750);
+        let vault_1_ata_account = fixture.get_token_account(&vault_1_ata).await?;
+        assert_eq!(vault_1_ata_account.amount, 250);
+
+        fixture.warp_slot_incremental(10).await?;
+        empty_vault_with_index(&mut fixture, &mint, 1).await?;
+
+        assert!(fixture.get_vault_account(&vault_1).await.is_err());
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_withdraw_vesting_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let cliff_slots = 10;
+        let slots_to_vest = 100;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let ix_data = LockVaultIxData::new_linear_vesting(0, cliff_slots, slots_to_vest, None);
+        let (vault, vault_ata) = lock_vault_with_ix_data(&mut fixture, &mint, ix_data).await?;
+
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(vault_account.lock_mode().unwrap(), LockMode::LinearVesting);
+        assert_eq!(vault_account.slots_locked(), slots_to_vest);
+        let vault_extension = fixture.get_vault_extension(&vault).await?.unwrap();
+        assert_eq!(vault_extension.cliff_slots(), cliff_slots);
+        assert_eq!(vault_extension.tokens_claimed(), 0);
+        let start_slot = vault_account.start_slot();
+
+        // Before the cliff nothing has vested
+        fixture.warp_to_slot(start_slot + 5).await?;
+        assert!(withdraw(&mut fixture, &mint).await.is_err());
+
+        // A quarter of the way through
+        fixture.warp_to_slot(start_slot + 25).await?;
+        withdraw(&mut fixture, &mint).await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, 250);
+        let vault_extension = fixture.get_vault_extension(&vault).await?.unwrap();
+        assert_eq!(vault_extension.tokens_claimed(), 250);
+
+        // Only what vested since the last withdraw comes out
+        fixture.warp_to_slot(start_slot + 60).await?;
+        withdraw(&mut fixture, &mint).await?;
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, 600);
+        let vault_ata_account = fixture.get_token_account(&vault_ata).await?;
+        assert_eq!(vault_ata_account.amount, 400);
+
+        // Fully vested, the vault is drained and closed
+        fixture.warp_to_slot(start_slot + slots_to_vest).await?;
+        withdraw(&mut fixture, &mint).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_vault_vesting_cliff_after_end() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let ix_data = LockVaultIxData::new_linear_vesting(0, 101, 100, None);
+        assert!(lock_vault_with_ix_data(&mut fixture, &mint, ix_data)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_slot_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+
+        // Withdraw is only for vesting vaults, even once the lock has passed
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        lock_vault(&mut fixture, &mint, 10, None).await?;
+        fixture.warp_slot_incremental(20).await?;
+
+        assert!(withdraw(&mut fixture, &mint).await.is_err());
+        empty_vault(&mut fixture, &mint).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_vault_for_beneficiary() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let tokens_to_mint = 1000;
+        let beneficiary = Keypair::new();
+        fixture
+            .airdrop(&beneficiary.pubkey(), 1_000_000_000)
+            .await?;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let ix_data =
+            LockVaultIxData::new(0, 10, None).with_beneficiary(beneficiary.pubkey().to_bytes());
+        let (vault, vault_ata) = lock_vault_with_ix_data(&mut fixture, &mint, ix_data).await?;
+
+        let vault_extension = fixture.get_vault_extension(&vault).await?.unwrap();
+        assert_eq!(
+            vault_extension.beneficiary(),
+            Some(&beneficiary.pubkey().to_bytes())
+        );
+
+        // The admin funded it, but the beneficiary can't be rugged - even after it unlocks
+        fixture.warp_slot_incremental(20).await?;
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+
+        empty_vault_as_beneficiary(&mut fixture, &mint, &beneficiary).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+
+        let beneficiary_ata =
+            token_address(&beneficiary.pubkey(), &mint, &spl_token_interface::id());
+        let beneficiary_ata_account = fixture.get_token_account(&beneficiary_ata).await?;
+        assert_eq!(beneficiary_ata_account.amount, tokens_to_mint);
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_crank_empty() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.pubkey();
+        let tokens_to_mint = 1000;
+        let cranker = Keypair::new();
+        fixture.airdrop(&cranker.pubkey(), 1_000_000_000).await?;
+
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(tokens_to_mint)).await?;
+        let (vault, vault_ata) = lock_vault(&mut fixture, &mint, 10, None).await?;
+
+        fixture.warp_slot_incremental(20).await?;
+        let logs = crank_empty(&mut fixture, &mint, &cranker, &admin).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+
+        // Everything went back to the admin, the cranker got nothing
+        let admin_ata_account = fixture.get_token_account(&admin_ata).await?;
+        assert_eq!(admin_ata_account.amount, tokens_to_mint);
+        let cranker_ata = token_address(&cranker.pubkey(), &mint, &spl_token_interface::id());
+        assert!(fixture.get_token_account(&cranker_ata).await.is_err());
+
+        assert_eq!(
+            parse_events(&logs),
+            vec![VaultEvent::CrankEmpty(CrankEmptyEvent::new(
+                &vault.to_bytes(),
+                &cranker.pubkey().to_bytes(),
+                &admin.to_bytes(),
+                &mint.to_bytes(),
+                tokens_to_mint,
+            ))]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_crank_empty_error_still_locked() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.pubkey();
+        let cranker = Keypair::new();
+        fixture.airdrop(&cranker.pubkey(), 1_000_000_000).await?;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (_, vault_ata) = lock_vault(&mut fixture, &mint, 100, None).await?;
+
+        fixture.warp_slot_incremental(10).await?;
+        let crank_error = crank_empty(&mut fixture, &mint, &cranker, &admin)
+            .await
+            .unwrap_err();
+        assert!(crank_error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::VaultLocked as u32
+        )));
+
+        let vault_ata_account = fixture.get_token_account(&vault_ata).await?;
+        assert_eq!(vault_ata_account.amount, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_crank_empty_only_to_withdraw_authority() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.pubkey();
+        let beneficiary = Keypair::new();
+        let cranker = Keypair::new();
+        fixture.airdrop(&cranker.pubkey(), 1_000_000_000).await?;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let ix_data =
+            LockVaultIxData::new(0, 10, None).with_beneficiary(beneficiary.pubkey().to_bytes());
+        let (vault, _) = lock_vault_with_ix_data(&mut fixture, &mint, ix_data).await?;
+        fixture.warp_slot_incremental(20).await?;
+
+        // The cranker can't send the tokens to themselves, or back to the admin that funded it
+        for authority in [cranker.pubkey(), admin] {
+            let crank_error = crank_empty(&mut fixture, &mint, &cranker, &authority)
+                .await
+                .unwrap_err();
+            assert!(crank_error.to_string().contains(&format!(
+                "custom program error: {:#x}",
+                DiamondHandsError::InvalidWithdrawAuthority as u32
+            )));
+        }
+
+        // Or to a token account the beneficiary owns that isn't their ATA
+        let other_token = Keypair::new();
+        fixture
+            .create_token_account(&other_token, &mint, &beneficiary.pubkey())
+            .await?;
+        let mut ixs = crank_empty_ix(
+            &cranker.pubkey(),
+            &beneficiary.pubkey(),
+            &admin,
+            &mint,
+            None,
+            &spl_token_interface::id(),
+        );
+        ixs[1].accounts[4].pubkey = other_token.pubkey();
+        let crank_error = fixture
+            .send_transaction(&ixs[1..], Some(&cranker.pubkey()), &[&cranker])
+            .await
+            .unwrap_err();
+        assert!(crank_error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::AuthorityTokenNotAssociated as u32
+        )));
+
+        crank_empty(&mut fixture, &mint, &cranker, &beneficiary.pubkey()).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        let beneficiary_ata =
+            token_address(&beneficiary.pubkey(), &mint, &spl_token_interface::id());
+        let beneficiary_ata_account = fixture.get_token_account(&beneficiary_ata).await?;
+        assert_eq!(beneficiary_ata_account.amount, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transfer_admin() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let new_admin = Keypair::new();
+        fixture.airdrop(&new_admin.pubkey(), 1_000_000_000).await?;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (vault, _) = lock_vault(&mut fixture, &mint, 10, None).await?;
+
+        // Both admins have to sign
+        let ix = transfer_admin_ix(
+            &admin.pubkey(),
+            &new_admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+        );
+        let mut unsigned_ix = ix.clone();
+        unsigned_ix.accounts[2].is_signer = false;
+        assert!(fixture
+            .send_transaction(&[unsigned_ix], None, &[&admin])
+            .await
+            .is_err());
+
+        // Transferring to yourself doesn't do anything, so it is rejected
+        let self_ix = transfer_admin_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+        );
+        let error = fixture
+            .send_transaction(&[self_ix], None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::AdminUnchanged as u32
+        )));
+
+        fixture
+            .send_transaction(&[ix], None, &[&admin, &new_admin])
+            .await?;
+
+        // Same PDA, new admin - the original admin is kept around for the seeds
+        let vault_account = fixture.get_vault_account(&vault).await?;
+        assert_eq!(vault_account.admin(), &new_admin.pubkey().to_bytes());
+        let vault_extension = fixture.get_vault_extension(&vault).await?.unwrap();
+        assert_eq!(
+            vault_extension.seed_admin(),
+            Some(&admin.pubkey().to_bytes())
+        );
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.admin, new_admin.pubkey());
+        assert_eq!(vault_state.seed_admin, admin.pubkey());
+        assert_eq!(vault_state.withdraw_authority(), new_admin.pubkey());
+
+        // The old admin is locked out, the new admin can empty once it unlocks
+        fixture.warp_slot_incremental(20).await?;
+        assert!(empty_vault(&mut fixture, &mint).await.is_err());
+        empty_vault_as_beneficiary(&mut fixture, &mint, &new_admin).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        let new_admin_ata = token_address(&new_admin.pubkey(), &mint, &spl_token_interface::id());
+        let new_admin_ata_account = fixture.get_token_account(&new_admin_ata).await?;
+        assert_eq!(new_admin_ata_account.amount, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deposit_after_transfer_admin() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let new_admin = Keypair::new();
+        let token_program = spl_token_interface::id();
+        fixture.airdrop(&new_admin.pubkey(), 1_000_000_000).await?;
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (vault, vault_token) = lock_vault(&mut fixture, &mint, 10, Some(600)).await?;
+        let ix = transfer_admin_ix(
+            &admin.pubkey(),
+            &new_admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+        );
+        fixture
+            .send_transaction(&[ix], None, &[&admin, &new_admin])
+            .await?;
+
+        // The vault is still at the original admin's PDA, the new admin deposits into it
+        fixture
+            .mint_spl_to(&mint, &new_admin.pubkey(), 100, &token_program)
+            .await?;
+        let ix = deposit_vault_ix(
+            &new_admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+            &token_program,
+            Some(100),
+        );
+        fixture
+            .send_transaction(&[ix], Some(&new_admin.pubkey()), &[&new_admin])
+            .await?;
+
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.admin, new_admin.pubkey());
+        let vault_token_account = fixture.get_token_account(&vault_token).await?;
+        assert_eq!(vault_token_account.amount, 700);
+
+        Ok(())
+    }
+
+    /// What `verify` reads on-chain for a proof-of-lock - the vault account if it is still there,
+    /// and the vault token balance
+    async fn read_for_proof(
+        fixture: &TestBuilder,
+        vault: &Pubkey,
+        vault_token: &Pubkey,
+    ) -> Result<(Option<Vec<u8>>, u64)> {
+        let banks_client = &fixture.context.banks_client;
+        let vault_data = banks_client
+            .get_account(*vault)
+            .await?
+            .map(|account| account.data);
+        let vault_token_balance = match banks_client.get_account(*vault_token).await? {
+            Some(account) => spl_token_interface::state::Account::unpack(&account.data)?.amount,
+            None => 0,
+        };
+
+        Ok((vault_data, vault_token_balance))
+    }
+
+    #[tokio::test]
+    async fn test_proof_of_lock_still_locked() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let (vault, vault_token) = lock_vault(&mut fixture, &mint, 10, Some(600)).await?;
+        let certified = fixture.get_vault_state(&vault).await?;
+        let (_, certified_balance) = read_for_proof(&fixture, &vault, &vault_token).await?;
+        assert_eq!(certified_balance, 600);
+
+        // A deposit only adds to what was certified
+        let ix = deposit_vault_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+            &token_program,
+            Some(100),
+        );
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+        let (vault_data, balance) = read_for_proof(&fixture, &vault, &vault_token).await?;
+        let state = VaultState::check_still_locked(
+            &certified,
+            certified_balance,
+            vault_data.as_deref(),
+            balance,
+        )?;
+        assert_eq!(state.slots_locked, certified.slots_locked);
+        assert_eq!(balance, 700);
+
+        // Once it is emptied the certificate doesn't hold anymore
+        fixture.warp_slot_incremental(10).await?;
+        empty_vault(&mut fixture, &mint).await?;
+        let (vault_data, balance) = read_for_proof(&fixture, &vault, &vault_token).await?;
+        assert!(VaultState::check_still_locked(
+            &certified,
+            certified_balance,
+            vault_data.as_deref(),
+            balance,
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vault_label() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let ix_data = LockVaultIxData::new(0, 10, Some(400))
+            .with_label(VaultLabel::new("team allocation".as_bytes()).unwrap());
+        let ixs = lock_vault_with_ix_data_ix(&admin.pubkey(), &mint, None, &token_program, ix_data);
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        let (vault, _) = vault_address(&admin.pubkey(), &mint);
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.label.as_str(), Some("team allocation"));
+
+        // Only the admin can rename it
+        let other = Keypair::new();
+        fixture.airdrop(&other.pubkey(), 1_000_000_000).await?;
+        let label = VaultLabel::new("marketing reserve".as_bytes()).unwrap();
+        let ix = update_label_ix(&other.pubkey(), &admin.pubkey(), &mint, None, label);
+        assert!(fixture
+            .send_transaction(&[ix], Some(&other.pubkey()), &[&other])
+            .await
+            .is_err());
+
+        let ix = update_label_ix(&admin.pubkey(), &admin.pubkey(), &mint, None, label);
+        let logs = fixture
+            .send_transaction_with_logs(&[ix], None, &[&admin])
+            .await?;
+        assert_eq!(
+            parse_events(&logs),
+            vec![VaultEvent::UpdateLabel(UpdateLabelEvent::new(
+                &vault.to_bytes(),
+                &admin.pubkey().to_bytes(),
+                label,
+            ))]
+        );
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert_eq!(vault_state.label.as_str(), Some("marketing reserve"));
+
+        // Garbage after the length is refused, even though the label itself is fine
+        let mut ix = update_label_ix(&admin.pubkey(), &admin.pubkey(), &mint, None, label);
+        *ix.data.last_mut().unwrap() = b'x';
+        let error = fixture
+            .send_transaction(&[ix], None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::InvalidLabel as u32
+        )));
+
+        // An empty label clears it, the lock is untouched
+        let ix = update_label_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+            VaultLabel::default(),
+        );
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+        let cleared_state = fixture.get_vault_state(&vault).await?;
+        assert!(cleared_state.label.is_empty());
+        assert_eq!(cleared_state.slots_locked, vault_state.slots_locked);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_events() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        let ixs = lock_vault_ix(
+            &admin.pubkey(),
+            &mint,
+            None,
+            &spl_token_interface::id(),
+            10,
+            Some(400),
+        );
+        let logs = fixture
+            .send_transaction_with_logs(&ixs, None, &[&admin])
+            .await?;
+
+        let (vault, _) = vault_address(&admin.pubkey(), &mint);
+        let events = parse_events(&logs);
+        let [VaultEvent::Lock(lock_event)] = events.as_slice() else {
+            panic!("Expected one lock event, got {:?}", events);
+        };
+        assert_eq!(lock_event.vault, vault.to_bytes());
+        assert_eq!(lock_event.admin, admin.pubkey().to_bytes());
+        assert_eq!(lock_event.mint, mint.to_bytes());
+        assert_eq!(lock_event.tokens_locked.get(), 400);
+        assert_eq!(lock_event.slots_locked.get(), 10);
+        assert_eq!(lock_event.lock_mode, LockMode::Slot as u8);
+
+        fixture.warp_slot_incremental(20).await?;
+        let ixs = empty_vault_ix(&admin.pubkey(), &mint, None, &spl_token_interface::id());
+        let logs = fixture
+            .send_transaction_with_logs(&ixs, None, &[&admin])
+            .await?;
+
+        // The token program's logs are in there too, only our event comes out
+        assert_eq!(
+            parse_events(&logs),
+            vec![VaultEvent::Empty(EmptyEvent::new(
+                &vault.to_bytes(),
+                &admin.pubkey().to_bytes(),
+                &mint.to_bytes(),
+                400,
+            ))]
+        );
+
+        Ok(())
+    }
+
+    /// Sends `ixs` with `unit_limit` in front, after logging what they actually use. The
+    /// `compute_budget` limits are the Token-2022 numbers logged here plus a 50% margin
+    pub async fn send_within_budget(
+        fixture: &mut TestBuilder,
+        name: &str,
+        unit_limit: u32,
+        ixs: impl IntoIterator<Item = Instruction>,
+        signers: &[&dyn Signer],
+    ) -> Result<()> {
+        let ixs = ComputeBudget::new()
+            .with_unit_limit(unit_limit)
+            .with_unit_price(1_000)
+            .prepend(ixs);
+
+        let units_consumed = fixture.simulate_units_consumed(&ixs, None, signers).await?;
+        println!(
+            "{}: {} CU of {}, with a 50% margin it would be {}",
+            name,
+            units_consumed,
+            unit_limit,
+            units_consumed * 3 / 2
+        );
+        assert!(units_consumed <= unit_limit as u64);
+
+        fixture.send_transaction(&ixs, None, signers).await
+    }
+
+    #[tokio::test]
+    async fn test_compute_budget_limits() -> Result<()> {
+        // The SDK limits have to be enough for both token programs, Token-2022 is the pricier one
+        for token_program in [spl_token_interface::id(), token_2022_id()] {
+            let mut fixture = TestBuilder::new().await;
+            let admin = fixture.context.payer.insecure_clone();
+            let new_admin = Keypair::new();
+            fixture.airdrop(&new_admin.pubkey(), 1_000_000_000).await?;
+            let slots_to_lock = 10;
+            println!("Token program {}", token_program);
+
+            let (mint, admin_ata) =
+                create_token_and_mint_with_program(&mut fixture, Some(1000), &token_program)
+                    .await?;
+            let (vault, _) = find_vault_address(&admin.pubkey(), &mint, None);
+            let vault_ata = token_address(&vault, &mint, &token_program);
+            let (vesting_vault, _) = find_vault_address(&admin.pubkey(), &mint, Some(1));
+
+            // A slot lock that gets emptied
+            let ixs = lock_vault_ix(
+                &admin.pubkey(),
+                &mint,
+                None,
+                &token_program,
+                slots_to_lock,
+                Some(400),
+            );
+            send_within_budget(
+                &mut fixture,
+                "lock",
+                LOCK_VAULT_COMPUTE_UNITS,
+                ixs,
+                &[&admin],
+            )
+            .await?;
+
+            let ix = deposit_vault_ix(
+                &admin.pubkey(),
+                &admin.pubkey(),
+                &mint,
+                None,
+                &token_program,
+                Some(100),
+            );
+            send_within_budget(
+                &mut fixture,
+                "deposit",
+                DEPOSIT_VAULT_COMPUTE_UNITS,
+                [ix],
+                &[&admin],
+            )
+            .await?;
+
+            let ix = extend_lock_ix(&admin.pubkey(), &mint, None, 5);
+            send_within_budget(
+                &mut fixture,
+                "extend lock",
+                EXTEND_LOCK_COMPUTE_UNITS,
+                [ix],
+                &[&admin],
+            )
+            .await?;
+
+            let label = VaultLabel::new("compute budget".as_bytes()).unwrap();
+            let ix = update_label_ix(&admin.pubkey(), &admin.pubkey(), &mint, None, label);
+            send_within_budget(
+                &mut fixture,
+                "update label",
+                UPDATE_LABEL_COMPUTE_UNITS,
+                [ix],
+                &[&admin],
+            )
+            .await?;
+
+            // A vesting vault that is withdrawn from, transferred and then cranked
+            let ixs = lock_vault_vesting_ix(
+                &admin.pubkey(),
+                &mint,
+                Some(1),
+                &token_program,
+                0,
+                slots_to_lock * 2,
+                Some(300),
+            );
+            send_within_budget(
+                &mut fixture,
+                "lock vesting",
+                LOCK_VAULT_COMPUTE_UNITS,
+                ixs,
+                &[&admin],
+            )
+            .await?;
+
+            fixture.warp_slot_incremental(slots_to_lock).await?;
+
+            let ixs = withdraw_ix(&admin.pubkey(), &mint, Some(1), &token_program);
+            send_within_budget(
+                &mut fixture,
+                "withdraw",
+                WITHDRAW_COMPUTE_UNITS,
+                ixs,
+                &[&admin],
+            )
+            .await?;
+
+            let ix = transfer_admin_ix(
+                &admin.pubkey(),
+                &new_admin.pubkey(),
+                &admin.pubkey(),
+                &mint,
+                Some(1),
+            );
+            send_within_budget(
+                &mut fixture,
+                "transfer admin",
+                TRANSFER_ADMIN_COMPUTE_UNITS,
+                [ix],
+                &[&admin, &new_admin],
+            )
+            .await?;
+
+            fixture.warp_slot_incremental(slots_to_lock * 2).await?;
+
+            let ixs = empty_vault_ix(&admin.pubkey(), &mint, None, &token_program);
+            send_within_budget(
+                &mut fixture,
+                "empty",
+                EMPTY_VAULT_COMPUTE_UNITS,
+                ixs,
+                &[&admin],
+            )
+            .await?;
+
+            let ixs = crank_empty_ix(
+                &admin.pubkey(),
+                &new_admin.pubkey(),
+                &admin.pubkey(),
+                &mint,
+                Some(1),
+                &token_program,
+            );
+            send_within_budget(
+                &mut fixture,
+                "crank empty",
+                CRANK_EMPTY_COMPUTE_UNITS,
+                ixs,
+                &[&admin],
+            )
+            .await?;
+
+            assert!(fixture.get_vault_account(&vault).await.is_err());
+            assert!(fixture.get_vault_account(&vesting_vault).await.is_err());
+            assert!(fixture.get_token_account(&vault_ata).await.is_err());
+            let new_admin_ata = token_address(&new_admin.pubkey(), &mint, &token_program);
+            let new_admin_tokens = fixture.get_token_account(&new_admin_ata).await?.amount;
+            let admin_tokens = fixture.get_token_account(&admin_ata).await?.amount;
+            assert_eq!(admin_tokens + new_admin_tokens, 1000);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_sol_vault() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let lamports = 1_500_000_000;
+        let slots_to_lock = 10;
+        let token_program = spl_token_interface::id();
+
+        let ixs = lock_sol_ix(&admin.pubkey(), None, slots_to_lock, lamports);
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        let (vault, _) = vault_address(&admin.pubkey(), &native_mint());
+        let vault_ata = token_address(&vault, &native_mint(), &token_program);
+        let admin_ata = token_address(&admin.pubkey(), &native_mint(), &token_program);
+        let vault_state = fixture.get_vault_state(&vault).await?;
+        assert!(vault_state.is_native_sol());
+        assert_eq!(vault_state.mint_decimals, 9);
+        assert_eq!(
+            fixture.get_token_account(&vault_ata).await?.amount,
+            lamports
+        );
+        // Everything that was wrapped went into the vault
+        assert_eq!(fixture.get_token_account(&admin_ata).await?.amount, 0);
+
+        fixture.warp_slot_incremental(slots_to_lock).await?;
+
+        let balance_before = fixture
+            .context
+            .banks_client
+            .get_balance(admin.pubkey())
+            .await?;
+        let ixs = empty_sol_vault_ix(&admin.pubkey(), &admin.pubkey(), None);
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert!(fixture.get_token_account(&vault_ata).await.is_err());
+        // Unwrapped, the SOL is back in the wallet along with the rent ( less the fee )
+        assert!(fixture.get_token_account(&admin_ata).await.is_err());
+        let balance_after = fixture
+            .context
+            .banks_client
+            .get_balance(admin.pubkey())
+            .await?;
+        assert!(balance_after > balance_before + lamports);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_empty_basket() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let basket_index = 2;
+        let slots_to_lock = 10;
+
+        // One more mint than fits in a transaction, so emptying takes two
+        let mut deposits = Vec::new();
+        for _ in 0..BASKET_MINTS_PER_TRANSACTION + 1 {
+            let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+            deposits.push(BasketDeposit {
+                mint,
+                token_program,
+                tokens_to_deposit: Some(400),
+            });
+        }
+
+        let txs = lock_basket_txs(
+            &admin.pubkey(),
+            CreateBasketIxData::new(0, basket_index, slots_to_lock),
+            &deposits,
+        );
+        assert_eq!(txs.len(), 2);
+        for tx in &txs {
+            fixture
+                .send_transaction(&tx.instructions, None, &[&admin])
+                .await?;
+        }
+
+        // Depositing a mint that is already in the basket tops it up
+        let ixs = deposit_basket_ix(
+            &admin.pubkey(),
+            basket_index,
+            &deposits[0].mint,
+            &token_program,
+            Some(100),
+        );
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        // Same as a vault, a deposit has to actually deposit something
+        let ixs = deposit_basket_ix(
+            &admin.pubkey(),
+            basket_index,
+            &deposits[0].mint,
+            &token_program,
+            Some(0),
+        );
+        let error = fixture
+            .send_transaction(&ixs, None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::NothingToDeposit as u32
+        )));
+
+        let (basket, _) = basket_address(&admin.pubkey(), basket_index);
+        let basket_state = fixture.get_basket_state(&basket).await?;
+        assert_eq!(basket_state.basket_index, basket_index);
+        assert_eq!(basket_state.slots_locked, slots_to_lock);
+        assert_eq!(basket_state.entries.len(), deposits.len());
+        for (entry, deposit) in basket_state.entries.iter().zip(&deposits) {
+            assert_eq!(entry.mint, deposit.mint);
+            assert_eq!(
+                entry.vault_token,
+                token_address(&basket, &deposit.mint, &token_program)
+            );
+        }
+        assert_eq!(
+            fixture
+                .get_token_account(&basket_state.entries[0].vault_token)
+                .await?
+                .amount,
+            500
+        );
+
+        let mints = deposits
+            .iter()
+            .map(|deposit| (deposit.mint, deposit.token_program))
+            .collect::<Vec<_>>();
+
+        // Still locked
+        let txs = empty_basket_txs(&admin.pubkey(), basket_index, &mints);
+        let error = fixture
+            .send_transaction(&txs[0].instructions, None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::VaultLocked as u32
+        )));
+
+        fixture.warp_slot_incremental(slots_to_lock).await?;
+
+        // The first transaction takes out the first few mints, the rest are still in there
+        fixture
+            .send_transaction(&txs[0].instructions, None, &[&admin])
+            .await?;
+        let basket_state = fixture.get_basket_state(&basket).await?;
+        assert_eq!(basket_state.entries.len(), 1);
+        assert_eq!(basket_state.entries[0].mint, deposits[4].mint);
+
+        // The second one empties the last mint and closes the basket
+        fixture
+            .send_transaction(&txs[1].instructions, None, &[&admin])
+            .await?;
+        assert!(fixture.get_basket_state(&basket).await.is_err());
+
+        for deposit in &deposits {
+            let admin_ata = token_address(&admin.pubkey(), &deposit.mint, &token_program);
+            assert_eq!(fixture.get_token_account(&admin_ata).await?.amount, 1000);
+            let vault_ata = token_address(&basket, &deposit.mint, &token_program);
+            assert!(fixture.get_token_account(&vault_ata).await.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mint_stats() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+
+        // The first lock creates the stats, the second one just adds to them
+        lock_vault_with_index(&mut fixture, &mint, 1, 100, Some(300)).await?;
+        lock_vault_with_index(&mut fixture, &mint, 2, 500, Some(100)).await?;
+
+        let stats = fixture.get_mint_stats(&mint).await?;
+        assert_eq!(stats.mint, mint);
+        assert_eq!(stats.tokens_locked, 400);
+        assert_eq!(stats.active_vaults, 2);
+        assert_eq!(stats.lifetime_locks, 2);
+        // ( 300 * 100 + 100 * 500 ) / 400
+        assert_eq!(stats.average_lock_slots(), 200);
+
+        // Deposits count, but don't change the average
+        let ix = deposit_vault_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            Some(1),
+            &token_program,
+            Some(50),
+        );
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+        let stats = fixture.get_mint_stats(&mint).await?;
+        assert_eq!(stats.tokens_locked, 450);
+        assert_eq!(stats.average_lock_slots(), 200);
+
+        // Emptying takes the vault and everything in it off, the lifetime numbers stay
+        fixture.warp_slot_incremental(100).await?;
+        empty_vault_with_index(&mut fixture, &mint, 1).await?;
+        let stats = fixture.get_mint_stats(&mint).await?;
+        assert_eq!(stats.tokens_locked, 100);
+        assert_eq!(stats.active_vaults, 1);
+        assert_eq!(stats.lifetime_locks, 2);
+
+        // The wrong stats account is refused
+        let (other_mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+        lock_vault(&mut fixture, &other_mint, 10, None).await?;
+        let mut ix = deposit_vault_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            Some(2),
+            &token_program,
+            Some(1),
+        );
+        ix.accounts[7].pubkey = mint_stats_address(&other_mint).0;
+        let error = fixture
+            .send_transaction(&[ix], None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::InvalidMintStats as u32
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mint_stats_canonical_bump() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+
+        // A lower bump that is also off curve is a perfectly good PDA, just not the canonical one
+        let (_, canonical_bump) = mint_stats_address(&mint);
+        let (other_stats, other_bump) = (0..canonical_bump)
+            .rev()
+            .find_map(|bump| {
+                Pubkey::create_program_address(&[MintStats::SEED, mint.as_ref(), &[bump]], &id())
+                    .ok()
+                    .map(|pda| (pda, bump))
+            })
+            .unwrap();
+
+        let mut ixs = lock_vault_with_ix_data_ix(
+            &admin.pubkey(),
+            &mint,
+            None,
+            &token_program,
+            LockVaultIxData::new(0, 10, Some(100)),
+        );
+        ixs[1].data[offset_of!(LockVaultIxData, mint_stats_bump)] = other_bump;
+        ixs[1].accounts[7].pubkey = other_stats;
+        let error = fixture
+            .send_transaction(&ixs, None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "custom program error: {:#x}",
+            DiamondHandsError::InvalidMintStats as u32
+        )));
+
+        lock_vault(&mut fixture, &mint, 10, Some(100)).await?;
+        assert_eq!(fixture.get_mint_stats(&mint).await?.bump, canonical_bump);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mint_stats_prefunded() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+
+        // Someone sends SOL to the stats address before anyone locks, that can't block locking
+        let (mint_stats, _) = mint_stats_address(&mint);
+        fixture.airdrop(&mint_stats, 1_000_000).await?;
+
+        lock_vault(&mut fixture, &mint, 10, Some(1000)).await?;
+
+        let stats = fixture.get_mint_stats(&mint).await?;
+        assert_eq!(stats.tokens_locked, 1000);
+        assert_eq!(stats.active_vaults, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_legacy_vault_without_mint_stats() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let (mint, admin_ata) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+
+        // Clients from before the stats existed send the original 7 accounts
+        let mut ixs = lock_vault_ix(&admin.pubkey(), &mint, None, &token_program, 10, Some(600));
+        ixs[1].accounts.pop();
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        let (vault, _) = vault_address(&admin.pubkey(), &mint);
+        let extension = fixture.get_vault_extension(&vault).await?.unwrap();
+        assert!(!extension.in_mint_stats());
+        assert!(fixture.get_mint_stats(&mint).await.is_err());
+
+        let mut ix = deposit_vault_ix(
+            &admin.pubkey(),
+            &admin.pubkey(),
+            &mint,
+            None,
+            &token_program,
+            Some(400),
+        );
+        ix.accounts.pop();
+        fixture.send_transaction(&[ix], None, &[&admin]).await?;
+
+        fixture.warp_slot_incremental(10).await?;
+        let mut ixs = empty_vault_ix(&admin.pubkey(), &mint, None, &token_program);
+        assert_eq!(
+            ixs[1].accounts.pop().unwrap().pubkey,
+            mint_stats_address(&mint).0
+        );
+        fixture.send_transaction(&ixs, None, &[&admin]).await?;
+
+        assert!(fixture.get_vault_account(&vault).await.is_err());
+        assert_eq!(fixture.get_token_account(&admin_ata).await?.amount, 1000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_counted_vault_needs_mint_stats() -> Result<()> {
+        let mut fixture = TestBuilder::new().await;
+        let admin = fixture.context.payer.insecure_clone();
+        let token_program = spl_token_interface::id();
+        let (mint, _) = create_token_and_mint(&mut fixture, Some(1000)).await?;
+
+        // Counted vaults can't leave the stats account out, the totals would never go down
+        lock_vault(&mut fixture, &mint, 10, None).await?;
+        fixture.warp_slot_incremental(10).await?;
+
+        let mut ixs = empty_vault_ix(&admin.pubkey(), &mint, None, &token_program);
+        ixs[1].accounts.pop();
+        let error = fixture
+            .send_transaction(&ixs, None, &[&admin])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("insufficient account keys"));
+
+        empty_vault(&mut fixture, &mint).await?;
+        let stats = fixture.get_mint_stats(&mint).await?;
+        assert_eq!(stats.tokens_locked, 0);
+        assert_eq!(stats.active_vaults, 0);
+
+        Ok(())
+    }
 }
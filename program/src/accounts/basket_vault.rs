@@ -0,0 +1,458 @@
+use core::fmt;
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{self, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+use crate::{
+    accounts::{
+        vault::{check_lock_expired, LockMode},
+        VaultProgramDiscriminator,
+    },
+    errors::DiamondHandsError,
+    instructions::create_basket::CreateBasketIxData,
+    pod::{PodI64, PodOption, PodU64},
+    utils::{
+        load_account, load_account_mut, load_account_mut_unchecked, load_signer, DataLen,
+        Discriminator, Initialized,
+    },
+};
+
+/// A basket locks a handful of mints under one lock - think a treasury locking 5-10 tokens on
+/// the same schedule. Instead of one `Vault` ( and its rent ) per mint, the basket is this
+/// header followed by `mint_count` `BasketEntry`s. So unlike the `Vault` the account is not a
+/// fixed size, `DepositBasket` grows it by one entry for every new mint and `EmptyBasket`
+/// shrinks it again.
+///
+/// Note: same 1-byte alignment rules as the `Vault`, the entries are 1-byte aligned as well so
+/// the tail of the account can be mapped straight onto a slice of them
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C, packed)]
+pub struct BasketVault {
+    /// Always `VaultProgramDiscriminator::BasketVault`
+    discriminator: PodOption<u8>,
+    /// Same as the `Vault`, saved so we can use `create_program_address` on-chain
+    bump: u8,
+    /// The signer that created the basket, deposits into it and empties it
+    admin: Pubkey,
+    /// Extra PDA seed, so an admin can have more than one basket
+    basket_index: PodU64,
+    /// The slot at which the basket was created and the lockup starts
+    start_slot: PodU64,
+    /// Only used with `LockMode::Slot`
+    slots_locked: PodU64,
+    /// `LockMode` as a u8 - baskets are all-or-nothing, so no `LockMode::LinearVesting`
+    lock_mode: u8,
+    /// Only used with `LockMode::UnixTimestamp`
+    unlock_timestamp: PodI64,
+    /// How many `BasketEntry`s come after the header
+    mint_count: u8,
+    /// Room to grow, the entries start right after this
+    reserved: [u8; 32],
+}
+
+/// One mint in the basket and the token account ( owned by the basket ) holding its tokens
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C, packed)]
+pub struct BasketEntry {
+    mint: Pubkey,
+    vault_token: Pubkey,
+    mint_decimals: u8,
+}
+
+impl DataLen for BasketVault {
+    const LEN: usize = core::mem::size_of::<BasketVault>();
+}
+
+impl DataLen for BasketEntry {
+    const LEN: usize = core::mem::size_of::<BasketEntry>();
+}
+
+impl Initialized for BasketVault {
+    fn is_initialized(&self) -> bool {
+        matches!(self.discriminator(), Some(discriminator) if *discriminator == Self::DISCRIMINATOR)
+    }
+}
+
+impl Discriminator for BasketVault {
+    const DISCRIMINATOR: u8 = VaultProgramDiscriminator::BasketVault as u8;
+}
+
+/// The Basket PDA is: "BASKET" || ADMIN || INDEX || Bump
+#[macro_export]
+macro_rules! basket_seed_with_bump {
+    ($admin:expr, $index_bytes:expr, $bump_slice:expr) => {
+        [
+            $crate::accounts::basket_vault::BasketVault::SEED,
+            $admin.as_ref(),
+            $index_bytes,
+            $bump_slice,
+        ]
+    };
+}
+
+impl BasketEntry {
+    pub fn new(mint: &Pubkey, vault_token: &Pubkey, mint_decimals: u8) -> Self {
+        Self {
+            mint: *mint,
+            vault_token: *vault_token,
+            mint_decimals,
+        }
+    }
+
+    pub fn mint(&self) -> &Pubkey {
+        &self.mint
+    }
+
+    pub fn vault_token(&self) -> &Pubkey {
+        &self.vault_token
+    }
+
+    pub fn mint_decimals(&self) -> u8 {
+        self.mint_decimals
+    }
+}
+
+impl BasketVault {
+    /// Baskets have their own seed, so a basket can never collide with a vault
+    pub const SEED: &[u8] = b"BASKET";
+
+    /// Every mint is one more set of accounts in `EmptyBasket`, this keeps the account ( and the
+    /// number of transactions it takes to empty it ) sensible
+    pub const MAX_MINTS: u8 = 16;
+
+    /// Where the admin pubkey starts in the basket account, handy for `getProgramAccounts` filters.
+    /// Note: this is the same offset as `Vault::ADMIN_OFFSET`, check the discriminator as well
+    pub const ADMIN_OFFSET: usize = core::mem::offset_of!(BasketVault, admin);
+
+    /// Where the mint count is, the entries it counts start `BasketVault::LEN` in
+    pub const MINT_COUNT_OFFSET: usize = core::mem::offset_of!(BasketVault, mint_count);
+
+    /// The size of a basket account holding `mint_count` mints
+    pub const fn account_len(mint_count: usize) -> usize {
+        Self::LEN + mint_count * BasketEntry::LEN
+    }
+
+    // ----------------------- LOADING ---------------------------
+    /// Only the header is mapped onto the struct, the length has to match `mint_count`
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load(data: &[u8]) -> Result<&BasketVault, ProgramError> {
+        let header = data
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let account = load_account::<BasketVault>(header)?;
+        if data.len() != Self::account_len(account.mint_count as usize) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(account)
+    }
+
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut(data: &mut [u8]) -> Result<&mut BasketVault, ProgramError> {
+        let data_len = data.len();
+        let header = data
+            .get_mut(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let account = load_account_mut::<BasketVault>(header)?;
+        if data_len != Self::account_len(account.mint_count as usize) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(account)
+    }
+
+    /// Only for `initialize`, a new basket is just the header
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut_unchecked(data: &mut [u8]) -> Result<&mut BasketVault, ProgramError> {
+        load_account_mut_unchecked::<BasketVault>(data)
+    }
+
+    /// The mints in the basket, in no particular order
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn entries(data: &[u8]) -> Result<&[BasketEntry], ProgramError> {
+        let mint_count = Self::load(data)?.mint_count as usize;
+        let entries = &data[Self::LEN..];
+
+        // `load` checked the length, and `BasketEntry` is 1-byte aligned
+        Ok(core::slice::from_raw_parts(
+            entries.as_ptr() as *const BasketEntry,
+            mint_count,
+        ))
+    }
+
+    // ----------------------- ACCOUNT CHECKS ---------------------------
+    pub fn create_program_address(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        basket_index: u64,
+        bump: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let bump_bytes = [bump];
+        let index_bytes = basket_index.to_le_bytes();
+        let seed_with_bump = basket_seed_with_bump!(admin, &index_bytes, &bump_bytes);
+
+        let pda = pubkey::create_program_address(&seed_with_bump, program_id)?;
+
+        Ok(pda)
+    }
+
+    /// Same as `Vault::invoke_signed`, just the basket seeds
+    pub fn invoke_signed<F>(admin: &Pubkey, basket_index: u64, bump: u8, f: F) -> ProgramResult
+    where
+        F: FnOnce(&[Signer]) -> ProgramResult,
+    {
+        let bump_bytes = [bump];
+        let index_bytes = basket_index.to_le_bytes();
+        let seed_with_bump = basket_seed_with_bump!(admin, &index_bytes, &bump_bytes);
+        let signing_seeds = [
+            Seed::from(seed_with_bump[0]),
+            Seed::from(seed_with_bump[1]),
+            Seed::from(seed_with_bump[2]),
+            Seed::from(seed_with_bump[3]),
+        ];
+
+        let signer = Signer::from(&signing_seeds);
+        f(core::slice::from_ref(&signer))
+    }
+
+    /// The basket version of `Vault::check` - the mint and token checks are per entry, so those
+    /// are up to the instruction
+    pub fn check(
+        program_id: &Pubkey,
+        account_info: &AccountInfo,
+        expect_writable: bool,
+        check_admin: Option<&AccountInfo>,
+    ) -> Result<(), ProgramError> {
+        let account_owner = account_info.owner();
+        if account_owner.ne(program_id) {
+            log!(
+                "Basket account has an invalid program owner {} != {}",
+                program_id,
+                account_owner
+            );
+            return Err(DiamondHandsError::InvalidVaultOwner.into());
+        }
+
+        if expect_writable && !account_info.is_writable() {
+            log!("Basket account is not writable");
+            return Err(DiamondHandsError::VaultNotWritable.into());
+        }
+
+        let data = unsafe { account_info.borrow_data_unchecked() };
+        let account = unsafe {
+            let result = BasketVault::load(data);
+
+            if result.is_err() {
+                log!("Basket account could not be deseralized");
+                return Err(DiamondHandsError::InvalidVaultData.into());
+            }
+            result?
+        };
+
+        let account_key: Pubkey = Self::create_program_address(
+            program_id,
+            account.admin(),
+            account.basket_index(),
+            account.bump,
+        )?;
+        if account_info.key().ne(&account_key) {
+            log!(
+                "Basket PDA does not match {} != {}",
+                &account_key,
+                account_info.key()
+            );
+            return Err(DiamondHandsError::InvalidVaultAddress.into());
+        }
+
+        if let Some(admin) = check_admin {
+            load_signer(admin, true)?;
+            if account.admin().ne(admin.key()) {
+                log!(
+                    "Basket admin does not match {} != {}",
+                    account.admin(),
+                    admin.key()
+                );
+                return Err(DiamondHandsError::InvalidVaultAdmin.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Same clock check as the vaults, for the whole basket at once
+    pub fn check_unlock_okay(account_info: &AccountInfo) -> Result<(), ProgramError> {
+        let data = unsafe { account_info.borrow_data_unchecked() };
+        let account = unsafe { BasketVault::load(data)? };
+
+        check_lock_expired(
+            account.lock_mode()?,
+            account.start_slot(),
+            account.slots_locked(),
+            account.unlock_timestamp(),
+        )
+    }
+
+    // ----------------------- ENTRIES ------------------------
+    /// Grows the account by one entry and writes `entry` into it. Rent is up to the caller,
+    /// the account has to be rent exempt at its new size by the end of the instruction
+    /// # Safety
+    /// The basket has to have been checked already
+    pub unsafe fn push_entry(account_info: &AccountInfo, entry: BasketEntry) -> ProgramResult {
+        let mint_count = {
+            let data = account_info.borrow_data_unchecked();
+            BasketVault::load(data)?.mint_count
+        };
+        if mint_count >= Self::MAX_MINTS {
+            log!("Basket already has {} mints", mint_count);
+            return Err(DiamondHandsError::BasketFull.into());
+        }
+
+        // New bytes are always zeroed by the runtime
+        account_info.resize(Self::account_len(mint_count as usize + 1))?;
+
+        let data = account_info.borrow_mut_data_unchecked();
+        let (header, entries) = data.split_at_mut(Self::LEN);
+        let account = load_account_mut::<BasketVault>(header)?;
+        let offset = mint_count as usize * BasketEntry::LEN;
+        *load_account_mut_unchecked::<BasketEntry>(&mut entries[offset..])? = entry;
+        account.mint_count = mint_count + 1;
+
+        Ok(())
+    }
+
+    /// Swaps the last entry into `index` and shrinks the account by one entry, so the order of
+    /// the entries is not kept. The extra rent is left in the account for the caller
+    /// # Safety
+    /// The basket has to have been checked already
+    pub unsafe fn remove_entry(account_info: &AccountInfo, index: usize) -> ProgramResult {
+        let mint_count = {
+            let data = account_info.borrow_mut_data_unchecked();
+            let (header, entries) = data.split_at_mut(Self::LEN);
+            let account = load_account_mut::<BasketVault>(header)?;
+            let mint_count = account.mint_count as usize;
+            if index >= mint_count || entries.len() != mint_count * BasketEntry::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let last = (mint_count - 1) * BasketEntry::LEN;
+            entries.copy_within(last.., index * BasketEntry::LEN);
+            account.mint_count -= 1;
+            account.mint_count as usize
+        };
+
+        account_info.resize(Self::account_len(mint_count))
+    }
+
+    // ----------------------- INITIALIZE ------------------------
+    /// Just the header, the mints come in with `DepositBasket`
+    /// # Safety
+    /// Needs to load the account, which is "unsafe"
+    pub unsafe fn initialize(
+        account_info: &AccountInfo,
+        admin: &Pubkey,
+        ix_data: &CreateBasketIxData,
+    ) -> Result<(), ProgramError> {
+        let data = account_info.borrow_mut_data_unchecked();
+        let account = BasketVault::load_mut_unchecked(data)?;
+
+        // Fail initialization if already initalized - this stops re-init attacks
+        if account.is_initialized() {
+            log!("Basket account is already initialized");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let clock = Clock::get()?;
+
+        account.discriminator = PodOption::some(Self::DISCRIMINATOR);
+        account.bump = ix_data.basket_bump;
+        account.admin = *admin;
+        account.basket_index = ix_data.basket_index;
+        account.start_slot = PodU64::from(clock.slot);
+        account.slots_locked = ix_data.slots_to_lock;
+        account.lock_mode = ix_data.lock_mode;
+        account.unlock_timestamp = ix_data.unlock_timestamp;
+        account.mint_count = 0;
+
+        Ok(())
+    }
+
+    // ----------------------- GETTERS ---------------------------
+    pub fn discriminator(&self) -> Option<&u8> {
+        self.discriminator.as_ref()
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub fn admin(&self) -> &Pubkey {
+        &self.admin
+    }
+
+    pub fn basket_index(&self) -> u64 {
+        self.basket_index.into()
+    }
+
+    pub fn start_slot(&self) -> u64 {
+        self.start_slot.into()
+    }
+
+    pub fn slots_locked(&self) -> u64 {
+        self.slots_locked.into()
+    }
+
+    pub fn lock_mode(&self) -> Result<LockMode, ProgramError> {
+        LockMode::try_from(self.lock_mode).map_err(ProgramError::from)
+    }
+
+    pub fn unlock_timestamp(&self) -> i64 {
+        self.unlock_timestamp.into()
+    }
+
+    pub fn mint_count(&self) -> u8 {
+        self.mint_count
+    }
+}
+
+impl fmt::Display for BasketVault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lock_str = match self.lock_mode() {
+            Ok(LockMode::UnixTimestamp) => {
+                format!("Unlock Timestamp: {}", self.unlock_timestamp())
+            }
+            _ => format!(
+                "Slots Locked: {} ({:.3} epochs)",
+                self.slots_locked(),
+                self.slots_locked() as f64 / 432_000.0
+            ),
+        };
+
+        write!(
+            f,
+            "Basket Vault Account:\n\
+             ├─ Bump: {}\n\
+             ├─ Basket Index: {}\n\
+             ├─ Admin: {:?}\n\
+             ├─ Mints: {}\n\
+             ├─ Start Slot: {}\n\
+             └─ {}",
+            self.bump,
+            self.basket_index(),
+            self.admin,
+            self.mint_count,
+            self.start_slot(),
+            lock_str,
+        )
+    }
+}
@@ -0,0 +1,364 @@
+use core::fmt;
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::{self, Pubkey},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+use crate::{
+    accounts::VaultProgramDiscriminator,
+    errors::DiamondHandsError,
+    pod::{PodOption, PodU128, PodU64},
+    utils::{
+        load_account, load_account_mut, load_account_mut_unchecked, DataLen, Discriminator,
+        Initialized,
+    },
+};
+
+/// "How much of $SOLCAT is diamond-handed right now" used to mean scanning every vault with
+/// `getProgramAccounts` and then fetching every vault token account. This is one PDA per mint
+/// with the running totals instead - the first lock for a mint creates it, and locks, deposits,
+/// withdrawals and empties keep it up to date.
+///
+/// Only vaults locked since the stats existed are counted ( `VaultExtension::in_mint_stats` ),
+/// the stats account is optional so a lock from an older client that leaves it out isn't either.
+/// Baskets aren't counted at all. Tokens sent straight to a vault token account, outside of
+/// the program, aren't either.
+///
+/// Note: same 1-byte alignment rules as the `Vault`
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C, packed)]
+pub struct MintStats {
+    /// Always `VaultProgramDiscriminator::MintStats`
+    discriminator: PodOption<u8>,
+    /// Same as the `Vault`, saved so we can use `create_program_address` on-chain
+    bump: u8,
+    mint: Pubkey,
+    /// Saved so the totals can be shown without fetching the mint
+    mint_decimals: u8,
+    /// Tokens in the counted vaults right now
+    tokens_locked: PodU64,
+    /// Counted vaults that haven't been emptied yet
+    active_vaults: PodU64,
+    /// Every lock ever, emptied or not
+    lifetime_locks: PodU64,
+    /// The tokens locked by all of those locks ( deposits not included ), the weight for
+    /// `average_lock_slots`
+    lifetime_tokens_locked: PodU128,
+    /// Sum of tokens * lock length in slots over every lock, see `average_lock_slots`
+    lifetime_token_slots: PodU128,
+    /// Room to grow
+    reserved: [u8; 32],
+}
+
+impl DataLen for MintStats {
+    const LEN: usize = core::mem::size_of::<MintStats>();
+}
+
+impl Initialized for MintStats {
+    fn is_initialized(&self) -> bool {
+        matches!(self.discriminator(), Some(discriminator) if *discriminator == Self::DISCRIMINATOR)
+    }
+}
+
+impl Discriminator for MintStats {
+    const DISCRIMINATOR: u8 = VaultProgramDiscriminator::MintStats as u8;
+}
+
+/// The Mint Stats PDA is: "MINT_STATS" || MINT || Bump
+#[macro_export]
+macro_rules! mint_stats_seed_with_bump {
+    ($mint:expr, $bump_slice:expr) => {
+        [
+            $crate::accounts::mint_stats::MintStats::SEED,
+            $mint.as_ref(),
+            $bump_slice,
+        ]
+    };
+}
+
+impl MintStats {
+    /// One per mint, no admin in the seeds - everyone's vaults add up in the same account
+    pub const SEED: &[u8] = b"MINT_STATS";
+
+    /// Unix timestamp locks are in seconds, for the average they are turned into slots at
+    /// this rate. Slot times vary, so this is only ever an estimate
+    pub const ESTIMATED_MS_PER_SLOT: u64 = 400;
+
+    /// Where the mint pubkey starts in the stats account, handy for `getProgramAccounts` filters
+    pub const MINT_OFFSET: usize = core::mem::offset_of!(MintStats, mint);
+
+    // ----------------------- LOADING ---------------------------
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load(data: &[u8]) -> Result<&MintStats, ProgramError> {
+        load_account::<MintStats>(data)
+    }
+
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut(data: &mut [u8]) -> Result<&mut MintStats, ProgramError> {
+        load_account_mut::<MintStats>(data)
+    }
+
+    // ----------------------- ACCOUNT CHECKS ---------------------------
+    pub fn create_program_address(
+        program_id: &Pubkey,
+        mint: &Pubkey,
+        bump: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let bump_bytes = [bump];
+        let seed_with_bump = mint_stats_seed_with_bump!(mint, &bump_bytes);
+
+        let pda = pubkey::create_program_address(&seed_with_bump, program_id)?;
+
+        Ok(pda)
+    }
+
+    /// Searches for the canonical bump, which costs a lot more CU than `create_program_address`.
+    /// Only used when the account is created, after that the saved bump is the canonical one
+    pub fn find_program_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        pubkey::find_program_address(&[Self::SEED, mint.as_ref()], program_id)
+    }
+
+    /// Same as `Vault::invoke_signed`, just the mint stats seeds
+    pub fn invoke_signed<F>(mint: &Pubkey, bump: u8, f: F) -> ProgramResult
+    where
+        F: FnOnce(&[Signer]) -> ProgramResult,
+    {
+        let bump_bytes = [bump];
+        let seed_with_bump = mint_stats_seed_with_bump!(mint, &bump_bytes);
+        let signing_seeds = [
+            Seed::from(seed_with_bump[0]),
+            Seed::from(seed_with_bump[1]),
+            Seed::from(seed_with_bump[2]),
+        ];
+
+        let signer = Signer::from(&signing_seeds);
+        f(core::slice::from_ref(&signer))
+    }
+
+    /// Makes sure the account is an initialized `MintStats`, is writable and is the PDA for `mint`.
+    /// Everything wrong with it is `InvalidMintStats`, the logs say which part
+    pub fn check(
+        program_id: &Pubkey,
+        account_info: &AccountInfo,
+        mint: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        let account_owner = account_info.owner();
+        if account_owner.ne(program_id) {
+            log!(
+                "Mint stats account has an invalid program owner {} != {}",
+                program_id,
+                account_owner
+            );
+            return Err(DiamondHandsError::InvalidMintStats.into());
+        }
+
+        if !account_info.is_writable() {
+            log!("Mint stats account is not writable");
+            return Err(DiamondHandsError::InvalidMintStats.into());
+        }
+
+        let data = unsafe { account_info.borrow_data_unchecked() };
+        let Ok(account) = (unsafe { MintStats::load(data) }) else {
+            log!("Mint stats account could not be deseralized");
+            return Err(DiamondHandsError::InvalidMintStats.into());
+        };
+
+        if account.mint().ne(mint.key()) {
+            log!(
+                "Mint stats are for a different mint {} != {}",
+                account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::InvalidMintStats.into());
+        }
+
+        let account_key = Self::create_program_address(program_id, mint.key(), account.bump)?;
+        if account_info.key().ne(&account_key) {
+            log!(
+                "Mint stats PDA does not match {} != {}",
+                &account_key,
+                account_info.key()
+            );
+            return Err(DiamondHandsError::InvalidMintStats.into());
+        }
+
+        Ok(())
+    }
+
+    /// The stats account is the last account and optional, clients from before the stats existed
+    /// still send the original accounts. A vault that is counted can't leave it out though, or
+    /// its tokens would never come off the totals
+    pub fn required(account_info: Option<&AccountInfo>) -> Result<&AccountInfo, ProgramError> {
+        account_info.ok_or_else(|| {
+            log!("Vault is counted in the mint stats, but the mint stats account is missing");
+            ProgramError::NotEnoughAccountKeys
+        })
+    }
+
+    /// `check` and then hand the stats to `f` to update
+    pub fn update<F>(
+        program_id: &Pubkey,
+        account_info: &AccountInfo,
+        mint: &AccountInfo,
+        f: F,
+    ) -> ProgramResult
+    where
+        F: FnOnce(&mut MintStats),
+    {
+        Self::check(program_id, account_info, mint)?;
+
+        let data = unsafe { account_info.borrow_mut_data_unchecked() };
+        f(unsafe { MintStats::load_mut(data)? });
+
+        Ok(())
+    }
+
+    // ----------------------- INITIALIZE ------------------------
+    /// All of the totals start at zero, the lock that created the account adds itself after
+    pub fn new(mint: Pubkey, bump: u8, mint_decimals: u8) -> Self {
+        Self {
+            discriminator: PodOption::some(Self::DISCRIMINATOR),
+            bump,
+            mint,
+            mint_decimals,
+            ..Self::default()
+        }
+    }
+
+    /// Writes `MintStats::new` into a fresh account
+    /// # Safety
+    /// Needs to load the account, which is "unsafe"
+    pub unsafe fn initialize(
+        account_info: &AccountInfo,
+        mint: &Pubkey,
+        bump: u8,
+        mint_decimals: u8,
+    ) -> Result<(), ProgramError> {
+        let data = account_info.borrow_mut_data_unchecked();
+        let account = load_account_mut_unchecked::<MintStats>(data)?;
+
+        // Fail initialization if already initalized - this stops re-init attacks
+        if account.is_initialized() {
+            log!("Mint stats account is already initialized");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        *account = Self::new(*mint, bump, mint_decimals);
+
+        Ok(())
+    }
+
+    // ----------------------- UPDATES ---------------------------
+    // Everything saturates - the stats are for display, they should never be the reason a
+    // vault can't be emptied
+
+    /// A new vault, `lock_slots` is how long it was locked for at the time
+    pub fn add_lock(&mut self, tokens: u64, lock_slots: u64) {
+        self.add_tokens(tokens);
+        self.active_vaults = PodU64::from(self.active_vaults().saturating_add(1));
+        self.lifetime_locks = PodU64::from(self.lifetime_locks().saturating_add(1));
+        self.lifetime_tokens_locked =
+            PodU128::from(self.lifetime_tokens_locked().saturating_add(tokens as u128));
+        self.lifetime_token_slots = PodU128::from(
+            self.lifetime_token_slots()
+                .saturating_add((tokens as u128).saturating_mul(lock_slots as u128)),
+        );
+    }
+
+    /// Deposits into a counted vault
+    pub fn add_tokens(&mut self, tokens: u64) {
+        self.tokens_locked = PodU64::from(self.tokens_locked().saturating_add(tokens));
+    }
+
+    /// Withdrawals and empties of a counted vault
+    pub fn remove_tokens(&mut self, tokens: u64) {
+        self.tokens_locked = PodU64::from(self.tokens_locked().saturating_sub(tokens));
+    }
+
+    /// A counted vault was closed
+    pub fn remove_vault(&mut self) {
+        self.active_vaults = PodU64::from(self.active_vaults().saturating_sub(1));
+    }
+
+    // ----------------------- GETTERS ---------------------------
+    pub fn discriminator(&self) -> Option<&u8> {
+        self.discriminator.as_ref()
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub fn mint(&self) -> &Pubkey {
+        &self.mint
+    }
+
+    pub fn mint_decimals(&self) -> u8 {
+        self.mint_decimals
+    }
+
+    pub fn tokens_locked(&self) -> u64 {
+        self.tokens_locked.into()
+    }
+
+    pub fn active_vaults(&self) -> u64 {
+        self.active_vaults.into()
+    }
+
+    pub fn lifetime_locks(&self) -> u64 {
+        self.lifetime_locks.into()
+    }
+
+    pub fn lifetime_tokens_locked(&self) -> u128 {
+        self.lifetime_tokens_locked.into()
+    }
+
+    pub fn lifetime_token_slots(&self) -> u128 {
+        self.lifetime_token_slots.into()
+    }
+
+    /// How long tokens get locked for, on average - every lock counts as much as the tokens it
+    /// locked. 0 until something has been locked
+    pub fn average_lock_slots(&self) -> u64 {
+        average_lock_slots(self.lifetime_token_slots(), self.lifetime_tokens_locked())
+    }
+}
+
+/// Split out so the SDK can do the same maths on its copy of the stats
+pub fn average_lock_slots(lifetime_token_slots: u128, lifetime_tokens_locked: u128) -> u64 {
+    // Every lock adds at most `tokens * u64::MAX` slots, so the average always fits in a u64
+    lifetime_token_slots
+        .checked_div(lifetime_tokens_locked)
+        .unwrap_or_default()
+        .min(u64::MAX as u128) as u64
+}
+
+impl fmt::Display for MintStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Mint Stats Account:\n\
+             ├─ Bump: {}\n\
+             ├─ Mint: {:?}\n\
+             ├─ Mint Decimals: {}\n\
+             ├─ Tokens Locked: {}\n\
+             ├─ Active Vaults: {}\n\
+             ├─ Lifetime Locks: {}\n\
+             └─ Average Lock Slots: {}",
+            self.bump,
+            self.mint,
+            self.mint_decimals,
+            self.tokens_locked(),
+            self.active_vaults(),
+            self.lifetime_locks(),
+            self.average_lock_slots(),
+        )
+    }
+}
@@ -1,18 +1,32 @@
 use pinocchio::program_error::ProgramError;
 use pinocchio_log::log;
 
+pub mod basket_vault;
+pub mod mint_stats;
 pub mod vault;
+pub mod vault_extension;
 
-/// Only 1 account type here, I really like to use hex for these scanrios
+/// I really like to use hex for these scanrios
+///
+/// `IndexedVault` is the same `Vault` struct, the discriminator just tells us the PDA has the
+/// extra `vault_index` seed. v1 vaults keep their original seeds so they never have to migrate.
+/// `BasketVault` is a different account altogether, it locks several mints at once.
+/// `MintStats` isn't a vault at all, it is the running totals for every vault of one mint.
 #[repr(u8)]
 pub enum VaultProgramDiscriminator {
     Vault = 0x01,
+    IndexedVault = 0x02,
+    BasketVault = 0x03,
+    MintStats = 0x04,
 }
 
 impl VaultProgramDiscriminator {
     pub fn from_u8(value: u8) -> Result<Self, ProgramError> {
         match value {
             0x01 => Ok(VaultProgramDiscriminator::Vault),
+            0x02 => Ok(VaultProgramDiscriminator::IndexedVault),
+            0x03 => Ok(VaultProgramDiscriminator::BasketVault),
+            0x04 => Ok(VaultProgramDiscriminator::MintStats),
             _ => {
                 log!("Invalid account discriminator: {}", value);
                 Err(ProgramError::InvalidInstructionData)
@@ -26,3 +40,6 @@ impl VaultProgramDiscriminator {
 // initialized - this is also why I use PodOption<u8> for discriminators, so I know when
 // something has been intentionally set
 const _: () = assert!(VaultProgramDiscriminator::Vault as u8 != 0);
+const _: () = assert!(VaultProgramDiscriminator::IndexedVault as u8 != 0);
+const _: () = assert!(VaultProgramDiscriminator::BasketVault as u8 != 0);
+const _: () = assert!(VaultProgramDiscriminator::MintStats as u8 != 0);
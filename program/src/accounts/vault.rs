@@ -2,23 +2,65 @@ use core::fmt;
 
 use pinocchio::{
     account_info::AccountInfo,
-    instruction::Seed,
+    instruction::{Seed, Signer},
     program_error::ProgramError,
     pubkey::{self, Pubkey},
-    sysvars::{clock::Clock, Sysvar},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
 };
 use pinocchio_log::log;
+use pinocchio_system::instructions::Transfer;
 
 use crate::{
-    accounts::VaultProgramDiscriminator,
+    accounts::{vault_extension::VaultExtension, VaultProgramDiscriminator},
     errors::DiamondHandsError,
     instructions::lock_vault::LockVaultIxData,
-    pod::{PodOption, PodU64},
+    pod::{PodI64, PodOption, PodU64},
     utils::{
-        load_account, load_account_mut_unchecked, load_signer, DataLen, Discriminator, Initialized,
+        load_account, load_account_mut, load_account_mut_unchecked, load_signer, DataLen,
+        Discriminator, Initialized,
     },
 };
 
+/// How the vault decides when it can be unlocked. Slot based locks are the original mode,
+/// but slot times vary ( 400ms - 500ms+ ), so long slot based locks drift from the calendar.
+/// Unix timestamp locks use `Clock::unix_timestamp` instead, which tracks wall-clock time.
+///
+/// Linear vesting is slot based as well, but instead of all-or-nothing the tokens unlock bit by bit
+/// between the cliff and `start_slot + slots_locked`, and are taken out with `Withdraw`.
+///
+/// Note: existing vaults have zeroed `reserved` bytes, so `Slot` has to stay 0
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Slot = 0x00,
+    UnixTimestamp = 0x01,
+    LinearVesting = 0x02,
+}
+
+impl TryFrom<u8> for LockMode {
+    type Error = DiamondHandsError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(LockMode::Slot),
+            0x01 => Ok(LockMode::UnixTimestamp),
+            0x02 => Ok(LockMode::LinearVesting),
+            _ => Err(DiamondHandsError::InvalidLockMode),
+        }
+    }
+}
+
+impl fmt::Display for LockMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockMode::Slot => write!(f, "Slot"),
+            LockMode::UnixTimestamp => write!(f, "Unix Timestamp"),
+            LockMode::LinearVesting => write!(f, "Linear Vesting"),
+        }
+    }
+}
+
 /// The Counter account structure
 /// Note: Note that all fields here are 1-byte aligned - thats by we use PodXX. This allows us to derefrence the
 /// account `C-style`, you could run into some trouble if you start using types that are not 1-byte aligned.
@@ -33,7 +75,8 @@ pub struct Vault {
     /// u8 "Bump" that is used to "bump" the vault PDA on curve. I can be derived on-chain, but that takes up CU. So we tend
     /// to derive it off-chain ( using find_program_address ) and then save it on-chain to rederive the PDA ( using create_program_address )
     bump: u8,
-    /// The owner and admin of the vault - the signer that locks and empties their vault
+    /// The owner and admin of the vault - the signer that locks and empties their vault.
+    /// This can be changed with `TransferAdmin`, so the PDA is derived from `seed_admin` instead
     admin: Pubkey,
     /// The token mint of the token that is to be locked
     mint: Pubkey,
@@ -45,8 +88,15 @@ pub struct Vault {
     start_slot: PodU64,
     /// The minimum amount of slots that vault has to be locked for
     slots_locked: PodU64,
+    /// `LockMode` as a u8 - this was carved out of the reserved bytes, so old vaults read as `LockMode::Slot`
+    lock_mode: u8,
+    /// Only used with `LockMode::UnixTimestamp` - the unix timestamp at which the vault unlocks
+    unlock_timestamp: PodI64,
+    /// Only used by `VaultProgramDiscriminator::IndexedVault` vaults - the extra PDA seed that
+    /// lets an admin have more than one vault per mint. Also carved out of the reserved bytes.
+    vault_index: PodU64,
     /// General good practice to have some reserved bytes for new features - but not necessary for the current implementation
-    reserved: [u8; 32],
+    reserved: [u8; 15],
 }
 
 impl DataLen for Vault {
@@ -54,14 +104,14 @@ impl DataLen for Vault {
 }
 
 /// Initialized for us is just that the account's first byte is the correct discriminator
-/// when space is allocated on-chain it is always zerod out
+/// when space is allocated on-chain it is always zerod out. Both v1 and indexed vaults count.
 impl Initialized for Vault {
     fn is_initialized(&self) -> bool {
-        if let Some(discriminator) = self.discriminator() {
-            *discriminator == Self::DISCRIMINATOR
-        } else {
-            false
-        }
+        matches!(
+            self.discriminator(),
+            Some(discriminator) if *discriminator == Self::DISCRIMINATOR
+                || *discriminator == VaultProgramDiscriminator::IndexedVault as u8
+        )
     }
 }
 
@@ -83,14 +133,73 @@ macro_rules! vault_seed_with_bump {
     };
 }
 
+/// Indexed vaults ( `VaultProgramDiscriminator::IndexedVault` ) add the little-endian index
+/// The Vault PDA is: ADMIN || MINT || INDEX || Bump
+#[macro_export]
+macro_rules! vault_seed_with_index_and_bump {
+    ($admin:expr, $mint:expr, $index_bytes:expr, $bump_slice:expr) => {
+        [
+            $crate::accounts::vault::Vault::SEED,
+            $admin.as_ref(),
+            $mint.as_ref(),
+            $index_bytes,
+            $bump_slice,
+        ]
+    };
+}
+
 impl Vault {
+    // ----------------------- LOADING ---------------------------
+    /// Vault accounts are the `Vault`, optionally followed by a `VaultExtension`. So unlike our
+    /// other accounts, we only map the first `Vault::LEN` bytes onto the struct.
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load(data: &[u8]) -> Result<&Vault, ProgramError> {
+        Self::check_len(data.len())?;
+        load_account::<Vault>(&data[..Self::LEN])
+    }
+
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut(data: &mut [u8]) -> Result<&mut Vault, ProgramError> {
+        Self::check_len(data.len())?;
+        load_account_mut::<Vault>(&mut data[..Self::LEN])
+    }
+
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut_unchecked(data: &mut [u8]) -> Result<&mut Vault, ProgramError> {
+        Self::check_len(data.len())?;
+        load_account_mut_unchecked::<Vault>(&mut data[..Self::LEN])
+    }
+
+    // ----------------------- OFFSETS ---------------------------
+    /// Where the admin pubkey starts in the vault account, handy for `getProgramAccounts` filters.
+    /// Note: this is the current admin, after a `TransferAdmin` it is not the one in the seeds
+    pub const ADMIN_OFFSET: usize = core::mem::offset_of!(Vault, admin);
+
+    /// Where the mint pubkey starts in the vault account, same deal as `ADMIN_OFFSET`
+    pub const MINT_OFFSET: usize = core::mem::offset_of!(Vault, mint);
+
+    /// Where the `LockMode` byte is, to filter vaults by how they unlock
+    pub const LOCK_MODE_OFFSET: usize = core::mem::offset_of!(Vault, lock_mode);
+
+    fn check_len(len: usize) -> Result<(), ProgramError> {
+        if len != Self::LEN && len != VaultExtension::ACCOUNT_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
     // ----------------------- ACCOUNT CHECKS ---------------------------
 
     /// A little discussion on PDA Selection. In this case, we are using the SEED || ADMIN || MINT || Bump.
     /// This configuration allows us to create one Vault per Admin and Mint pair.
     /// A benifit of this is it acts kinda like an associated token vault, so we always can know if a vault exists for a given admin and mint pair.
     /// This decision is arbitrary, you could use a different seed or even a different PDA selection strategy.
-    /// For example if you wanted multiple vaults per admin and mint pair, you could add u8 "COUNT" to the seed.
+    /// Since you can't stagger unlocks with one vault, indexed vaults add a u64 "INDEX" to the seed - the
+    /// original seeds are kept for v1 vaults, so `vault_index` is an `Option` everywhere.
     pub const SEED: &[u8] = b"VAULT";
 
     /// We use `create_program_address` to derive the vault PDA given a vault. So offchain we use `offchain_find_program_address`
@@ -99,11 +208,20 @@ impl Vault {
         program_id: &Pubkey,
         admin: &Pubkey,
         mint: &Pubkey,
+        vault_index: Option<u64>,
         bump: u8,
     ) -> Result<Pubkey, ProgramError> {
         let bump_bytes = [bump];
+        let index_bytes = vault_index.unwrap_or_default().to_le_bytes();
         let seed_with_bump = vault_seed_with_bump!(admin, mint, &bump_bytes);
-        let pda = pubkey::create_program_address(&seed_with_bump, program_id)?;
+        let seed_with_index_and_bump =
+            vault_seed_with_index_and_bump!(admin, mint, &index_bytes, &bump_bytes);
+
+        let seeds: &[&[u8]] = match vault_index {
+            Some(_) => &seed_with_index_and_bump,
+            None => &seed_with_bump,
+        };
+        let pda = pubkey::create_program_address(seeds, program_id)?;
 
         Ok(pda)
     }
@@ -112,28 +230,82 @@ impl Vault {
     pub fn check_seeds(
         admin: &Pubkey,
         mint: &Pubkey,
+        vault_index: Option<u64>,
         bump: u8,
         seeds: &[Seed],
     ) -> Result<(), ProgramError> {
         let bump_bytes = [bump];
+        let index_bytes = vault_index.unwrap_or_default().to_le_bytes();
         let seed_with_bump = vault_seed_with_bump!(admin, mint, &bump_bytes);
+        let seed_with_index_and_bump =
+            vault_seed_with_index_and_bump!(admin, mint, &index_bytes, &bump_bytes);
 
-        if seeds.len() != seed_with_bump.len() {
+        let expected_seeds: &[&[u8]] = match vault_index {
+            Some(_) => &seed_with_index_and_bump,
+            None => &seed_with_bump,
+        };
+
+        if seeds.len() != expected_seeds.len() {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        for (seed_index, seed) in seeds.iter().enumerate() {
-            for (byte_index, byte) in seed.as_ref().iter().enumerate() {
-                let seed_byte = seed_with_bump[seed_index][byte_index];
-                if byte.ne(&seed_byte) {
-                    return Err(ProgramError::InvalidAccountData);
-                }
+        for (seed, expected_seed) in seeds.iter().zip(expected_seeds.iter()) {
+            if seed.as_ref().ne(*expected_seed) {
+                return Err(ProgramError::InvalidAccountData);
             }
         }
 
         Ok(())
     }
 
+    /// v1 vaults sign with 4 seeds and indexed vaults with 5, so the seed arrays are different
+    /// types. Instead of copy and pasting both at every CPI, this builds the right `Signer` and
+    /// hands it to `f` - seeds are still a little magical in rust land.
+    pub fn invoke_signed<F>(
+        admin: &Pubkey,
+        mint: &Pubkey,
+        vault_index: Option<u64>,
+        bump: u8,
+        f: F,
+    ) -> ProgramResult
+    where
+        F: FnOnce(&[Signer]) -> ProgramResult,
+    {
+        let bump_bytes = [bump];
+
+        match vault_index {
+            Some(vault_index) => {
+                let index_bytes = vault_index.to_le_bytes();
+                let seed_with_bump =
+                    vault_seed_with_index_and_bump!(admin, mint, &index_bytes, &bump_bytes);
+                let signing_seeds = [
+                    Seed::from(seed_with_bump[0]),
+                    Seed::from(seed_with_bump[1]),
+                    Seed::from(seed_with_bump[2]),
+                    Seed::from(seed_with_bump[3]),
+                    Seed::from(seed_with_bump[4]),
+                ];
+                Self::check_seeds(admin, mint, Some(vault_index), bump, &signing_seeds)?;
+
+                let signer = Signer::from(&signing_seeds);
+                f(core::slice::from_ref(&signer))
+            }
+            None => {
+                let seed_with_bump = vault_seed_with_bump!(admin, mint, &bump_bytes);
+                let signing_seeds = [
+                    Seed::from(seed_with_bump[0]),
+                    Seed::from(seed_with_bump[1]),
+                    Seed::from(seed_with_bump[2]),
+                    Seed::from(seed_with_bump[3]),
+                ];
+                Self::check_seeds(admin, mint, None, bump, &signing_seeds)?;
+
+                let signer = Signer::from(&signing_seeds);
+                f(core::slice::from_ref(&signer))
+            }
+        }
+    }
+
     /// This function does all of the checks needed for a vault. Checks are probably the biggest part of on-chain programming.
     /// With that being said, clear concise and non-messy code makes it way less likely that you're going to miss a check.
     /// So I always like to make a "check" function per account
@@ -155,40 +327,47 @@ impl Vault {
                 program_id,
                 account_owner
             );
-            return Err(ProgramError::InvalidAccountOwner);
+            return Err(DiamondHandsError::InvalidVaultOwner.into());
         }
 
         if expect_writable && !account_info.is_writable() {
             log!("Vault account is not writable");
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::VaultNotWritable.into());
         }
 
         // Yes this is "unsafe" at its core, we are just mapping memory to a struct.
         // In C land, this is common, not so much in Rust. Take a look at the
         // `load_account` function in the `utils` folder for more details.
         // note - this is a continuation of our 1-byte aligned discussion from above
+        let data = unsafe { account_info.borrow_data_unchecked() };
         let account = unsafe {
-            let data = account_info.borrow_data_unchecked();
-            let result = load_account::<Vault>(data);
+            let result = Vault::load(data);
 
-            if let Err(error) = result {
+            if result.is_err() {
                 log!("Vault account could not be deseralized");
-                return Err(error);
+                return Err(DiamondHandsError::InvalidVaultData.into());
             }
             result?
         };
 
         // We need to re-derive the PDA here to make sure it matches - else you could pass in a
         // an account that does not match
-        let account_key: Pubkey =
-            Self::create_program_address(program_id, &account.admin, &account.mint, account.bump)?;
+        // Note: after a `TransferAdmin` the stored admin is not the one in the seeds
+        let extension = unsafe { VaultExtension::load(data)? };
+        let account_key: Pubkey = Self::create_program_address(
+            program_id,
+            account.seed_admin(extension),
+            &account.mint,
+            account.vault_index(),
+            account.bump,
+        )?;
         if account_info.key().ne(&account_key) {
             log!(
                 "Vault PDA does not match {} != {}",
                 &account_key,
                 account_info.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::InvalidVaultAddress.into());
         }
 
         // Optional admin check - this is like the `has_one` check in anchor
@@ -201,7 +380,7 @@ impl Vault {
                     account.admin(),
                     admin.key()
                 );
-                return Err(ProgramError::InvalidAccountData);
+                return Err(DiamondHandsError::InvalidVaultAdmin.into());
             }
         }
 
@@ -213,7 +392,7 @@ impl Vault {
                     account.mint(),
                     mint.key()
                 );
-                return Err(ProgramError::InvalidAccountData);
+                return Err(DiamondHandsError::InvalidVaultMint.into());
             }
         }
 
@@ -225,41 +404,139 @@ impl Vault {
                     account.vault_token(),
                     token.key()
                 );
-                return Err(ProgramError::InvalidAccountData);
+                return Err(DiamondHandsError::InvalidVaultTokenAccount.into());
             }
         }
 
         Ok(())
     }
 
-    /// This is a general function to check if a vault can be unlocked.
-    /// Note that we ALWAYS use checked arithmatic, in this case `saturating_sub`
-    /// byte overflows and underflows are deadly and quiet.
-    /// # Safety
-    /// Needs to load the account, which is "unsafe"
-    pub fn check_unlock_okay(account_info: &AccountInfo) -> Result<(), ProgramError> {
-        let data = unsafe { account_info.borrow_mut_data_unchecked() };
-        let account = unsafe { load_account_mut_unchecked::<Vault>(data)? };
-        let clock = Clock::get()?;
+    /// The admin funds the vault, but if the vault was locked for a beneficiary only the
+    /// beneficiary can take the tokens out. This checks `authority` is whichever one that is,
+    /// and that it is a signer. Call `check` first, this does not re-check the vault.
+    pub fn check_withdraw_authority(
+        account_info: &AccountInfo,
+        authority: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        load_signer(authority, true)?;
 
-        let slots_elapsed = clock.slot.saturating_sub(account.start_slot());
-        if slots_elapsed < account.slots_locked() {
-            let remaining_slots = account.slots_locked().saturating_sub(slots_elapsed);
+        Self::check_withdraw_authority_key(account_info, authority.key())
+    }
 
-            // Okay, one caveat to the checked arithmatic. Unsigned division is OK, is the denominator
-            // is a constant that can never be 0. This is because all `checked_div` checks for is
-            // a 0 in the denominator. However, in my programs, I still used checked_div for consistancy
+    /// Same as `check_withdraw_authority`, just without the signer check. `CrankEmpty` uses this,
+    /// there the withdraw authority only receives the tokens, someone else signs
+    pub fn check_withdraw_authority_key(
+        account_info: &AccountInfo,
+        authority: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        let data = unsafe { account_info.borrow_data_unchecked() };
+        let account = unsafe { Vault::load(data)? };
+        let extension = unsafe { VaultExtension::load(data)? };
+
+        let withdraw_authority = extension
+            .and_then(|extension| extension.beneficiary())
+            .unwrap_or(account.admin());
+
+        if withdraw_authority.ne(authority) {
             log!(
-                "Vault will unlock in {} slots ({} epochs)",
-                remaining_slots,
-                remaining_slots / 432_000u64
+                "Vault withdraw authority does not match {} != {}",
+                withdraw_authority,
+                authority
             );
-            return Err(DiamondHandsError::VaultLocked.into());
+            return Err(DiamondHandsError::InvalidWithdrawAuthority.into());
         }
 
         Ok(())
     }
 
+    /// This is a general function to check if a vault can be unlocked, the clock part is
+    /// shared with basket vaults in `check_lock_expired`
+    /// # Safety
+    /// Needs to load the account, which is "unsafe"
+    pub fn check_unlock_okay(account_info: &AccountInfo) -> Result<(), ProgramError> {
+        let data = unsafe { account_info.borrow_data_unchecked() };
+        let account = unsafe { Vault::load(data)? };
+
+        check_lock_expired(
+            account.lock_mode()?,
+            account.start_slot(),
+            account.slots_locked(),
+            account.unlock_timestamp(),
+        )
+    }
+
+    /// How many of `total_tokens` have vested at `slot` for a `LockMode::LinearVesting` vault.
+    /// Nothing before the cliff, then linear from `start_slot` to `start_slot + slots_locked`.
+    ///
+    /// `total_tokens` is what is in the vault plus what has already been claimed, that way
+    /// deposits vest on the same schedule as the original tokens.
+    pub fn vested_tokens(&self, cliff_slots: u64, total_tokens: u64, slot: u64) -> u64 {
+        let slots_elapsed = slot.saturating_sub(self.start_slot());
+        if slots_elapsed < cliff_slots {
+            return 0;
+        }
+
+        if slots_elapsed >= self.slots_locked() {
+            return total_tokens;
+        }
+
+        // u128 so the multiplication can't overflow, and the result is always <= total_tokens
+        // since `slots_elapsed < slots_locked` - so the cast back down is safe
+        let vested = (total_tokens as u128)
+            .saturating_mul(slots_elapsed as u128)
+            .checked_div(self.slots_locked() as u128)
+            .unwrap_or_default();
+
+        vested as u64
+    }
+
+    // ----------------------- CLOSE ------------------------
+    /// Sends all of the vault's lamports to `destination` and zeros the data
+    /// # Safety
+    /// The vault has to have been checked already
+    pub unsafe fn close(account_info: &AccountInfo, destination: &AccountInfo) {
+        // Transfer all lamports from vault to the destination
+        // Note: when transferring lamports from a PDA, this is the best way to do it
+        // NOT by calling system.transfer
+        *destination.borrow_mut_lamports_unchecked() = destination
+            .lamports()
+            .saturating_add(account_info.lamports());
+        *account_info.borrow_mut_lamports_unchecked() = 0;
+
+        // Zero out the vault data to mark it as closed
+        // I would always reccomend this as there could be `rehydration` attacks
+        // where if you re-initalize this account in the same transaction it could have
+        // lingering data - so, boyscouts rule here.
+        account_info.borrow_mut_data_unchecked().fill(0);
+    }
+
+    // ----------------------- GROW ------------------------
+    /// Vaults from before the extension are only `Vault::LEN` bytes. Anything that writes to the
+    /// extension calls this first - `payer` tops the rent up to `VaultExtension::ACCOUNT_LEN` and
+    /// the account is resized. Vaults that already have the extension are left alone
+    pub fn grow_to_extension(vault: &AccountInfo, payer: &AccountInfo) -> ProgramResult {
+        if vault.data_len() != Self::LEN {
+            return Ok(());
+        }
+
+        let rent = Rent::get()?;
+        let lamports = rent
+            .minimum_balance(VaultExtension::ACCOUNT_LEN)
+            .saturating_sub(vault.lamports());
+
+        if lamports > 0 {
+            Transfer {
+                from: payer,
+                to: vault,
+                lamports,
+            }
+            .invoke()?;
+        }
+
+        // New bytes are zeroed, which is "not set" for everything in the extension
+        vault.resize(VaultExtension::ACCOUNT_LEN)
+    }
+
     // ----------------------- INITIALIZE ------------------------
     /// Just initalizes the Vault account, nothing special here
     /// # Safety
@@ -271,9 +548,10 @@ impl Vault {
         ix_data: &LockVaultIxData,
         vault_token: &Pubkey,
         mint_decimals: u8,
+        in_mint_stats: bool,
     ) -> Result<(), ProgramError> {
         let data = account_info.borrow_mut_data_unchecked();
-        let account = load_account_mut_unchecked::<Vault>(data)?;
+        let account = Vault::load_mut_unchecked(data)?;
 
         // Fail initialization if already initalized - this stops re-init attacks
         if account.is_initialized() {
@@ -283,7 +561,17 @@ impl Vault {
 
         let clock = Clock::get()?;
 
-        account.discriminator = PodOption::some(VaultProgramDiscriminator::Vault as u8);
+        // The discriminator decides which seeds the PDA uses from here on out
+        match ix_data.vault_index.as_ref() {
+            Some(vault_index) => {
+                account.discriminator =
+                    PodOption::some(VaultProgramDiscriminator::IndexedVault as u8);
+                account.vault_index = *vault_index;
+            }
+            None => {
+                account.discriminator = PodOption::some(VaultProgramDiscriminator::Vault as u8);
+            }
+        }
         account.bump = ix_data.vault_bump;
         account.admin = *admin;
         account.mint = *mint;
@@ -291,6 +579,17 @@ impl Vault {
         account.mint_decimals = mint_decimals;
         account.start_slot = PodU64::from(clock.slot);
         account.slots_locked = ix_data.slots_to_lock;
+        account.lock_mode = ix_data.lock_mode;
+        account.unlock_timestamp = ix_data.unlock_timestamp;
+
+        // New vaults always have the extension, it is zeroed by the create so we only
+        // have to set what is used
+        if let Some(extension) = VaultExtension::load_mut(data)? {
+            extension.set_cliff_slots(ix_data.cliff_slots.get());
+            extension.set_beneficiary(ix_data.beneficiary.as_ref().copied());
+            extension.set_label(ix_data.label);
+            extension.set_in_mint_stats(in_mint_stats);
+        }
 
         Ok(())
     }
@@ -313,6 +612,10 @@ impl Vault {
         &self.mint
     }
 
+    pub fn mint_decimals(&self) -> u8 {
+        self.mint_decimals
+    }
+
     pub fn vault_token(&self) -> &Pubkey {
         &self.vault_token
     }
@@ -326,6 +629,95 @@ impl Vault {
     pub fn slots_locked(&self) -> u64 {
         self.slots_locked.into()
     }
+
+    pub fn lock_mode(&self) -> Result<LockMode, ProgramError> {
+        LockMode::try_from(self.lock_mode).map_err(ProgramError::from)
+    }
+
+    pub fn unlock_timestamp(&self) -> i64 {
+        self.unlock_timestamp.into()
+    }
+
+    /// The admin the PDA was derived from - this is `admin` unless the vault was handed over
+    /// with `TransferAdmin`, then the original admin is kept in the extension
+    pub fn seed_admin<'a>(&'a self, extension: Option<&'a VaultExtension>) -> &'a Pubkey {
+        extension
+            .and_then(|extension| extension.seed_admin())
+            .unwrap_or(self.admin())
+    }
+
+    /// `None` for v1 vaults, they don't have an index seed
+    pub fn vault_index(&self) -> Option<u64> {
+        match self.discriminator() {
+            Some(discriminator)
+                if *discriminator == VaultProgramDiscriminator::IndexedVault as u8 =>
+            {
+                Some(self.vault_index.into())
+            }
+            _ => None,
+        }
+    }
+
+    // ----------------------- SETTERS ---------------------------
+    /// Note: these don't check that the lock only gets longer, that is up to the instruction
+    pub fn set_slots_locked(&mut self, slots_locked: u64) {
+        self.slots_locked = PodU64::from(slots_locked);
+    }
+
+    pub fn set_unlock_timestamp(&mut self, unlock_timestamp: i64) {
+        self.unlock_timestamp = PodI64::from(unlock_timestamp);
+    }
+
+    /// Note: this does not touch the seeds, `TransferAdmin` saves the seed admin first
+    pub fn set_admin(&mut self, admin: Pubkey) {
+        self.admin = admin;
+    }
+}
+
+/// Everything that can be locked ( vaults and baskets ) unlocks the same way, so this is the
+/// clock check on its own. Note that we ALWAYS use checked arithmatic, in this case `saturating_sub`
+/// byte overflows and underflows are deadly and quiet.
+pub(crate) fn check_lock_expired(
+    lock_mode: LockMode,
+    start_slot: u64,
+    slots_locked: u64,
+    unlock_timestamp: i64,
+) -> Result<(), ProgramError> {
+    let clock = Clock::get()?;
+
+    // Each vault is checked against the clock it was locked with
+    if lock_mode == LockMode::UnixTimestamp {
+        if clock.unix_timestamp < unlock_timestamp {
+            let remaining_seconds = unlock_timestamp.saturating_sub(clock.unix_timestamp);
+            log!(
+                "Vault will unlock in {} seconds ({} days)",
+                remaining_seconds,
+                remaining_seconds / 86_400i64
+            );
+            return Err(DiamondHandsError::VaultLocked.into());
+        }
+
+        return Ok(());
+    }
+
+    // Slot and linear vesting vaults - a vesting vault is fully unlocked at the end of
+    // the vesting period, `Withdraw` takes care of everything before that
+    let slots_elapsed = clock.slot.saturating_sub(start_slot);
+    if slots_elapsed < slots_locked {
+        let remaining_slots = slots_locked.saturating_sub(slots_elapsed);
+
+        // Okay, one caveat to the checked arithmatic. Unsigned division is OK, is the denominator
+        // is a constant that can never be 0. This is because all `checked_div` checks for is
+        // a 0 in the denominator. However, in my programs, I still used checked_div for consistancy
+        log!(
+            "Vault will unlock in {} slots ({} epochs)",
+            remaining_slots,
+            remaining_slots / 432_000u64
+        );
+        return Err(DiamondHandsError::VaultLocked.into());
+    }
+
+    Ok(())
 }
 
 // I like to always have a good display for all of my on-chain
@@ -337,24 +729,52 @@ impl fmt::Display for Vault {
             None => "None".to_string(),
         };
 
+        // Only one of these is meaningful for a given vault, so only print that one
+        let lock_str = match self.lock_mode() {
+            Ok(LockMode::UnixTimestamp) => {
+                format!("Unlock Timestamp: {}", self.unlock_timestamp())
+            }
+            Ok(LockMode::LinearVesting) => format!(
+                "Vesting Over: {} slots ({:.3} epochs)",
+                self.slots_locked(),
+                self.slots_locked() as f64 / 432_000.0
+            ),
+            _ => format!(
+                "Slots Locked: {} ({:.3} epochs)",
+                self.slots_locked(),
+                self.slots_locked() as f64 / 432_000.0
+            ),
+        };
+        let vault_index_str = match self.vault_index() {
+            Some(vault_index) => format!("{}", vault_index),
+            None => "None (v1)".to_string(),
+        };
+        let lock_mode_str = match self.lock_mode() {
+            Ok(lock_mode) => format!("{}", lock_mode),
+            Err(_) => format!("Unknown ({})", self.lock_mode),
+        };
+
         write!(
             f,
             "Vault Account:\n\
              ├─ Discriminator: {}\n\
              ├─ Bump: {}\n\
+             ├─ Vault Index: {}\n\
              ├─ Admin: {:?}\n\
              ├─ Mint: {:?}\n\
              ├─ Vault Token Account: {:?}\n\
              ├─ Start Slot: {}\n\
-             └─ Slots Locked: {} ({:.3} epochs)",
+             ├─ Lock Mode: {}\n\
+             └─ {}",
             discriminator_str,
             self.bump,
+            vault_index_str,
             self.admin,
             self.mint,
             self.vault_token,
             self.start_slot(),
-            self.slots_locked(),
-            self.slots_locked() as f64 / 432_000.0,
+            lock_mode_str,
+            lock_str,
         )
     }
 }
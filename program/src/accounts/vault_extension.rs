@@ -0,0 +1,246 @@
+use core::fmt;
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+use pinocchio_log::log;
+
+use crate::{
+    accounts::vault::Vault,
+    errors::DiamondHandsError,
+    pod::{PodBool, PodOption, PodU64},
+    utils::{load_account_mut_unchecked, load_account_unchecked, DataLen},
+};
+
+/// We ran out of `reserved` bytes in the `Vault`, so new fields live here. This is the same
+/// trick Token-2022 uses - the base account stays exactly the same and the extension is tacked
+/// onto the end of it. Vaults locked before the extension existed are just the base `Vault`,
+/// so everything here has to have a sensible "not set" value.
+///
+/// Note: same 1-byte alignment rules as the `Vault`
+#[derive(Debug, Copy, Clone)]
+#[repr(C, packed)]
+pub struct VaultExtension {
+    /// Only used with `LockMode::LinearVesting` - nothing vests until this many slots after `start_slot`
+    cliff_slots: PodU64,
+    /// Only used with `LockMode::LinearVesting` - how many tokens have been withdrawn so far
+    tokens_claimed: PodU64,
+    /// The wallet the tokens were locked for - if this is set, only the beneficiary can take the
+    /// tokens out ( and gets the rent ), the admin that funded the vault has no withdrawal rights
+    beneficiary: PodOption<Pubkey>,
+    /// Set by `TransferAdmin` - the admin the vault PDA was derived from. The `Vault` admin is
+    /// whoever controls the vault now, but the seeds can never change
+    seed_admin: PodOption<Pubkey>,
+    /// Set on lock or with `UpdateLabel` - a name for the vault so you can tell them apart
+    label: VaultLabel,
+    /// Set on lock - the vault's tokens are counted in the mint's `MintStats`. Vaults locked
+    /// before the stats existed ( or without the stats account ) never were, so they leave the
+    /// stats alone on the way out too
+    in_mint_stats: PodBool,
+    /// Same as the `Vault` - room to grow without another extension
+    reserved: [u8; 140],
+}
+
+impl DataLen for VaultExtension {
+    const LEN: usize = core::mem::size_of::<VaultExtension>();
+}
+
+impl VaultExtension {
+    /// Where the extension starts in the vault account
+    pub const OFFSET: usize = Vault::LEN;
+
+    /// The full size of a vault account with an extension
+    pub const ACCOUNT_LEN: usize = Vault::LEN + VaultExtension::LEN;
+
+    /// Where the beneficiary pubkey starts in the vault account ( after the `PodOption` tag ),
+    /// handy for `getProgramAccounts` filters
+    pub const BENEFICIARY_OFFSET: usize =
+        Self::OFFSET + core::mem::offset_of!(VaultExtension, beneficiary) + 1;
+
+    /// `None` for vaults that were locked before extensions existed
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load(data: &[u8]) -> Result<Option<&VaultExtension>, ProgramError> {
+        match data.len() {
+            Vault::LEN => Ok(None),
+            Self::ACCOUNT_LEN => Ok(Some(load_account_unchecked::<VaultExtension>(
+                &data[Self::OFFSET..],
+            )?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// # Safety
+    /// Caller must ensure everything is 1 byte aligned
+    pub unsafe fn load_mut(data: &mut [u8]) -> Result<Option<&mut VaultExtension>, ProgramError> {
+        match data.len() {
+            Vault::LEN => Ok(None),
+            Self::ACCOUNT_LEN => Ok(Some(load_account_mut_unchecked::<VaultExtension>(
+                &mut data[Self::OFFSET..],
+            )?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // ----------------------- GETTERS ---------------------------
+    pub fn cliff_slots(&self) -> u64 {
+        self.cliff_slots.into()
+    }
+
+    pub fn tokens_claimed(&self) -> u64 {
+        self.tokens_claimed.into()
+    }
+
+    pub fn beneficiary(&self) -> Option<&Pubkey> {
+        self.beneficiary.as_ref()
+    }
+
+    pub fn seed_admin(&self) -> Option<&Pubkey> {
+        self.seed_admin.as_ref()
+    }
+
+    pub fn label(&self) -> &VaultLabel {
+        &self.label
+    }
+
+    pub fn in_mint_stats(&self) -> bool {
+        self.in_mint_stats.get()
+    }
+
+    // ----------------------- SETTERS ---------------------------
+    pub fn set_cliff_slots(&mut self, cliff_slots: u64) {
+        self.cliff_slots = PodU64::from(cliff_slots);
+    }
+
+    pub fn set_tokens_claimed(&mut self, tokens_claimed: u64) {
+        self.tokens_claimed = PodU64::from(tokens_claimed);
+    }
+
+    pub fn set_beneficiary(&mut self, beneficiary: Option<Pubkey>) {
+        self.beneficiary = PodOption::from(beneficiary);
+    }
+
+    pub fn set_seed_admin(&mut self, seed_admin: Option<Pubkey>) {
+        self.seed_admin = PodOption::from(seed_admin);
+    }
+
+    /// Note: `label` should already have passed `VaultLabel::check`
+    pub fn set_label(&mut self, label: VaultLabel) {
+        self.label = label;
+    }
+
+    pub fn set_in_mint_stats(&mut self, in_mint_stats: bool) {
+        self.in_mint_stats = PodBool::from(in_mint_stats);
+    }
+}
+
+impl fmt::Display for VaultExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let beneficiary_str = match self.beneficiary() {
+            Some(beneficiary) => format!("{:?}", beneficiary),
+            None => "None".to_string(),
+        };
+
+        let seed_admin_str = match self.seed_admin() {
+            Some(seed_admin) => format!("{:?}", seed_admin),
+            None => "None".to_string(),
+        };
+
+        write!(
+            f,
+            "Vault Extension:\n\
+             ├─ Cliff Slots: {}\n\
+             ├─ Tokens Claimed: {}\n\
+             ├─ Beneficiary: {}\n\
+             ├─ Seed Admin: {}\n\
+             ├─ Label: {}\n\
+             └─ In Mint Stats: {}",
+            self.cliff_slots(),
+            self.tokens_claimed(),
+            beneficiary_str,
+            seed_admin_str,
+            self.label(),
+            self.in_mint_stats(),
+        )
+    }
+}
+
+/// A short UTF-8 name for a vault, e.g. "team allocation". Fixed size so it fits in the
+/// extension ( and instruction data ) like everything else - `len` says how many bytes are used
+/// and the rest are zeros. A `len` of 0 is no label, which is what every vault from before
+/// labels existed reads as.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[repr(C, packed)]
+pub struct VaultLabel {
+    len: u8,
+    bytes: [u8; VaultLabel::MAX_LEN],
+}
+
+impl DataLen for VaultLabel {
+    const LEN: usize = core::mem::size_of::<VaultLabel>();
+}
+
+impl VaultLabel {
+    /// In bytes, not characters - multi-byte UTF-8 fits less
+    pub const MAX_LEN: usize = 32;
+
+    /// Copies `label` in, too long or not UTF-8 is an error instead of being cut short
+    pub fn new(label: &[u8]) -> Result<Self, ProgramError> {
+        if label.len() > Self::MAX_LEN || core::str::from_utf8(label).is_err() {
+            return Err(DiamondHandsError::InvalidLabel.into());
+        }
+
+        let mut bytes = [0u8; Self::MAX_LEN];
+        bytes[..label.len()].copy_from_slice(label);
+
+        Ok(Self {
+            len: label.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Labels come in as raw instruction data, so anyone can send a `len` past the end or bytes
+    /// that aren't UTF-8. We also want the unused bytes zeroed, so the same label is always the
+    /// same bytes
+    pub fn check(&self) -> Result<(), ProgramError> {
+        let len = self.len as usize;
+        if len > Self::MAX_LEN {
+            log!("Label is too long {} > {}", len, Self::MAX_LEN);
+            return Err(DiamondHandsError::InvalidLabel.into());
+        }
+
+        if core::str::from_utf8(&self.bytes[..len]).is_err() {
+            log!("Label is not UTF-8");
+            return Err(DiamondHandsError::InvalidLabel.into());
+        }
+
+        if self.bytes[len..].iter().any(|byte| *byte != 0) {
+            log!("Label has bytes after its length");
+            return Err(DiamondHandsError::InvalidLabel.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Never more than `MAX_LEN`, even if the `len` is garbage
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..(self.len as usize).min(Self::MAX_LEN)]
+    }
+
+    /// `None` if the bytes aren't UTF-8, which `check` makes sure never ends up in a vault
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(self.as_bytes()).ok()
+    }
+}
+
+impl fmt::Display for VaultLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_str() {
+            Some("") => write!(f, "None"),
+            Some(label) => write!(f, "{}", label),
+            None => write!(f, "Invalid ({} bytes)", self.as_bytes().len()),
+        }
+    }
+}
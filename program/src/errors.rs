@@ -1,11 +1,100 @@
+use core::fmt;
+
 use pinocchio::program_error::ProgramError;
 
+use crate::accounts::vault_extension::VaultLabel;
+
 /// Nothing special here, just some more error types
-#[derive(Clone, PartialEq)]
+///
+/// These come back as `ProgramError::Custom(code)`, so the codes are part of the public
+/// interface - only ever add new variants at the end, never reorder or reuse them.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DiamondHandsError {
-    InvalidInstruction,
-    InvalidInstructionData,
-    VaultLocked,
+    InvalidInstruction = 0,
+    InvalidInstructionData = 1,
+    VaultLocked = 2,
+    InvalidLockMode = 3,
+    NothingToWithdraw = 4,
+    /// The vault account is not owned by this program
+    InvalidVaultOwner = 5,
+    VaultNotWritable = 6,
+    /// The vault account could not be deserialized
+    InvalidVaultData = 7,
+    /// The vault account is not the PDA for its admin, mint ( and index )
+    InvalidVaultAddress = 8,
+    InvalidVaultAdmin = 9,
+    InvalidVaultMint = 10,
+    /// The vault token account does not match the one stored in the vault
+    InvalidVaultTokenAccount = 11,
+    /// Only the beneficiary ( or the admin, if there is no beneficiary ) can take tokens out
+    InvalidWithdrawAuthority = 12,
+    UnlockTimestampInPast = 13,
+    InvalidVestingSchedule = 14,
+    VaultAlreadyExists = 15,
+    InsufficientTokens = 16,
+    VaultTokenOwnerMismatch = 17,
+    VaultTokenMintMismatch = 18,
+    /// The authority's token account ( admin, beneficiary ) is not owned by the authority
+    AuthorityTokenOwnerMismatch = 19,
+    AuthorityTokenMintMismatch = 20,
+    SignerNotWritable = 21,
+    /// A basket vault can only hold `BasketVault::MAX_MINTS` different mints
+    BasketFull = 22,
+    MintNotInBasket = 23,
+    /// Labels are UTF-8 and at most `VaultLabel::MAX_LEN` bytes
+    InvalidLabel = 24,
+    /// The mint stats account is not the PDA for the mint, or isn't a `MintStats`
+    InvalidMintStats = 25,
+    /// `CrankEmpty` only sends tokens to the authority's associated token account
+    AuthorityTokenNotAssociated = 26,
+    /// `ExtendLock` can only make the vault unlock later, never earlier or at the same time
+    LockNotExtended = 27,
+    /// A deposit of zero tokens, or of everything when there is nothing
+    NothingToDeposit = 28,
+    /// `TransferAdmin` to the admin the vault already has
+    AdminUnchanged = 29,
+}
+
+impl DiamondHandsError {
+    /// The reverse of `Custom(e as u32)` - `None` if the code isn't one of ours
+    pub fn from_code(code: u32) -> Option<Self> {
+        let error = match code {
+            0 => Self::InvalidInstruction,
+            1 => Self::InvalidInstructionData,
+            2 => Self::VaultLocked,
+            3 => Self::InvalidLockMode,
+            4 => Self::NothingToWithdraw,
+            5 => Self::InvalidVaultOwner,
+            6 => Self::VaultNotWritable,
+            7 => Self::InvalidVaultData,
+            8 => Self::InvalidVaultAddress,
+            9 => Self::InvalidVaultAdmin,
+            10 => Self::InvalidVaultMint,
+            11 => Self::InvalidVaultTokenAccount,
+            12 => Self::InvalidWithdrawAuthority,
+            13 => Self::UnlockTimestampInPast,
+            14 => Self::InvalidVestingSchedule,
+            15 => Self::VaultAlreadyExists,
+            16 => Self::InsufficientTokens,
+            17 => Self::VaultTokenOwnerMismatch,
+            18 => Self::VaultTokenMintMismatch,
+            19 => Self::AuthorityTokenOwnerMismatch,
+            20 => Self::AuthorityTokenMintMismatch,
+            21 => Self::SignerNotWritable,
+            22 => Self::BasketFull,
+            23 => Self::MintNotInBasket,
+            24 => Self::InvalidLabel,
+            25 => Self::InvalidMintStats,
+            26 => Self::AuthorityTokenNotAssociated,
+            27 => Self::LockNotExtended,
+            28 => Self::NothingToDeposit,
+            29 => Self::AdminUnchanged,
+            _ => return None,
+        };
+
+        Some(error)
+    }
 }
 
 impl From<DiamondHandsError> for ProgramError {
@@ -13,3 +102,117 @@ impl From<DiamondHandsError> for ProgramError {
         Self::Custom(e as u32)
     }
 }
+
+/// Off-chain friendly messages - on-chain we still `log!` the details
+impl fmt::Display for DiamondHandsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::InvalidLabel => {
+                return write!(
+                    f,
+                    "Label has to be UTF-8 and at most {} bytes",
+                    VaultLabel::MAX_LEN
+                )
+            }
+            Self::InvalidInstruction => "Unknown instruction",
+            Self::InvalidInstructionData => "Invalid instruction data",
+            Self::VaultLocked => "Vault is still locked",
+            Self::InvalidLockMode => "The vault's lock mode does not support this",
+            Self::NothingToWithdraw => "Nothing has vested since the last withdraw",
+            Self::InvalidVaultOwner => "Vault account is not owned by the vault program",
+            Self::VaultNotWritable => "Vault account is not writable",
+            Self::InvalidVaultData => "Vault account data is invalid",
+            Self::InvalidVaultAddress => "Vault address does not match its admin and mint",
+            Self::InvalidVaultAdmin => "Signer is not the vault admin",
+            Self::InvalidVaultMint => "Mint does not match the vault",
+            Self::InvalidVaultTokenAccount => "Token account does not match the vault",
+            Self::InvalidWithdrawAuthority => {
+                "Signer is not allowed to take tokens out of this vault"
+            }
+            Self::UnlockTimestampInPast => "Unlock time has to be in the future",
+            Self::InvalidVestingSchedule => "Vesting period has to be longer than the cliff",
+            Self::VaultAlreadyExists => "Vault already exists, use deposit or a new index",
+            Self::InsufficientTokens => "Not enough tokens",
+            Self::VaultTokenOwnerMismatch => "Vault token account is not owned by the vault",
+            Self::VaultTokenMintMismatch => "Vault token account is for a different mint",
+            Self::AuthorityTokenOwnerMismatch => "Token account is not owned by the authority",
+            Self::AuthorityTokenMintMismatch => "Token account is for a different mint",
+            Self::SignerNotWritable => "Signer is not writable",
+            Self::BasketFull => "Basket already holds as many mints as it can",
+            Self::MintNotInBasket => "Mint is not in the basket",
+            Self::InvalidMintStats => "Mint stats account does not match the mint",
+            Self::AuthorityTokenNotAssociated => {
+                "Token account is not the authority's associated token account"
+            }
+            Self::LockNotExtended => "New unlock has to be later than the current one",
+            Self::NothingToDeposit => "Nothing to deposit",
+            Self::AdminUnchanged => "New admin is already the vault admin",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every variant with the code it has always had - a new variant goes at the end of this
+    // list too, and none of the existing codes should ever change
+    const CODES: [(DiamondHandsError, u32); 30] = [
+        (DiamondHandsError::InvalidInstruction, 0),
+        (DiamondHandsError::InvalidInstructionData, 1),
+        (DiamondHandsError::VaultLocked, 2),
+        (DiamondHandsError::InvalidLockMode, 3),
+        (DiamondHandsError::NothingToWithdraw, 4),
+        (DiamondHandsError::InvalidVaultOwner, 5),
+        (DiamondHandsError::VaultNotWritable, 6),
+        (DiamondHandsError::InvalidVaultData, 7),
+        (DiamondHandsError::InvalidVaultAddress, 8),
+        (DiamondHandsError::InvalidVaultAdmin, 9),
+        (DiamondHandsError::InvalidVaultMint, 10),
+        (DiamondHandsError::InvalidVaultTokenAccount, 11),
+        (DiamondHandsError::InvalidWithdrawAuthority, 12),
+        (DiamondHandsError::UnlockTimestampInPast, 13),
+        (DiamondHandsError::InvalidVestingSchedule, 14),
+        (DiamondHandsError::VaultAlreadyExists, 15),
+        (DiamondHandsError::InsufficientTokens, 16),
+        (DiamondHandsError::VaultTokenOwnerMismatch, 17),
+        (DiamondHandsError::VaultTokenMintMismatch, 18),
+        (DiamondHandsError::AuthorityTokenOwnerMismatch, 19),
+        (DiamondHandsError::AuthorityTokenMintMismatch, 20),
+        (DiamondHandsError::SignerNotWritable, 21),
+        (DiamondHandsError::BasketFull, 22),
+        (DiamondHandsError::MintNotInBasket, 23),
+        (DiamondHandsError::InvalidLabel, 24),
+        (DiamondHandsError::InvalidMintStats, 25),
+        (DiamondHandsError::AuthorityTokenNotAssociated, 26),
+        (DiamondHandsError::LockNotExtended, 27),
+        (DiamondHandsError::NothingToDeposit, 28),
+        (DiamondHandsError::AdminUnchanged, 29),
+    ];
+
+    #[test]
+    fn error_codes_round_trip() {
+        for (error, code) in CODES {
+            assert_eq!(error as u32, code);
+            assert_eq!(DiamondHandsError::from_code(code), Some(error));
+            assert_eq!(ProgramError::from(error), ProgramError::Custom(code));
+        }
+
+        // Nothing past the last variant
+        assert_eq!(DiamondHandsError::from_code(CODES.len() as u32), None);
+        assert_eq!(DiamondHandsError::from_code(u32::MAX), None);
+    }
+
+    #[test]
+    fn label_error_uses_max_len() {
+        assert_eq!(
+            DiamondHandsError::InvalidLabel.to_string(),
+            format!(
+                "Label has to be UTF-8 and at most {} bytes",
+                VaultLabel::MAX_LEN
+            )
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use pinocchio::{log::sol_log_data, pubkey::Pubkey};
+
+use crate::{
+    accounts::{
+        basket_vault::BasketVault,
+        vault::Vault,
+        vault_extension::{VaultExtension, VaultLabel},
+    },
+    pod::{PodI64, PodOption, PodU64},
+    utils::{to_bytes, DataLen, Discriminator},
+};
+
+/// `log!` is great for reading a transaction in the explorer, not so much for an indexer.
+/// So every instruction also emits one of these with `sol_log_data`, they show up in the
+/// transaction logs as `Program data: <base64>`.
+///
+/// Same rules as the accounts - 1-byte aligned and `repr(C, packed)`, the first byte is the
+/// discriminator. The layouts are part of the public interface, so only ever add new events,
+/// don't change the existing ones.
+#[repr(u8)]
+pub enum VaultEventDiscriminator {
+    Lock = 0x01,
+    Deposit = 0x02,
+    ExtendLock = 0x03,
+    Withdraw = 0x04,
+    Empty = 0x05,
+    TransferAdmin = 0x06,
+    CrankEmpty = 0x07,
+    CreateBasket = 0x08,
+    BasketDeposit = 0x09,
+    UpdateLabel = 0x0A,
+}
+
+// Same as the instructions, discriminators should never be 0
+const _: () = assert!(VaultEventDiscriminator::Lock as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::Deposit as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::ExtendLock as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::Withdraw as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::Empty as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::TransferAdmin as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::CrankEmpty as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::CreateBasket as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::BasketDeposit as u8 != 0);
+const _: () = assert!(VaultEventDiscriminator::UpdateLabel as u8 != 0);
+
+pub trait Event: DataLen + Discriminator + Sized {
+    /// Logs the raw bytes of the event
+    fn emit(&self) {
+        sol_log_data(&[unsafe { to_bytes::<Self>(self) }]);
+    }
+}
+
+/// A new vault was created and the tokens moved in
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LockEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub vault_index: PodOption<PodU64>,
+    pub beneficiary: PodOption<Pubkey>,
+    /// `LockMode` as a u8
+    pub lock_mode: u8,
+    pub tokens_locked: PodU64,
+    pub start_slot: PodU64,
+    pub slots_locked: PodU64,
+    pub cliff_slots: PodU64,
+    pub unlock_timestamp: PodI64,
+}
+
+impl LockEvent {
+    /// Everything comes from the freshly initialized vault, so the event always matches the account
+    pub fn new(
+        vault: &Pubkey,
+        vault_account: &Vault,
+        extension: Option<&VaultExtension>,
+        tokens_locked: u64,
+    ) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            admin: *vault_account.admin(),
+            mint: *vault_account.mint(),
+            vault_index: PodOption::from(vault_account.vault_index().map(PodU64::from)),
+            beneficiary: PodOption::from(
+                extension.and_then(|extension| extension.beneficiary().copied()),
+            ),
+            lock_mode: vault_account
+                .lock_mode()
+                .map_or(0, |lock_mode| lock_mode as u8),
+            tokens_locked: PodU64::from(tokens_locked),
+            start_slot: PodU64::from(vault_account.start_slot()),
+            slots_locked: PodU64::from(vault_account.slots_locked()),
+            cliff_slots: PodU64::from(extension.map_or(0, |extension| extension.cliff_slots())),
+            unlock_timestamp: PodI64::from(vault_account.unlock_timestamp()),
+        }
+    }
+}
+
+/// More tokens went into an existing vault
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepositEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub tokens_deposited: PodU64,
+}
+
+impl DepositEvent {
+    pub fn new(vault: &Pubkey, admin: &Pubkey, tokens_deposited: u64) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            admin: *admin,
+            tokens_deposited: PodU64::from(tokens_deposited),
+        }
+    }
+}
+
+/// The lock got longer - these are the new values, not the amount added
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtendLockEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub slots_locked: PodU64,
+    pub unlock_timestamp: PodI64,
+}
+
+impl ExtendLockEvent {
+    pub fn new(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        slots_locked: u64,
+        unlock_timestamp: i64,
+    ) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            authority: *authority,
+            slots_locked: PodU64::from(slots_locked),
+            unlock_timestamp: PodI64::from(unlock_timestamp),
+        }
+    }
+}
+
+/// Vested tokens came out of a linear vesting vault, `tokens_remaining` of 0 means it was closed
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WithdrawEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub tokens_withdrawn: PodU64,
+    pub tokens_remaining: PodU64,
+}
+
+impl WithdrawEvent {
+    pub fn new(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        tokens_withdrawn: u64,
+        tokens_remaining: u64,
+    ) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            authority: *authority,
+            tokens_withdrawn: PodU64::from(tokens_withdrawn),
+            tokens_remaining: PodU64::from(tokens_remaining),
+        }
+    }
+}
+
+/// The vault was emptied and closed
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmptyEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub tokens_emptied: PodU64,
+}
+
+impl EmptyEvent {
+    pub fn new(vault: &Pubkey, authority: &Pubkey, mint: &Pubkey, tokens_emptied: u64) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            authority: *authority,
+            mint: *mint,
+            tokens_emptied: PodU64::from(tokens_emptied),
+        }
+    }
+}
+
+/// Someone other than the withdraw authority emptied an unlocked vault with `CrankEmpty`,
+/// the tokens and rent still went to the `authority`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrankEmptyEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub cranker: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub tokens_emptied: PodU64,
+}
+
+impl CrankEmptyEvent {
+    pub fn new(
+        vault: &Pubkey,
+        cranker: &Pubkey,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        tokens_emptied: u64,
+    ) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            cranker: *cranker,
+            authority: *authority,
+            mint: *mint,
+            tokens_emptied: PodU64::from(tokens_emptied),
+        }
+    }
+}
+
+/// The vault was handed over with `TransferAdmin`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransferAdminEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+impl TransferAdminEvent {
+    pub fn new(vault: &Pubkey, old_admin: &Pubkey, new_admin: &Pubkey) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            old_admin: *old_admin,
+            new_admin: *new_admin,
+        }
+    }
+}
+
+/// A new, empty basket vault was created - the mints come in with `BasketDepositEvent`s
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CreateBasketEvent {
+    pub discriminator: u8,
+    pub basket: Pubkey,
+    pub admin: Pubkey,
+    pub basket_index: PodU64,
+    /// `LockMode` as a u8
+    pub lock_mode: u8,
+    pub start_slot: PodU64,
+    pub slots_locked: PodU64,
+    pub unlock_timestamp: PodI64,
+}
+
+impl CreateBasketEvent {
+    pub fn new(basket: &Pubkey, basket_account: &BasketVault) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            basket: *basket,
+            admin: *basket_account.admin(),
+            basket_index: PodU64::from(basket_account.basket_index()),
+            lock_mode: basket_account
+                .lock_mode()
+                .map_or(0, |lock_mode| lock_mode as u8),
+            start_slot: PodU64::from(basket_account.start_slot()),
+            slots_locked: PodU64::from(basket_account.slots_locked()),
+            unlock_timestamp: PodI64::from(basket_account.unlock_timestamp()),
+        }
+    }
+}
+
+/// Tokens went into a basket. Emptying a basket emits an `EmptyEvent` per mint, with the
+/// basket as the `vault`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BasketDepositEvent {
+    pub discriminator: u8,
+    pub basket: Pubkey,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub tokens_deposited: PodU64,
+}
+
+impl BasketDepositEvent {
+    pub fn new(basket: &Pubkey, admin: &Pubkey, mint: &Pubkey, tokens_deposited: u64) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            basket: *basket,
+            admin: *admin,
+            mint: *mint,
+            tokens_deposited: PodU64::from(tokens_deposited),
+        }
+    }
+}
+
+/// The vault's label was changed with `UpdateLabel`, an empty label means it was cleared
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UpdateLabelEvent {
+    pub discriminator: u8,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub label: VaultLabel,
+}
+
+impl UpdateLabelEvent {
+    pub fn new(vault: &Pubkey, admin: &Pubkey, label: VaultLabel) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault: *vault,
+            admin: *admin,
+            label,
+        }
+    }
+}
+
+// Some macro magic so we don't have to copy and paste the trait impls for every event
+macro_rules! impl_event {
+    ($event:ty, $discriminator:expr) => {
+        impl DataLen for $event {
+            const LEN: usize = core::mem::size_of::<$event>();
+        }
+
+        impl Discriminator for $event {
+            const DISCRIMINATOR: u8 = $discriminator as u8;
+        }
+
+        impl Event for $event {}
+    };
+}
+
+impl_event!(LockEvent, VaultEventDiscriminator::Lock);
+impl_event!(DepositEvent, VaultEventDiscriminator::Deposit);
+impl_event!(ExtendLockEvent, VaultEventDiscriminator::ExtendLock);
+impl_event!(WithdrawEvent, VaultEventDiscriminator::Withdraw);
+impl_event!(EmptyEvent, VaultEventDiscriminator::Empty);
+impl_event!(TransferAdminEvent, VaultEventDiscriminator::TransferAdmin);
+impl_event!(CrankEmptyEvent, VaultEventDiscriminator::CrankEmpty);
+impl_event!(CreateBasketEvent, VaultEventDiscriminator::CreateBasket);
+impl_event!(BasketDepositEvent, VaultEventDiscriminator::BasketDeposit);
+impl_event!(UpdateLabelEvent, VaultEventDiscriminator::UpdateLabel);
@@ -0,0 +1,117 @@
+use crate::{
+    events::{CrankEmptyEvent, Event},
+    instructions::{
+        empty_vault::{
+            check_authority_ata, empty_to_withdraw_authority, in_mint_stats, remove_from_mint_stats,
+        },
+        VaultProgramInstructions,
+    },
+    utils::{
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use pinocchio_log::log;
+
+/// Same as `EmptyVaultIxData`, no inputs - everything comes from the vault
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrankEmptyIxData {
+    pub discriminator: u8,
+}
+
+impl Default for CrankEmptyIxData {
+    fn default() -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+        }
+    }
+}
+
+impl CrankEmptyIxData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Safety
+    /// C style cast into bytes - to do this, the struct needs to be 1-byte aligned
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for CrankEmptyIxData {
+    const LEN: usize = core::mem::size_of::<CrankEmptyIxData>();
+}
+
+impl Discriminator for CrankEmptyIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::CrankEmpty as u8;
+}
+
+/// `EmptyVault` that anyone can call once the vault has unlocked. If the admin lost their
+/// keys ( or their phone died ) the tokens would otherwise just sit there.
+///
+/// The `cranker` only signs and pays the fee, they get nothing out of it. The tokens go to the
+/// `authority`'s token account and the rent to the `authority`, exactly the same as if they had
+/// emptied it themselves. `authority` is the withdraw authority - the beneficiary if there is
+/// one, otherwise the admin.
+pub fn process_crank_empty(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Same as `EmptyVault`, `mint_stats` is only needed for vaults counted in the stats
+    let [vault, cranker, authority, mint, authority_token, vault_token, token_program, system_program, rest @ ..] =
+        accounts
+    else {
+        log!("Not enough keys, need 8, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let mint_stats = rest.first();
+    let _ = unsafe { load_ix_data::<CrankEmptyIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_token_program(token_program)?;
+    load_system_program(system_program)?;
+    // The cranker doesn't need to be writable, the fee payer is always writable anyway
+    load_signer(cranker, false)?;
+
+    check_authority_ata(authority, mint, authority_token, token_program)?;
+
+    // Every other check is the same as `EmptyVault` - the authority just doesn't sign
+    let in_mint_stats = in_mint_stats(vault);
+    let tokens_to_empty = empty_to_withdraw_authority(
+        program_id,
+        vault,
+        authority,
+        mint,
+        authority_token,
+        vault_token,
+        token_program,
+    )?;
+
+    if in_mint_stats {
+        remove_from_mint_stats(program_id, mint_stats, mint, tokens_to_empty)?;
+    }
+
+    CrankEmptyEvent::new(
+        vault.key(),
+        cranker.key(),
+        authority.key(),
+        mint.key(),
+        tokens_to_empty,
+    )
+    .emit();
+
+    // ----------------------- Info -----------------------
+    log!(
+        "Vault cranked {} tokens ( {} ) to {}",
+        tokens_to_empty,
+        mint.key(),
+        authority.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::{
+    accounts::{basket_vault::BasketVault, vault::LockMode},
+    errors::DiamondHandsError,
+    events::{CreateBasketEvent, Event},
+    pod::{PodI64, PodU64},
+    utils::{
+        load_ix_data, load_signer, load_system_account, load_system_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_system::instructions::CreateAccount;
+
+use super::VaultProgramInstructions;
+
+/// Same deal as `LockVaultIxData` - 1-byte aligned and `repr(C, packed)`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CreateBasketIxData {
+    pub discriminator: u8,
+    pub basket_bump: u8,
+    /// `LockMode` as a u8 - only `Slot` and `UnixTimestamp`
+    pub lock_mode: u8,
+    /// Only used with `LockMode::Slot`
+    pub slots_to_lock: PodU64,
+    /// Only used with `LockMode::UnixTimestamp`
+    pub unlock_timestamp: PodI64,
+    /// The extra PDA seed, an admin can have as many baskets as they want
+    pub basket_index: PodU64,
+}
+
+impl CreateBasketIxData {
+    pub fn new(basket_bump: u8, basket_index: u64, slots_to_lock: u64) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            basket_bump,
+            lock_mode: LockMode::Slot as u8,
+            slots_to_lock: PodU64::from(slots_to_lock),
+            unlock_timestamp: PodI64::default(),
+            basket_index: PodU64::from(basket_index),
+        }
+    }
+
+    /// Same as `new`, but the basket unlocks at a unix timestamp
+    pub fn new_unix_timestamp(basket_bump: u8, basket_index: u64, unlock_timestamp: i64) -> Self {
+        Self {
+            lock_mode: LockMode::UnixTimestamp as u8,
+            unlock_timestamp: PodI64::from(unlock_timestamp),
+            ..Self::new(basket_bump, basket_index, 0)
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for CreateBasketIxData {
+    const LEN: usize = core::mem::size_of::<CreateBasketIxData>();
+}
+
+impl Discriminator for CreateBasketIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::CreateBasket as u8;
+}
+
+/// Creates an empty basket and starts the lock. Nothing is locked yet, every mint goes in with
+/// its own `DepositBasket` - they all unlock together with the basket
+pub fn process_create_basket(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [basket, admin, system_program] = accounts else {
+        log!("Not enough keys, need 3, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let ix_data = unsafe { load_ix_data::<CreateBasketIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    // Vesting doesn't make much sense for a basket, `Withdraw` works on one mint at a time
+    let lock_mode = LockMode::try_from(ix_data.lock_mode)?;
+    if lock_mode == LockMode::LinearVesting {
+        log!("Baskets can't vest, use a slot or timestamp lock");
+        return Err(DiamondHandsError::InvalidLockMode.into());
+    }
+
+    // Same as `lock_vault`, a timestamp in the past is almost certainly a mistake
+    if lock_mode == LockMode::UnixTimestamp {
+        let clock = Clock::get()?;
+        if ix_data.unlock_timestamp.get() <= clock.unix_timestamp {
+            log!(
+                "Unlock timestamp is not in the future {} <= {}",
+                ix_data.unlock_timestamp.get(),
+                clock.unix_timestamp
+            );
+            return Err(DiamondHandsError::UnlockTimestampInPast.into());
+        }
+    }
+
+    load_system_program(system_program)?;
+    if basket.is_owned_by(program_id) {
+        log!("Basket already exists {}", basket.key());
+        return Err(DiamondHandsError::VaultAlreadyExists.into());
+    }
+    load_system_account(basket, true)?;
+    load_signer(admin, true)?;
+
+    let basket_index = ix_data.basket_index.get();
+    let pda = BasketVault::create_program_address(
+        program_id,
+        admin.key(),
+        basket_index,
+        ix_data.basket_bump,
+    )?;
+    if basket.key().ne(&pda) {
+        log!(
+            "Basket account has an invalid key {} != {}",
+            basket.key(),
+            &pda
+        );
+        return Err(DiamondHandsError::InvalidVaultAddress.into());
+    }
+
+    // ----------------------- Create Basket -----------------------
+    // Just the header for now, `DepositBasket` grows it one entry at a time
+    let rent = Rent::get()?;
+    BasketVault::invoke_signed(admin.key(), basket_index, ix_data.basket_bump, |signers| {
+        CreateAccount {
+            from: admin,
+            to: basket,
+            space: BasketVault::LEN as u64,
+            owner: program_id,
+            lamports: rent.minimum_balance(BasketVault::LEN),
+        }
+        .invoke_signed(signers)
+    })?;
+
+    unsafe {
+        BasketVault::initialize(basket, admin.key(), ix_data)?;
+    }
+
+    // ----------------------- Event -----------------------
+    unsafe {
+        let data = basket.borrow_data_unchecked();
+        CreateBasketEvent::new(basket.key(), BasketVault::load(data)?).emit();
+    }
+
+    // ----------------------- Info -----------------------
+    if lock_mode == LockMode::UnixTimestamp {
+        log!(
+            "Basket {} created, locked until {}",
+            basket_index,
+            ix_data.unlock_timestamp.get()
+        );
+    } else {
+        log!(
+            "Basket {} created, locked for {} slots",
+            basket_index,
+            ix_data.slots_to_lock.get()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use crate::{
+    accounts::basket_vault::{BasketEntry, BasketVault},
+    errors::DiamondHandsError,
+    events::{BasketDepositEvent, Event},
+    instructions::VaultProgramInstructions,
+    pod::{PodOption, PodU64},
+    token_interface::{load_mint, load_token_account, TransferChecked},
+    utils::{
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_system::instructions::Transfer;
+
+/// Same as `DepositVaultIxData`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepositBasketIxData {
+    pub discriminator: u8,
+    /// `None` deposits all of the admin's tokens
+    pub tokens_to_deposit: PodOption<PodU64>,
+}
+
+impl DepositBasketIxData {
+    pub fn new(tokens_to_deposit: Option<u64>) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            tokens_to_deposit: PodOption::from(tokens_to_deposit.map(PodU64::from)),
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for DepositBasketIxData {
+    const LEN: usize = core::mem::size_of::<DepositBasketIxData>();
+}
+
+impl Discriminator for DepositBasketIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::DepositBasket as u8;
+}
+
+/// Moves one mint's tokens into the basket. The first deposit of a mint adds it to the basket,
+/// which grows the account by a `BasketEntry` - the admin pays the extra rent, and gets it back
+/// when the basket is emptied. After that the `vault_token` has to be the one in the entry.
+///
+/// Same as `DepositVault`, the lock is not touched.
+pub fn process_deposit_basket(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [basket, admin, mint, admin_token, vault_token, token_program, system_program] = accounts
+    else {
+        log!("Not enough keys, need 7, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let ix_data = unsafe { load_ix_data::<DepositBasketIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_token_program(token_program)?;
+    load_system_program(system_program)?;
+    load_signer(admin, true)?;
+
+    // Writable, a new mint makes the account bigger
+    BasketVault::check(program_id, basket, true, Some(admin))?;
+
+    let mint_decimals = {
+        let mint = load_mint(mint, token_program)?;
+        mint.decimals()
+    };
+
+    {
+        let vault_token_account = load_token_account(vault_token, token_program)?;
+        if vault_token_account.owner().ne(basket.key()) {
+            log!(
+                "Basket is not the owner of the vault token account {} != {}",
+                vault_token_account.owner(),
+                basket.key()
+            );
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
+        }
+
+        if vault_token_account.mint().ne(mint.key()) {
+            log!(
+                "Mint does not match the vault token account {} != {}",
+                vault_token_account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::VaultTokenMintMismatch.into());
+        }
+    }
+
+    let all_tokens = {
+        let admin_token_account = load_token_account(admin_token, token_program)?;
+        if admin_token_account.owner().ne(admin.key()) {
+            log!(
+                "Admin is not the owner of the admin token account {} != {}",
+                admin_token_account.owner(),
+                admin.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
+        }
+
+        if admin_token_account.mint().ne(mint.key()) {
+            log!(
+                "Mint does not match the admin token account {} != {}",
+                admin_token_account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
+        }
+
+        admin_token_account.amount()
+    };
+
+    let tokens_to_deposit = match ix_data.tokens_to_deposit.as_ref() {
+        Some(tokens_to_deposit) => tokens_to_deposit.get(),
+        None => all_tokens,
+    };
+
+    if tokens_to_deposit == 0 {
+        log!("Nothing to deposit");
+        return Err(DiamondHandsError::NothingToDeposit.into());
+    }
+
+    if tokens_to_deposit > all_tokens {
+        log!(
+            "Tokens to deposit exceed the available tokens {} > {}",
+            tokens_to_deposit,
+            all_tokens
+        );
+        return Err(DiamondHandsError::InsufficientTokens.into());
+    }
+
+    // Is the mint already in the basket? If it is, it has to be the same token account
+    let existing_vault_token = unsafe {
+        let data = basket.borrow_data_unchecked();
+        BasketVault::entries(data)?
+            .iter()
+            .find(|entry| entry.mint().eq(mint.key()))
+            .map(|entry| *entry.vault_token())
+    };
+
+    // ----------------------- Add Mint -----------------------
+    match existing_vault_token {
+        Some(existing_vault_token) => {
+            if existing_vault_token.ne(vault_token.key()) {
+                log!(
+                    "Basket token account does not match {} != {}",
+                    &existing_vault_token,
+                    vault_token.key()
+                );
+                return Err(DiamondHandsError::InvalidVaultTokenAccount.into());
+            }
+        }
+        None => {
+            // Top up the rent first, then grow - the runtime only checks rent at the end
+            let mint_count =
+                unsafe { BasketVault::load(basket.borrow_data_unchecked())? }.mint_count() as usize;
+            let rent = Rent::get()?;
+            let rent_due = rent
+                .minimum_balance(BasketVault::account_len(mint_count + 1))
+                .saturating_sub(basket.lamports());
+            if rent_due > 0 {
+                Transfer {
+                    from: admin,
+                    to: basket,
+                    lamports: rent_due,
+                }
+                .invoke()?;
+            }
+
+            unsafe {
+                BasketVault::push_entry(
+                    basket,
+                    BasketEntry::new(mint.key(), vault_token.key(), mint_decimals),
+                )?;
+            }
+
+            log!("Added {} to the basket", mint.key());
+        }
+    }
+
+    // ----------------------- Transfer Tokens -----------------------
+    TransferChecked {
+        token_program,
+        from: admin_token,
+        mint,
+        to: vault_token,
+        authority: admin,
+        amount: tokens_to_deposit,
+        decimals: mint_decimals,
+    }
+    .invoke()?;
+
+    BasketDepositEvent::new(basket.key(), admin.key(), mint.key(), tokens_to_deposit).emit();
+
+    // ----------------------- Info -----------------------
+    log!(
+        "Basket deposited {} tokens ( {} )",
+        tokens_to_deposit,
+        mint.key()
+    );
+
+    Ok(())
+}
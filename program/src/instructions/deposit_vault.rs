@@ -0,0 +1,190 @@
+use crate::{
+    accounts::{mint_stats::MintStats, vault::Vault, vault_extension::VaultExtension},
+    errors::DiamondHandsError,
+    events::{DepositEvent, Event},
+    instructions::VaultProgramInstructions,
+    pod::{PodOption, PodU64},
+    token_interface::{load_mint, load_token_account, TransferChecked},
+    utils::{
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use pinocchio_log::log;
+
+/// Same deal as `LockVaultIxData` - 1-byte aligned and `repr(C, packed)`
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepositVaultIxData {
+    pub discriminator: u8,
+    /// If this is provided, it will only deposit that amount of tokens, if its `None`
+    /// all of the admin's tokens will be deposited
+    pub tokens_to_deposit: PodOption<PodU64>,
+}
+
+impl DepositVaultIxData {
+    pub fn new(tokens_to_deposit: Option<u64>) -> Self {
+        let tokens_to_deposit = match tokens_to_deposit {
+            Some(tokens_to_deposit) => PodOption::some(PodU64::from(tokens_to_deposit)),
+            None => PodOption::none(),
+        };
+
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            tokens_to_deposit,
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for DepositVaultIxData {
+    const LEN: usize = core::mem::size_of::<DepositVaultIxData>();
+}
+
+impl Discriminator for DepositVaultIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::DepositVault as u8;
+}
+
+/// Tops up an existing vault with more tokens from the admin. The lock itself is not touched,
+/// so the new tokens unlock with the rest of the vault - this is a one-way street, just like locking.
+pub fn process_deposit_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // `mint_stats` is optional, vaults that aren't counted in the stats can still be topped up
+    // with the original 7 accounts
+    let [vault, admin, mint, admin_token, vault_token, token_program, system_program, rest @ ..] =
+        accounts
+    else {
+        log!("Not enough keys, need 7, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let mint_stats = rest.first();
+    let ix_data = unsafe { load_ix_data::<DepositVaultIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_token_program(token_program)?;
+    load_system_program(system_program)?;
+    load_signer(admin, true)?;
+
+    // Unlike `lock_vault` the vault has to exist already - this checks the admin matches
+    // and is a signer, the mint matches and the vault_token matches what is in the account
+    Vault::check(
+        program_id,
+        vault,
+        false,
+        Some(admin),
+        Some(mint),
+        Some(vault_token),
+    )?;
+
+    {
+        let _ = load_mint(mint, token_program)?;
+    }
+
+    {
+        let vault_token_account = load_token_account(vault_token, token_program)?;
+        if vault_token_account.owner().ne(vault.key()) {
+            log!(
+                "Vault is not the owner of the vault token account {} != {}",
+                vault_token_account.owner(),
+                vault.key()
+            );
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
+        }
+    }
+
+    let all_tokens = {
+        let admin_token_account = load_token_account(admin_token, token_program)?;
+        if admin_token_account.owner().ne(admin.key()) {
+            log!(
+                "Admin is not the owner of the admin token account {} != {}",
+                admin_token_account.owner(),
+                admin.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
+        }
+
+        if admin_token_account.mint().ne(mint.key()) {
+            log!(
+                "Mint does not match the admin token account {} != {}",
+                admin_token_account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
+        }
+
+        admin_token_account.amount()
+    };
+
+    // If we did not specify how many tokens to deposit, we deposit all of them
+    let tokens_to_deposit = match ix_data.tokens_to_deposit.as_ref() {
+        Some(tokens_to_deposit) => tokens_to_deposit.get(),
+        None => all_tokens,
+    };
+
+    if tokens_to_deposit == 0 {
+        log!("Nothing to deposit");
+        return Err(DiamondHandsError::NothingToDeposit.into());
+    }
+
+    if tokens_to_deposit > all_tokens {
+        log!(
+            "Tokens to deposit exceed the available tokens {} > {}",
+            tokens_to_deposit,
+            all_tokens
+        );
+        return Err(DiamondHandsError::InsufficientTokens.into());
+    }
+
+    let (mint_decimals, in_mint_stats) = unsafe {
+        let data = vault.borrow_data_unchecked();
+        let vault_account = Vault::load(data)?;
+        let vault_extension = VaultExtension::load(data)?;
+        (
+            vault_account.mint_decimals(),
+            vault_extension.is_some_and(VaultExtension::in_mint_stats),
+        )
+    };
+
+    // ----------------------- Transfer Tokens -----------------------
+    // Nothing else to do - the vault's `start_slot` and `slots_locked` stay as they are
+    TransferChecked {
+        token_program,
+        from: admin_token,
+        mint,
+        to: vault_token,
+        authority: admin,
+        amount: tokens_to_deposit,
+        decimals: mint_decimals,
+    }
+    .invoke()?;
+
+    // ----------------------- Mint Stats -----------------------
+    // Vaults from before the stats existed were never counted, so their deposits aren't either
+    if in_mint_stats {
+        let mint_stats = MintStats::required(mint_stats)?;
+        MintStats::update(program_id, mint_stats, mint, |stats| {
+            stats.add_tokens(tokens_to_deposit)
+        })?;
+    }
+
+    DepositEvent::new(vault.key(), admin.key(), tokens_to_deposit).emit();
+
+    // ----------------------- Info -----------------------
+    log!(
+        "Vault deposited {} tokens ( {} )",
+        tokens_to_deposit,
+        mint.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use crate::{
+    accounts::{basket_vault::BasketVault, vault::Vault},
+    errors::DiamondHandsError,
+    events::{EmptyEvent, Event},
+    instructions::VaultProgramInstructions,
+    token_interface::{load_mint, load_token_account, CloseAccount, TransferChecked},
+    utils::{
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+/// No inputs, the mints to empty are the remaining accounts
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmptyBasketIxData {
+    pub discriminator: u8,
+}
+
+impl Default for EmptyBasketIxData {
+    fn default() -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+        }
+    }
+}
+
+impl EmptyBasketIxData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Safety
+    /// C style cast into bytes - to do this, the struct needs to be 1-byte aligned
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for EmptyBasketIxData {
+    const LEN: usize = core::mem::size_of::<EmptyBasketIxData>();
+}
+
+impl Discriminator for EmptyBasketIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::EmptyBasket as u8;
+}
+
+/// How many remaining accounts each mint takes - [mint, admin_token, vault_token, token_program]
+pub const EMPTY_BASKET_ACCOUNTS_PER_MINT: usize = 4;
+
+/// Empties an unlocked basket, any number of mints at a time. Every mint is 4 remaining accounts
+/// ( see `EMPTY_BASKET_ACCOUNTS_PER_MINT` ), the tokens go back to the admin and the mint's
+/// vault token account is closed.
+///
+/// A basket with a lot of mints won't fit in one transaction, so every mint that was emptied is
+/// taken out of the basket and the account shrinks - the next transaction just passes the mints
+/// that are left. Once the last mint is gone the basket is closed and the rent goes back to the admin.
+pub fn process_empty_basket(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [basket, admin, system_program, remaining @ ..] = accounts else {
+        log!("Not enough keys, need at least 3, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let _ = unsafe { load_ix_data::<EmptyBasketIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_system_program(system_program)?;
+    load_signer(admin, true)?;
+
+    if remaining.len() % EMPTY_BASKET_ACCOUNTS_PER_MINT != 0 {
+        log!(
+            "Every mint needs {} accounts, got {} remaining accounts",
+            EMPTY_BASKET_ACCOUNTS_PER_MINT,
+            remaining.len()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    BasketVault::check(program_id, basket, true, Some(admin))?;
+    BasketVault::check_unlock_okay(basket)?;
+
+    let (basket_index, mint_count) = unsafe {
+        let basket_account = BasketVault::load(basket.borrow_data_unchecked())?;
+        (basket_account.basket_index(), basket_account.mint_count())
+    };
+
+    // Nothing passed in is only okay for a basket that never had anything deposited, otherwise
+    // it would just be a wasted transaction
+    if remaining.is_empty() && mint_count > 0 {
+        log!("Basket still has {} mints, pass them in", mint_count);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // ----------------------- Empty Mints -----------------------
+    for accounts in remaining.chunks_exact(EMPTY_BASKET_ACCOUNTS_PER_MINT) {
+        let [mint, admin_token, vault_token, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let tokens_emptied =
+            empty_basket_mint(basket, admin, mint, admin_token, vault_token, token_program)?;
+
+        // The vault field is the basket, the `mint` tells them apart
+        EmptyEvent::new(basket.key(), admin.key(), mint.key(), tokens_emptied).emit();
+
+        log!(
+            "Basket emptied {} tokens ( {} ) to {}",
+            tokens_emptied,
+            mint.key(),
+            admin.key()
+        );
+    }
+
+    // ----------------------- Close or Shrink Basket -----------------------
+    let mints_left = unsafe { BasketVault::load(basket.borrow_data_unchecked())? }.mint_count();
+    if mints_left == 0 {
+        // Nothing vault specific in `close`, it's just lamports and zeroing the data
+        unsafe { Vault::close(basket, admin) };
+        log!("Basket {} emptied and closed", basket_index);
+        return Ok(());
+    }
+
+    // The account is smaller now, so is the rent - the difference goes back to the admin.
+    // The basket is owned by this program, so we can move its lamports directly
+    let rent_exempt = Rent::get()?.minimum_balance(BasketVault::account_len(mints_left as usize));
+    let refund = basket.lamports().saturating_sub(rent_exempt);
+    unsafe {
+        *admin.borrow_mut_lamports_unchecked() = admin.lamports().saturating_add(refund);
+        *basket.borrow_mut_lamports_unchecked() = rent_exempt;
+    }
+
+    log!(
+        "Basket {} has {} mints left to empty",
+        basket_index,
+        mints_left
+    );
+
+    Ok(())
+}
+
+/// Empties and closes one mint's vault token account into `admin_token`, and takes the mint out
+/// of the basket. Returns the tokens emptied
+fn empty_basket_mint(
+    basket: &AccountInfo,
+    admin: &AccountInfo,
+    mint: &AccountInfo,
+    admin_token: &AccountInfo,
+    vault_token: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    load_token_program(token_program)?;
+
+    // Which entry is this? Duplicates fail here too, the first one already took it out
+    let (entry_index, mint_decimals, basket_index, bump) = unsafe {
+        let data = basket.borrow_data_unchecked();
+        let basket_account = BasketVault::load(data)?;
+        let entries = BasketVault::entries(data)?;
+        let Some(entry_index) = entries.iter().position(|entry| entry.mint().eq(mint.key())) else {
+            log!("Mint is not in the basket {}", mint.key());
+            return Err(DiamondHandsError::MintNotInBasket.into());
+        };
+
+        let entry = &entries[entry_index];
+        if entry.vault_token().ne(vault_token.key()) {
+            log!(
+                "Basket token account does not match {} != {}",
+                entry.vault_token(),
+                vault_token.key()
+            );
+            return Err(DiamondHandsError::InvalidVaultTokenAccount.into());
+        }
+
+        (
+            entry_index,
+            entry.mint_decimals(),
+            basket_account.basket_index(),
+            basket_account.bump(),
+        )
+    };
+
+    {
+        let _ = load_mint(mint, token_program)?;
+    }
+
+    {
+        let admin_token_account = load_token_account(admin_token, token_program)?;
+        if admin_token_account.mint().ne(mint.key()) {
+            log!(
+                "Admin token account does not match mint {} != {}",
+                admin_token_account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
+        }
+        if admin_token_account.owner().ne(admin.key()) {
+            log!(
+                "Admin is not the owner of the admin token account {} != {}",
+                admin_token_account.owner(),
+                admin.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
+        }
+    }
+
+    let tokens_to_empty = {
+        let vault_token_account = load_token_account(vault_token, token_program)?;
+        if vault_token_account.owner().ne(basket.key()) {
+            log!(
+                "Basket is not the owner of the vault token account {} != {}",
+                vault_token_account.owner(),
+                basket.key()
+            );
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
+        }
+        vault_token_account.amount()
+    };
+
+    BasketVault::invoke_signed(admin.key(), basket_index, bump, |signers| {
+        TransferChecked {
+            token_program,
+            from: vault_token,
+            mint,
+            to: admin_token,
+            authority: basket,
+            amount: tokens_to_empty,
+            decimals: mint_decimals,
+        }
+        .invoke_signed(signers)?;
+
+        // The token account's rent goes straight back to the admin's wallet
+        CloseAccount {
+            token_program,
+            account: vault_token,
+            destination: admin,
+            authority: basket,
+        }
+        .invoke_signed(signers)
+    })?;
+
+    unsafe { BasketVault::remove_entry(basket, entry_index)? };
+
+    Ok(tokens_to_empty)
+}
@@ -1,21 +1,19 @@
 use crate::{
-    accounts::vault::Vault,
+    accounts::{mint_stats::MintStats, vault::Vault, vault_extension::VaultExtension},
+    errors::DiamondHandsError,
+    events::{EmptyEvent, Event},
     instructions::VaultProgramInstructions,
+    token_interface::{
+        associated_token_address, load_mint, load_token_account, CloseAccount, TransferChecked,
+    },
     utils::{
-        load_account, load_ix_data, load_signer, load_system_program, load_token_program, DataLen,
-        Discriminator,
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
     },
-    vault_seed_with_bump,
 };
 use pinocchio::{
-    account_info::AccountInfo,
-    instruction::{Seed, Signer},
-    program_error::ProgramError,
-    pubkey::Pubkey,
-    ProgramResult,
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
 };
 use pinocchio_log::log;
-use pinocchio_token::state::{Mint, TokenAccount};
 
 /// No inputs needed, if you did want to add in some,
 /// make sure they are 1-byte aligned, and you use `repr(C, packed)`
@@ -58,63 +56,162 @@ impl Discriminator for EmptyVaultIxData {
 /// This will check all nessecary accounts and make sure that the vault can be emptied
 /// When it does, it will transfer all of the tokens back to the creator as well as
 /// close the Vault account and its rent will go back to the creator as well!
+///
+/// If the vault was locked for a beneficiary, the beneficiary is the `authority` instead of the
+/// creator - they sign, and get both the tokens and the rent.
 pub fn process_empty_vault(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    let [vault, admin, mint, admin_token, vault_token, token_program, system_program] = accounts
+    // `mint_stats` is optional, vaults that aren't counted in the stats can still be emptied
+    // with the original 7 accounts
+    let [vault, authority, mint, authority_token, vault_token, token_program, system_program, rest @ ..] =
+        accounts
     else {
         log!("Not enough keys, need 7, got {}", accounts.len());
         return Err(ProgramError::NotEnoughAccountKeys);
     };
+    let mint_stats = rest.first();
     let _ = unsafe { load_ix_data::<EmptyVaultIxData>(data)? };
 
     // ----------------------- CHECKS -----------------------
     load_token_program(token_program)?;
     load_system_program(system_program)?;
-    load_signer(admin, true)?;
+    load_signer(authority, true)?;
+
+    let in_mint_stats = in_mint_stats(vault);
+    let tokens_to_empty = empty_to_withdraw_authority(
+        program_id,
+        vault,
+        authority,
+        mint,
+        authority_token,
+        vault_token,
+        token_program,
+    )?;
+
+    if in_mint_stats {
+        remove_from_mint_stats(program_id, mint_stats, mint, tokens_to_empty)?;
+    }
+
+    EmptyEvent::new(vault.key(), authority.key(), mint.key(), tokens_to_empty).emit();
+
+    // ----------------------- Info -----------------------
+    // I like to be more verbose in my logging, I don't really care about the CU
+    // in one-off transactions that will not be used often. Its also reassuring to see this
+    // on the solana explorer.
+    log!(
+        "Vault emptied {} tokens ( {} ) to {}",
+        tokens_to_empty,
+        mint.key(),
+        authority.key()
+    );
+
+    Ok(())
+}
 
+/// If the vault is counted in its mint's `MintStats` - this has to be read before the vault is
+/// emptied, closing it zeros the data. Anything that isn't a vault is just `false`, it is up to
+/// `Vault::check` to reject it
+pub(crate) fn in_mint_stats(vault: &AccountInfo) -> bool {
+    let data = unsafe { vault.borrow_data_unchecked() };
+    matches!(
+        unsafe { VaultExtension::load(data) },
+        Ok(Some(extension)) if extension.in_mint_stats()
+    )
+}
+
+/// A counted vault was emptied, its tokens and the vault itself come off the mint's stats
+pub(crate) fn remove_from_mint_stats(
+    program_id: &Pubkey,
+    mint_stats: Option<&AccountInfo>,
+    mint: &AccountInfo,
+    tokens_emptied: u64,
+) -> ProgramResult {
+    let mint_stats = MintStats::required(mint_stats)?;
+    MintStats::update(program_id, mint_stats, mint, |stats| {
+        stats.remove_tokens(tokens_emptied);
+        stats.remove_vault();
+    })
+}
+
+/// `CrankEmpty` on top of `empty_to_withdraw_authority` - the authority didn't sign, so the
+/// cranker can't pick where the tokens go, they only go to the authority's ATA. Otherwise any
+/// token account the authority owns ( say one they gave a delegate ) would do
+pub(crate) fn check_authority_ata(
+    authority: &AccountInfo,
+    mint: &AccountInfo,
+    authority_token: &AccountInfo,
+    token_program: &AccountInfo,
+) -> ProgramResult {
+    let expected = associated_token_address(authority.key(), mint.key(), token_program.key());
+    if authority_token.key().ne(&expected) {
+        log!(
+            "Authority token account is not the authority's ATA {} != {}",
+            authority_token.key(),
+            &expected
+        );
+        return Err(DiamondHandsError::AuthorityTokenNotAssociated.into());
+    }
+
+    Ok(())
+}
+
+/// Everything `EmptyVault` and `CrankEmpty` have in common. Checks `authority` is the vault's
+/// withdraw authority and that the vault has unlocked, then sends all of the tokens to
+/// `authority_token` and closes the vault, the rent goes to `authority`.
+///
+/// Who signs is up to the caller, this doesn't check any signers. Returns the tokens emptied
+pub(crate) fn empty_to_withdraw_authority(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    authority: &AccountInfo,
+    mint: &AccountInfo,
+    authority_token: &AccountInfo,
+    vault_token: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<u64, ProgramError> {
     // Load and validate the mint account
     {
-        let _ = Mint::from_account_info(mint)?;
+        let _ = load_mint(mint, token_program)?;
     }
 
-    // Load and validate the admin token account - Note, I like to seperate
+    // Load and validate the authority token account - Note, I like to seperate
     // these types of checks where I load an account because the refrence to
     // the `data` is dropped after the closing bracket - otherwise you have to
     // call `drop`, which looks bad to me
     {
-        let admin_token_account = TokenAccount::from_account_info(admin_token)?;
-        if admin_token_account.mint().ne(mint.key()) {
+        let authority_token_account = load_token_account(authority_token, token_program)?;
+        if authority_token_account.mint().ne(mint.key()) {
             log!(
-                "Admin token account does not match mint {} != {}",
-                admin_token_account.mint(),
+                "Authority token account does not match mint {} != {}",
+                authority_token_account.mint(),
                 mint.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
         }
-        if admin_token_account.owner().ne(admin.key()) {
+        if authority_token_account.owner().ne(authority.key()) {
             log!(
-                "Admin is not the owner of the admin token account {} != {}",
-                admin_token_account.owner(),
-                admin.key()
+                "Authority is not the owner of the authority token account {} != {}",
+                authority_token_account.owner(),
+                authority.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
         }
     }
 
     // Same here, if we only need one variable from an account, I like to just output it
     // from a code block and `drop` the refrence to the data. Drill and extract.
     let tokens_to_empty = {
-        let vault_token_account = TokenAccount::from_account_info(vault_token)?;
+        let vault_token_account = load_token_account(vault_token, token_program)?;
         if vault_token_account.owner().ne(vault.key()) {
             log!(
                 "Vault is not the owner of the vault token account {} != {}",
                 vault_token_account.owner(),
                 vault.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
         }
         if vault_token_account.mint().ne(mint.key()) {
             log!(
@@ -122,89 +219,66 @@ pub fn process_empty_vault(
                 vault_token_account.mint(),
                 mint.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::VaultTokenMintMismatch.into());
         }
         vault_token_account.amount()
     };
 
-    // Vault Checks - it makes sure the admin matches and is a signer, the mint matches
-    // and the vault_token matches what is in the account
-    Vault::check(
-        program_id,
-        vault,
-        true,
-        Some(admin),
-        Some(mint),
-        Some(vault_token),
-    )?;
+    // Vault Checks - it makes sure the mint matches and the vault_token matches what is in the account.
+    // The admin is not checked here, if the vault was locked for a beneficiary the admin has
+    // no say in emptying it - `check_withdraw_authority_key` checks the tokens go to the right wallet
+    Vault::check(program_id, vault, true, None, Some(mint), Some(vault_token))?;
+    Vault::check_withdraw_authority_key(vault, authority.key())?;
 
     // This makes sure the vault is able to be unlocked
     Vault::check_unlock_okay(vault)?;
 
     // ----------------------- Get Signer Seeds -----------------------
     // Seeds were always kinda confusing to me in a rust format, so I just tend to copy and past what works
-    let bump: u8 = unsafe {
+    // Indexed vaults have an extra seed, `Vault::invoke_signed` takes care of that
+    // The seeds always use the admin that locked the vault, even if someone else is emptying it
+    let (admin, bump, vault_index, mint_decimals) = unsafe {
         let data = vault.borrow_data_unchecked();
-        let vault_account = load_account::<Vault>(data)?;
-        vault_account.bump()
+        let vault_account = Vault::load(data)?;
+        let vault_extension = VaultExtension::load(data)?;
+        (
+            *vault_account.seed_admin(vault_extension),
+            vault_account.bump(),
+            vault_account.vault_index(),
+            vault_account.mint_decimals(),
+        )
     };
-    let bump_bytes = [bump];
-    let seed_with_bump = vault_seed_with_bump!(admin.key(), mint.key(), &bump_bytes);
-    let signing_seeds = [
-        Seed::from(seed_with_bump[0]),
-        Seed::from(seed_with_bump[1]),
-        Seed::from(seed_with_bump[2]),
-        Seed::from(seed_with_bump[3]),
-    ];
-    Vault::check_seeds(admin.key(), mint.key(), bump, &signing_seeds)?;
-    let signer = Signer::from(&signing_seeds);
-
-    // ----------------------- Transfer Tokens -----------------------
-
-    // Transfer all of the tokens back to the admin
-    pinocchio_token::instructions::Transfer {
-        from: vault_token,
-        to: admin_token,
-        authority: vault,
-        amount: tokens_to_empty,
-    }
-    .invoke_signed(std::slice::from_ref(&signer))?;
 
-    // ----------------------- Close Vault Token Account -----------------------
+    Vault::invoke_signed(&admin, mint.key(), vault_index, bump, |signers| {
+        // ----------------------- Transfer Tokens -----------------------
 
-    // You have to have a 0, token balance before you can close
-    pinocchio_token::instructions::CloseAccount {
-        account: vault_token,
-        destination: admin_token,
-        authority: vault,
-    }
-    .invoke_signed(std::slice::from_ref(&signer))?;
+        // Transfer all of the tokens back to the authority
+        TransferChecked {
+            token_program,
+            from: vault_token,
+            mint,
+            to: authority_token,
+            authority: vault,
+            amount: tokens_to_empty,
+            decimals: mint_decimals,
+        }
+        .invoke_signed(signers)?;
 
-    // ----------------------- Close Vault -----------------------
-    unsafe {
-        // Transfer all lamports from vault to admin
-        // Note: when transferring lamports from a PDA, this is the best way to do it
-        // NOT by calling system.transfer
-        *admin.borrow_mut_lamports_unchecked() = admin.lamports().saturating_add(vault.lamports());
-        *vault.borrow_mut_lamports_unchecked() = 0;
-
-        // Zero out the vault data to mark it as closed
-        // I would always reccomend this as there could be `rehydration` attacks
-        // where if you re-initalize this account in the same transaction it could have
-        // lingering data - so, boyscouts rule here.
-        vault.borrow_mut_data_unchecked().fill(0);
-    }
+        // ----------------------- Close Vault Token Account -----------------------
 
-    // ----------------------- Info -----------------------
-    // I like to be more verbose in my logging, I don't really care about the CU
-    // in one-off transactions that will not be used often. Its also reassuring to see this
-    // on the solana explorer.
-    log!(
-        "Vault emptied {} tokens ( {} ) to {}",
-        tokens_to_empty,
-        mint.key(),
-        admin.key()
-    );
+        // You have to have a 0, token balance before you can close
+        CloseAccount {
+            token_program,
+            account: vault_token,
+            destination: authority_token,
+            authority: vault,
+        }
+        .invoke_signed(signers)
+    })?;
 
-    Ok(())
+    // ----------------------- Close Vault -----------------------
+    // All of the lamports go back to the authority
+    unsafe { Vault::close(vault, authority) };
+
+    Ok(tokens_to_empty)
 }
@@ -0,0 +1,133 @@
+use crate::{
+    accounts::vault::{LockMode, Vault},
+    errors::DiamondHandsError,
+    events::{Event, ExtendLockEvent},
+    instructions::VaultProgramInstructions,
+    pod::{PodI64, PodU64},
+    utils::{load_ix_data, DataLen, Discriminator},
+};
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use pinocchio_log::log;
+
+/// Same deal as `LockVaultIxData` - 1-byte aligned and `repr(C, packed)`
+///
+/// Only one of the fields is used, depending on the `LockMode` the vault was locked with.
+/// The lock mode itself can't be changed - comparing slots to seconds is a guessing game.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtendLockIxData {
+    pub discriminator: u8,
+    /// Only used with slot based locks ( `LockMode::Slot` and `LockMode::LinearVesting` ) - how many
+    /// slots get added on to `slots_locked`
+    pub slots_to_add: PodU64,
+    /// Only used with `LockMode::UnixTimestamp` - the new unlock timestamp, it has to be
+    /// later than the current one
+    pub unlock_timestamp: PodI64,
+}
+
+impl ExtendLockIxData {
+    pub fn new(slots_to_add: u64) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            slots_to_add: PodU64::from(slots_to_add),
+            unlock_timestamp: PodI64::default(),
+        }
+    }
+
+    /// Same as `new`, but for vaults locked until a unix timestamp
+    pub fn new_unix_timestamp(unlock_timestamp: i64) -> Self {
+        Self {
+            slots_to_add: PodU64::default(),
+            unlock_timestamp: PodI64::from(unlock_timestamp),
+            ..Self::new(0)
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for ExtendLockIxData {
+    const LEN: usize = core::mem::size_of::<ExtendLockIxData>();
+}
+
+impl Discriminator for ExtendLockIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::ExtendLock as u8;
+}
+
+/// Pushes the unlock point of a vault further out. This is diamond hands after all, so the
+/// lock can only ever get longer - anything that would unlock the vault earlier is rejected.
+pub fn process_extend_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [vault, authority] = accounts else {
+        log!("Not enough keys, need 2, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let ix_data = unsafe { load_ix_data::<ExtendLockIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    // No tokens move so we don't need the mint or token accounts. Same as emptying, it is the
+    // withdraw authority that signs - otherwise the admin could keep a beneficiary locked out forever
+    Vault::check(program_id, vault, true, None, None, None)?;
+    Vault::check_withdraw_authority(vault, authority)?;
+
+    // ----------------------- Extend Lock -----------------------
+    let data = unsafe { vault.borrow_mut_data_unchecked() };
+    let vault_account = unsafe { Vault::load_mut(data)? };
+
+    match vault_account.lock_mode()? {
+        // Stretching a vesting schedule only ever slows down vesting, so it is fine too
+        LockMode::Slot | LockMode::LinearVesting => {
+            let slots_to_add = ix_data.slots_to_add.get();
+            if slots_to_add == 0 {
+                log!("Slots to add has to be greater than 0");
+                return Err(DiamondHandsError::LockNotExtended.into());
+            }
+
+            // Checked arithmatic again - an overflow here would wrap around to an earlier unlock
+            let slots_locked = vault_account
+                .slots_locked()
+                .checked_add(slots_to_add)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            vault_account.set_slots_locked(slots_locked);
+
+            log!(
+                "Vault lock extended by {} slots to {} slots",
+                slots_to_add,
+                slots_locked
+            );
+        }
+        LockMode::UnixTimestamp => {
+            let unlock_timestamp = ix_data.unlock_timestamp.get();
+            if unlock_timestamp <= vault_account.unlock_timestamp() {
+                log!(
+                    "New unlock timestamp is not later than the current one {} <= {}",
+                    unlock_timestamp,
+                    vault_account.unlock_timestamp()
+                );
+                return Err(DiamondHandsError::LockNotExtended.into());
+            }
+            vault_account.set_unlock_timestamp(unlock_timestamp);
+
+            log!("Vault lock extended to unix timestamp {}", unlock_timestamp);
+        }
+    }
+
+    ExtendLockEvent::new(
+        vault.key(),
+        authority.key(),
+        vault_account.slots_locked(),
+        vault_account.unlock_timestamp(),
+    )
+    .emit();
+
+    Ok(())
+}
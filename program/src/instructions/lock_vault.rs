@@ -1,23 +1,27 @@
 use crate::{
-    accounts::vault::Vault,
-    pod::{PodOption, PodU64},
+    accounts::{
+        mint_stats::MintStats,
+        vault::{LockMode, Vault},
+        vault_extension::{VaultExtension, VaultLabel},
+    },
+    errors::DiamondHandsError,
+    events::{Event, LockEvent},
+    pod::{PodI64, PodOption, PodU64},
+    token_interface::{load_mint, load_token_account, TransferChecked},
     utils::{
         load_ix_data, load_signer, load_system_account, load_system_program, load_token_program,
         DataLen, Discriminator,
     },
-    vault_seed_with_bump,
 };
 use pinocchio::{
     account_info::AccountInfo,
-    instruction::{Seed, Signer},
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvars::{rent::Rent, Sysvar},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
 use pinocchio_log::log;
-use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::state::{Mint, TokenAccount};
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer};
 
 use super::VaultProgramInstructions;
 
@@ -31,12 +35,28 @@ use super::VaultProgramInstructions;
 pub struct LockVaultIxData {
     pub discriminator: u8,
     pub vault_bump: u8,
+    /// `LockMode` as a u8 - decides if `slots_to_lock` or `unlock_timestamp` is used
+    pub lock_mode: u8,
     /// Amount of slots to lock - a Solana epoch has `432_000` slots per epoch
     /// and at the time of writing its about about 2 days per epoch.
+    /// With `LockMode::LinearVesting` this is how long the tokens vest over.
     pub slots_to_lock: PodU64,
+    /// Only used with `LockMode::LinearVesting` - nothing can be withdrawn for this many slots
+    pub cliff_slots: PodU64,
+    /// Only used with `LockMode::UnixTimestamp` - the unix timestamp the vault unlocks at
+    pub unlock_timestamp: PodI64,
     /// If this is provided, it will only lock up that amount of tokens, if its `None`
     /// all tokens will be locked
     pub tokens_to_lock: PodOption<PodU64>,
+    /// If this is provided, an indexed vault is created with this index as an extra PDA seed,
+    /// if its `None` it is a v1 vault ( one per admin and mint )
+    pub vault_index: PodOption<PodU64>,
+    /// If this is provided, the tokens are locked for the beneficiary - only they can take them out
+    pub beneficiary: PodOption<Pubkey>,
+    /// A name for the vault, empty for none. It can be changed later with `UpdateLabel`
+    pub label: VaultLabel,
+    /// Bump of the mint's `MintStats` PDA, only used by the first lock for a mint ( it creates it )
+    pub mint_stats_bump: u8,
 }
 
 impl LockVaultIxData {
@@ -50,8 +70,66 @@ impl LockVaultIxData {
         Self {
             discriminator: Self::DISCRIMINATOR,
             vault_bump,
+            lock_mode: LockMode::Slot as u8,
             slots_to_lock: PodU64::from(slots_to_lock),
+            cliff_slots: PodU64::default(),
+            unlock_timestamp: PodI64::default(),
             tokens_to_lock,
+            vault_index: PodOption::none(),
+            beneficiary: PodOption::none(),
+            label: VaultLabel::default(),
+            mint_stats_bump: 0,
+        }
+    }
+
+    /// Locks on behalf of `beneficiary`, the admin still funds the vault but gives up
+    /// the right to empty it
+    pub fn with_beneficiary(self, beneficiary: Pubkey) -> Self {
+        Self {
+            beneficiary: PodOption::some(beneficiary),
+            ..self
+        }
+    }
+
+    /// Names the vault, build `label` with `VaultLabel::new`
+    pub fn with_label(self, label: VaultLabel) -> Self {
+        Self { label, ..self }
+    }
+
+    /// Locks into an indexed vault instead - note, `vault_bump` has to be the bump of the
+    /// indexed PDA, not the v1 PDA
+    pub fn with_vault_index(self, vault_index: u64) -> Self {
+        Self {
+            vault_index: PodOption::some(PodU64::from(vault_index)),
+            ..self
+        }
+    }
+
+    /// Same as `new`, but the vault unlocks at a unix timestamp instead of after a number of slots
+    pub fn new_unix_timestamp(
+        vault_bump: u8,
+        unlock_timestamp: i64,
+        tokens_to_lock: Option<u64>,
+    ) -> Self {
+        Self {
+            lock_mode: LockMode::UnixTimestamp as u8,
+            slots_to_lock: PodU64::default(),
+            unlock_timestamp: PodI64::from(unlock_timestamp),
+            ..Self::new(vault_bump, 0, tokens_to_lock)
+        }
+    }
+
+    /// Tokens vest linearly over `slots_to_vest`, with nothing vested until `cliff_slots`
+    pub fn new_linear_vesting(
+        vault_bump: u8,
+        cliff_slots: u64,
+        slots_to_vest: u64,
+        tokens_to_lock: Option<u64>,
+    ) -> Self {
+        Self {
+            lock_mode: LockMode::LinearVesting as u8,
+            cliff_slots: PodU64::from(cliff_slots),
+            ..Self::new(vault_bump, slots_to_vest, tokens_to_lock)
         }
     }
 
@@ -70,6 +148,65 @@ impl Discriminator for LockVaultIxData {
     const DISCRIMINATOR: u8 = VaultProgramInstructions::LockVault as u8;
 }
 
+impl LockVaultIxData {
+    /// Either the layout above or the original one, `LegacyLockVaultIxData` - they have
+    /// different lengths, so the length says which one it is
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() == LegacyLockVaultIxData::LEN {
+            let legacy = unsafe { load_ix_data::<LegacyLockVaultIxData>(data)? };
+            return Ok(Self::from(*legacy));
+        }
+
+        Ok(*unsafe { load_ix_data::<LockVaultIxData>(data)? })
+    }
+}
+
+/// The `LockVaultIxData` the program shipped with, before lock modes and everything after them.
+/// Clients that were never updated ( like the web app ) still send this, so it still locks -
+/// always a slot lock into a v1 vault, with none of the newer options
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LegacyLockVaultIxData {
+    pub discriminator: u8,
+    pub vault_bump: u8,
+    pub slots_to_lock: PodU64,
+    pub tokens_to_lock: PodOption<PodU64>,
+}
+
+impl LegacyLockVaultIxData {
+    pub fn new(vault_bump: u8, slots_to_lock: u64, tokens_to_lock: Option<u64>) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            vault_bump,
+            slots_to_lock: PodU64::from(slots_to_lock),
+            tokens_to_lock: PodOption::from(tokens_to_lock.map(PodU64::from)),
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for LegacyLockVaultIxData {
+    const LEN: usize = core::mem::size_of::<LegacyLockVaultIxData>();
+}
+
+impl Discriminator for LegacyLockVaultIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::LockVault as u8;
+}
+
+impl From<LegacyLockVaultIxData> for LockVaultIxData {
+    fn from(legacy: LegacyLockVaultIxData) -> Self {
+        Self {
+            tokens_to_lock: legacy.tokens_to_lock,
+            ..Self::new(legacy.vault_bump, legacy.slots_to_lock.get(), None)
+        }
+    }
+}
+
 /// This function will check accounts and lock up some `Some(ix_data.tokens_to_lock)` or all
 /// `None(ix_data.tokens_to_lock)` tokens for `ix_data.slots_to_lock`
 pub fn process_lock_vault(
@@ -77,37 +214,90 @@ pub fn process_lock_vault(
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    let [vault, admin, mint, admin_token, vault_token, token_program, system_program] = accounts
+    // `mint_stats` is optional - clients from before the stats existed send the original
+    // 7 accounts, their vaults just aren't counted
+    let [vault, admin, mint, admin_token, vault_token, token_program, system_program, rest @ ..] =
+        accounts
     else {
         log!("Not enough keys, need 7, got {}", accounts.len());
         return Err(ProgramError::NotEnoughAccountKeys);
     };
-    let ix_data = unsafe { load_ix_data::<LockVaultIxData>(data)? };
+    let mint_stats = rest.first();
+    let ix_data = &LockVaultIxData::load(data)?;
 
     // ----------------------- CHECKS -----------------------
+    let lock_mode = LockMode::try_from(ix_data.lock_mode)?;
+
+    // A timestamp lock in the past would be unlockable right away, which is almost certainly
+    // a mistake, so we refuse it
+    if lock_mode == LockMode::UnixTimestamp {
+        let clock = Clock::get()?;
+        if ix_data.unlock_timestamp.get() <= clock.unix_timestamp {
+            log!(
+                "Unlock timestamp is not in the future {} <= {}",
+                ix_data.unlock_timestamp.get(),
+                clock.unix_timestamp
+            );
+            return Err(DiamondHandsError::UnlockTimestampInPast.into());
+        }
+    }
+
+    // A vesting period of 0 would divide by 0, and a cliff after the end of the vesting
+    // period doesn't mean anything
+    if lock_mode == LockMode::LinearVesting {
+        let slots_to_vest = ix_data.slots_to_lock.get();
+        let cliff_slots = ix_data.cliff_slots.get();
+        if slots_to_vest == 0 || cliff_slots > slots_to_vest {
+            log!(
+                "Invalid vesting schedule, cliff {} slots, vesting {} slots",
+                cliff_slots,
+                slots_to_vest
+            );
+            return Err(DiamondHandsError::InvalidVestingSchedule.into());
+        }
+    }
+
+    ix_data.label.check()?;
+
     load_token_program(token_program)?;
     load_system_program(system_program)?;
     // We make sure the vault is owned by the system account, as in, not this program yet.
+    // An existing vault gets its own error, it's an easy mistake to make with indexed vaults
+    if vault.is_owned_by(program_id) {
+        log!("Vault already exists {}", vault.key());
+        return Err(DiamondHandsError::VaultAlreadyExists.into());
+    }
     load_system_account(vault, true)?;
     load_signer(admin, true)?;
 
     // Check PDA is correct
-    let pda: Pubkey =
-        Vault::create_program_address(program_id, admin.key(), mint.key(), ix_data.vault_bump)?;
+    let vault_index = ix_data
+        .vault_index
+        .as_ref()
+        .map(|vault_index| vault_index.get());
+    let pda: Pubkey = Vault::create_program_address(
+        program_id,
+        admin.key(),
+        mint.key(),
+        vault_index,
+        ix_data.vault_bump,
+    )?;
     if vault.key().ne(&pda) {
         log!(
             "Vault account has an invalid key {} != {}",
             vault.key(),
             &pda
         );
-        return Err(ProgramError::InvalidAccountData);
+        return Err(DiamondHandsError::InvalidVaultAddress.into());
     };
 
     // Load and validate the mint account
     // Note, if we only need one or a couple variable(s) from an account, I like to just output it
     // from a code block, this `drops` the refrence to the data. Drill and extract.
+    // Note: the mint and token accounts are checked against whichever token program was
+    // passed in, so this works for both Token and Token-2022 mints
     let mint_decimals = {
-        let mint = Mint::from_account_info(mint)?;
+        let mint = load_mint(mint, token_program)?;
         mint.decimals()
     };
 
@@ -116,14 +306,14 @@ pub fn process_lock_vault(
     // this will drop the refrence to the data after the closing bracket
     // which means you dont have to call `drop`
     {
-        let vault_token_account = TokenAccount::from_account_info(vault_token)?;
+        let vault_token_account = load_token_account(vault_token, token_program)?;
         if vault_token_account.owner().ne(vault.key()) {
             log!(
-                "Admin is not the owner of the vault token account {} != {}",
+                "Vault is not the owner of the vault token account {} != {}",
                 vault_token_account.owner(),
                 vault.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
         }
 
         if vault_token_account.mint().ne(mint.key()) {
@@ -132,20 +322,20 @@ pub fn process_lock_vault(
                 vault_token_account.mint(),
                 mint.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::VaultTokenMintMismatch.into());
         }
     }
 
     // Grab how many tokens are in the token account and some additional checks
     let all_tokens = {
-        let admin_token_account = TokenAccount::from_account_info(admin_token)?;
+        let admin_token_account = load_token_account(admin_token, token_program)?;
         if admin_token_account.owner().ne(admin.key()) {
             log!(
                 "Admin is not the owner of the admin token account {} != {}",
                 admin_token_account.owner(),
                 admin.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
         }
 
         if admin_token_account.mint().ne(mint.key()) {
@@ -154,7 +344,7 @@ pub fn process_lock_vault(
                 admin_token_account.mint(),
                 mint.key()
             );
-            return Err(ProgramError::InvalidAccountData);
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
         }
 
         admin_token_account.amount()
@@ -175,7 +365,7 @@ pub fn process_lock_vault(
             tokens_to_lock,
             all_tokens
         );
-        return Err(ProgramError::InvalidArgument);
+        return Err(DiamondHandsError::InsufficientTokens.into());
     }
 
     // ----------------------- Create Vault -----------------------
@@ -184,28 +374,23 @@ pub fn process_lock_vault(
     // so I tend to copy and paste what works
     let rent = Rent::get()?;
 
-    let bump_bytes = [ix_data.vault_bump];
-    let seed_with_bump = vault_seed_with_bump!(admin.key(), mint.key(), &bump_bytes);
-    let signing_seeds = [
-        Seed::from(seed_with_bump[0]),
-        Seed::from(seed_with_bump[1]),
-        Seed::from(seed_with_bump[2]),
-        Seed::from(seed_with_bump[3]),
-    ];
-
-    // Sanity check that the seeds are okay
-    Vault::check_seeds(admin.key(), mint.key(), ix_data.vault_bump, &signing_seeds)?;
-
-    let signer = Signer::from(&signing_seeds);
-
-    CreateAccount {
-        from: admin,
-        to: vault,
-        space: Vault::LEN as u64,
-        owner: program_id,
-        lamports: rent.minimum_balance(Vault::LEN),
-    }
-    .invoke_signed(std::slice::from_ref(&signer))?;
+    // `invoke_signed` sanity checks that the seeds are okay
+    Vault::invoke_signed(
+        admin.key(),
+        mint.key(),
+        vault_index,
+        ix_data.vault_bump,
+        |signers| {
+            CreateAccount {
+                from: admin,
+                to: vault,
+                space: VaultExtension::ACCOUNT_LEN as u64,
+                owner: program_id,
+                lamports: rent.minimum_balance(VaultExtension::ACCOUNT_LEN),
+            }
+            .invoke_signed(signers)
+        },
+    )?;
 
     unsafe {
         Vault::initialize(
@@ -215,6 +400,7 @@ pub fn process_lock_vault(
             ix_data,
             vault_token.key(),
             mint_decimals,
+            mint_stats.is_some(),
         )?;
     }
 
@@ -222,24 +408,163 @@ pub fn process_lock_vault(
     // Now we transfer the token to the vault - note, we did not
     // create the Token Account here, so we actually need to call
     // `create_associated_token_account` in the same transaction before
-    // this instruction
-    pinocchio_token::instructions::Transfer {
+    // this instruction. We use `TransferChecked` as Token-2022 does not support the plain
+    // `Transfer` for mints with extensions
+    TransferChecked {
+        token_program,
         from: admin_token,
+        mint,
         to: vault_token,
         authority: admin,
         amount: tokens_to_lock,
+        decimals: mint_decimals,
     }
     .invoke()?;
 
+    // ----------------------- Mint Stats -----------------------
+    // The first lock for a mint pays for its stats account, after that it's just an update
+    if let Some(mint_stats) = mint_stats {
+        if !mint_stats.is_owned_by(program_id) {
+            create_mint_stats(
+                program_id,
+                mint_stats,
+                mint,
+                admin,
+                ix_data.mint_stats_bump,
+                mint_decimals,
+                &rent,
+            )?;
+        }
+
+        // Timestamp locks are in seconds, the average is in slots - so this one is an estimate
+        let lock_slots = match lock_mode {
+            LockMode::UnixTimestamp => {
+                let seconds = ix_data
+                    .unlock_timestamp
+                    .get()
+                    .saturating_sub(Clock::get()?.unix_timestamp);
+                (seconds as u64).saturating_mul(1_000) / MintStats::ESTIMATED_MS_PER_SLOT
+            }
+            LockMode::Slot | LockMode::LinearVesting => ix_data.slots_to_lock.get(),
+        };
+
+        MintStats::update(program_id, mint_stats, mint, |stats| {
+            stats.add_lock(tokens_to_lock, lock_slots)
+        })?;
+    }
+
+    // ----------------------- Event -----------------------
+    unsafe {
+        let data = vault.borrow_data_unchecked();
+        LockEvent::new(
+            vault.key(),
+            Vault::load(data)?,
+            VaultExtension::load(data)?,
+            tokens_to_lock,
+        )
+        .emit();
+    }
+
     // ----------------------- Info -----------------------
     // Love a good completed message at the end, its more comfortable when
     // you see the transaction in the solana explorer
-    log!(
-        "Vault locked with {} tokens ( {} ), for {} slots",
-        tokens_to_lock,
-        mint.key(),
-        ix_data.slots_to_lock.get()
-    );
+    if lock_mode == LockMode::UnixTimestamp {
+        log!(
+            "Vault locked with {} tokens ( {} ), until {}",
+            tokens_to_lock,
+            mint.key(),
+            ix_data.unlock_timestamp.get()
+        );
+    } else if lock_mode == LockMode::LinearVesting {
+        log!(
+            "Vault locked with {} tokens ( {} ), vesting over {} slots with a {} slot cliff",
+            tokens_to_lock,
+            mint.key(),
+            ix_data.slots_to_lock.get(),
+            ix_data.cliff_slots.get()
+        );
+    } else {
+        log!(
+            "Vault locked with {} tokens ( {} ), for {} slots",
+            tokens_to_lock,
+            mint.key(),
+            ix_data.slots_to_lock.get()
+        );
+    }
 
     Ok(())
 }
+
+/// Creates the mint's `MintStats` with `payer` paying the rent. Anyone can send lamports to the
+/// PDA before it exists and `CreateAccount` refuses an account that has lamports already - that
+/// would block every lock for the mint, so in that case we top it up and allocate it ourselves.
+///
+/// There is one stats account per mint, so the client's bump has to be the canonical one -
+/// any other bump that happens to be on curve would be a second "stats account" for the mint
+fn create_mint_stats(
+    program_id: &Pubkey,
+    mint_stats: &AccountInfo,
+    mint: &AccountInfo,
+    payer: &AccountInfo,
+    bump: u8,
+    mint_decimals: u8,
+    rent: &Rent,
+) -> ProgramResult {
+    load_system_account(mint_stats, true)?;
+
+    let (pda, canonical_bump) = MintStats::find_program_address(program_id, mint.key());
+    if bump != canonical_bump {
+        log!(
+            "Mint stats bump is not the canonical bump {} != {}",
+            bump,
+            canonical_bump
+        );
+        return Err(DiamondHandsError::InvalidMintStats.into());
+    }
+    if mint_stats.key().ne(&pda) {
+        log!(
+            "Mint stats account has an invalid key {} != {}",
+            mint_stats.key(),
+            &pda
+        );
+        return Err(DiamondHandsError::InvalidMintStats.into());
+    }
+
+    let lamports = rent.minimum_balance(MintStats::LEN);
+    MintStats::invoke_signed(mint.key(), bump, |signers| {
+        if mint_stats.lamports() == 0 {
+            return CreateAccount {
+                from: payer,
+                to: mint_stats,
+                space: MintStats::LEN as u64,
+                owner: program_id,
+                lamports,
+            }
+            .invoke_signed(signers);
+        }
+
+        let top_up = lamports.saturating_sub(mint_stats.lamports());
+        if top_up > 0 {
+            Transfer {
+                from: payer,
+                to: mint_stats,
+                lamports: top_up,
+            }
+            .invoke()?;
+        }
+
+        Allocate {
+            account: mint_stats,
+            space: MintStats::LEN as u64,
+        }
+        .invoke_signed(signers)?;
+
+        Assign {
+            account: mint_stats,
+            owner: program_id,
+        }
+        .invoke_signed(signers)
+    })?;
+
+    unsafe { MintStats::initialize(mint_stats, mint.key(), bump, mint_decimals) }
+}
@@ -1,5 +1,14 @@
+pub mod crank_empty;
+pub mod create_basket;
+pub mod deposit_basket;
+pub mod deposit_vault;
+pub mod empty_basket;
 pub mod empty_vault;
+pub mod extend_lock;
 pub mod lock_vault;
+pub mod transfer_admin;
+pub mod update_label;
+pub mod withdraw;
 
 use crate::errors::DiamondHandsError;
 
@@ -7,6 +16,15 @@ use crate::errors::DiamondHandsError;
 pub enum VaultProgramInstructions {
     LockVault = 0x01,
     EmptyVault = 0x02,
+    DepositVault = 0x03,
+    ExtendLock = 0x04,
+    Withdraw = 0x05,
+    TransferAdmin = 0x06,
+    CrankEmpty = 0x07,
+    CreateBasket = 0x08,
+    DepositBasket = 0x09,
+    EmptyBasket = 0x0A,
+    UpdateLabel = 0x0B,
 }
 
 impl TryFrom<&u8> for VaultProgramInstructions {
@@ -16,6 +34,15 @@ impl TryFrom<&u8> for VaultProgramInstructions {
         match *value {
             0x01 => Ok(VaultProgramInstructions::LockVault),
             0x02 => Ok(VaultProgramInstructions::EmptyVault),
+            0x03 => Ok(VaultProgramInstructions::DepositVault),
+            0x04 => Ok(VaultProgramInstructions::ExtendLock),
+            0x05 => Ok(VaultProgramInstructions::Withdraw),
+            0x06 => Ok(VaultProgramInstructions::TransferAdmin),
+            0x07 => Ok(VaultProgramInstructions::CrankEmpty),
+            0x08 => Ok(VaultProgramInstructions::CreateBasket),
+            0x09 => Ok(VaultProgramInstructions::DepositBasket),
+            0x0A => Ok(VaultProgramInstructions::EmptyBasket),
+            0x0B => Ok(VaultProgramInstructions::UpdateLabel),
             _ => Err(DiamondHandsError::InvalidInstruction),
         }
     }
@@ -25,3 +52,12 @@ impl TryFrom<&u8> for VaultProgramInstructions {
 // to make sure they are never 0
 const _: () = assert!(VaultProgramInstructions::LockVault as u8 != 0);
 const _: () = assert!(VaultProgramInstructions::EmptyVault as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::DepositVault as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::ExtendLock as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::Withdraw as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::TransferAdmin as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::CrankEmpty as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::CreateBasket as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::DepositBasket as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::EmptyBasket as u8 != 0);
+const _: () = assert!(VaultProgramInstructions::UpdateLabel as u8 != 0);
@@ -0,0 +1,107 @@
+use crate::{
+    accounts::{vault::Vault, vault_extension::VaultExtension},
+    errors::DiamondHandsError,
+    events::{Event, TransferAdminEvent},
+    instructions::VaultProgramInstructions,
+    utils::{load_ix_data, load_signer, load_system_program, DataLen, Discriminator},
+};
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use pinocchio_log::log;
+
+/// No inputs needed, the new admin is an account since it has to sign
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransferAdminIxData {
+    pub discriminator: u8,
+}
+
+impl Default for TransferAdminIxData {
+    fn default() -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+        }
+    }
+}
+
+impl TransferAdminIxData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for TransferAdminIxData {
+    const LEN: usize = core::mem::size_of::<TransferAdminIxData>();
+}
+
+impl Discriminator for TransferAdminIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::TransferAdmin as u8;
+}
+
+/// Hands a vault over to a new admin, e.g. when moving to a hardware wallet. Both the current
+/// and the new admin have to sign, that way a typo can't send a vault to a key nobody has.
+///
+/// The PDA was derived from the original admin and can't change, so the first transfer saves
+/// that admin in the extension as the `seed_admin` - everything that re-derives the PDA uses
+/// it from then on. Vaults from before the extension existed are grown to fit it, paid for by
+/// the current admin. The lock is not touched.
+pub fn process_transfer_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [vault, admin, new_admin, system_program] = accounts else {
+        log!("Not enough keys, need 4, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let _ = unsafe { load_ix_data::<TransferAdminIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_system_program(system_program)?;
+    load_signer(admin, true)?;
+    load_signer(new_admin, false)?;
+
+    // Makes sure the stored admin matches and is a signer
+    Vault::check(program_id, vault, true, Some(admin), None, None)?;
+
+    if admin.key().eq(new_admin.key()) {
+        log!("New admin is already the admin {}", new_admin.key());
+        return Err(DiamondHandsError::AdminUnchanged.into());
+    }
+
+    // ----------------------- Grow Old Vaults -----------------------
+    Vault::grow_to_extension(vault, admin)?;
+
+    // ----------------------- Transfer Admin -----------------------
+    let data = unsafe { vault.borrow_mut_data_unchecked() };
+    let vault_account = unsafe { Vault::load_mut(data)? };
+    let old_admin = *vault_account.admin();
+    vault_account.set_admin(*new_admin.key());
+
+    let Some(extension) = (unsafe { VaultExtension::load_mut(data)? }) else {
+        log!("Vault has no extension");
+        return Err(DiamondHandsError::InvalidVaultData.into());
+    };
+
+    // Only the first transfer has to save it, after that the old admin is not the seed admin
+    if extension.seed_admin().is_none() {
+        extension.set_seed_admin(Some(old_admin));
+    }
+
+    TransferAdminEvent::new(vault.key(), &old_admin, new_admin.key()).emit();
+
+    log!(
+        "Vault admin transferred from {} to {}",
+        &old_admin,
+        new_admin.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::{
+    accounts::{
+        vault::Vault,
+        vault_extension::{VaultExtension, VaultLabel},
+    },
+    events::{Event, UpdateLabelEvent},
+    instructions::VaultProgramInstructions,
+    utils::{load_ix_data, load_signer, load_system_program, DataLen, Discriminator},
+};
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use pinocchio_log::log;
+
+/// The new label, an empty one clears it
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UpdateLabelIxData {
+    pub discriminator: u8,
+    pub label: VaultLabel,
+}
+
+impl UpdateLabelIxData {
+    pub fn new(label: VaultLabel) -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+            label,
+        }
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for UpdateLabelIxData {
+    const LEN: usize = core::mem::size_of::<UpdateLabelIxData>();
+}
+
+impl Discriminator for UpdateLabelIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::UpdateLabel as u8;
+}
+
+/// Renames a vault - only the admin can, the label is just for the admin to keep track of their
+/// vaults. Works whether the vault is locked or not, and the lock is not touched.
+///
+/// Same as `TransferAdmin`, vaults from before the extension existed are grown to fit it, paid
+/// for by the admin.
+pub fn process_update_label(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [vault, admin, system_program] = accounts else {
+        log!("Not enough keys, need 3, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let ix_data = unsafe { load_ix_data::<UpdateLabelIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    ix_data.label.check()?;
+    load_system_program(system_program)?;
+    load_signer(admin, true)?;
+
+    // Makes sure the stored admin matches and is a signer
+    Vault::check(program_id, vault, true, Some(admin), None, None)?;
+
+    // ----------------------- Grow Old Vaults -----------------------
+    Vault::grow_to_extension(vault, admin)?;
+
+    // ----------------------- Update Label -----------------------
+    let data = unsafe { vault.borrow_mut_data_unchecked() };
+    let Some(extension) = (unsafe { VaultExtension::load_mut(data)? }) else {
+        log!("Vault has no extension");
+        return Err(ProgramError::InvalidAccountData);
+    };
+    extension.set_label(ix_data.label);
+
+    UpdateLabelEvent::new(vault.key(), admin.key(), ix_data.label).emit();
+
+    // `check` already made sure it is UTF-8
+    log!(
+        "Vault label updated to \"{}\"",
+        ix_data.label.as_str().unwrap_or_default()
+    );
+
+    Ok(())
+}
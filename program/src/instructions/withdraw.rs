@@ -0,0 +1,240 @@
+use crate::{
+    accounts::{
+        mint_stats::MintStats,
+        vault::{LockMode, Vault},
+        vault_extension::VaultExtension,
+    },
+    errors::DiamondHandsError,
+    events::{Event, WithdrawEvent},
+    instructions::VaultProgramInstructions,
+    token_interface::{load_mint, load_token_account, CloseAccount, TransferChecked},
+    utils::{
+        load_ix_data, load_signer, load_system_program, load_token_program, DataLen, Discriminator,
+    },
+};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_log::log;
+
+/// No inputs needed - you always get everything that has vested so far
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WithdrawIxData {
+    pub discriminator: u8,
+}
+
+impl Default for WithdrawIxData {
+    fn default() -> Self {
+        Self {
+            discriminator: Self::DISCRIMINATOR,
+        }
+    }
+}
+
+impl WithdrawIxData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Safety
+    /// C style cast into bytes
+    pub unsafe fn to_bytes(&self) -> &[u8] {
+        unsafe { crate::utils::to_bytes::<Self>(self) }
+    }
+}
+
+impl DataLen for WithdrawIxData {
+    const LEN: usize = core::mem::size_of::<WithdrawIxData>();
+}
+
+impl Discriminator for WithdrawIxData {
+    const DISCRIMINATOR: u8 = VaultProgramInstructions::Withdraw as u8;
+}
+
+/// Partial withdrawals for `LockMode::LinearVesting` vaults. This transfers whatever has vested
+/// minus what has already been claimed to the withdraw authority ( the beneficiary if there is one,
+/// else the admin ), and records the new claimed total.
+/// Once the vault is drained, it is closed just like `EmptyVault`.
+pub fn process_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // `mint_stats` is optional, vaults that aren't counted in the stats can still be withdrawn from
+    // with the original 7 accounts
+    let [vault, authority, mint, authority_token, vault_token, token_program, system_program, rest @ ..] =
+        accounts
+    else {
+        log!("Not enough keys, need 7, got {}", accounts.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let mint_stats = rest.first();
+    let _ = unsafe { load_ix_data::<WithdrawIxData>(data)? };
+
+    // ----------------------- CHECKS -----------------------
+    load_token_program(token_program)?;
+    load_system_program(system_program)?;
+    load_signer(authority, true)?;
+
+    {
+        let _ = load_mint(mint, token_program)?;
+    }
+
+    {
+        let authority_token_account = load_token_account(authority_token, token_program)?;
+        if authority_token_account.mint().ne(mint.key()) {
+            log!(
+                "Authority token account does not match mint {} != {}",
+                authority_token_account.mint(),
+                mint.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenMintMismatch.into());
+        }
+        if authority_token_account.owner().ne(authority.key()) {
+            log!(
+                "Authority is not the owner of the authority token account {} != {}",
+                authority_token_account.owner(),
+                authority.key()
+            );
+            return Err(DiamondHandsError::AuthorityTokenOwnerMismatch.into());
+        }
+    }
+
+    let tokens_in_vault = {
+        let vault_token_account = load_token_account(vault_token, token_program)?;
+        if vault_token_account.owner().ne(vault.key()) {
+            log!(
+                "Vault is not the owner of the vault token account {} != {}",
+                vault_token_account.owner(),
+                vault.key()
+            );
+            return Err(DiamondHandsError::VaultTokenOwnerMismatch.into());
+        }
+        vault_token_account.amount()
+    };
+
+    // Vault Checks - it makes sure the mint matches and the vault_token matches what is in the account.
+    // The admin is not checked here, if the vault was locked for a beneficiary the admin has
+    // no say in emptying it - `check_withdraw_authority` checks the right wallet is signing
+    Vault::check(program_id, vault, true, None, Some(mint), Some(vault_token))?;
+    Vault::check_withdraw_authority(vault, authority)?;
+
+    // ----------------------- Vesting -----------------------
+    let clock = Clock::get()?;
+    let (admin, bump, vault_index, mint_decimals, tokens_to_withdraw, in_mint_stats) = unsafe {
+        let data = vault.borrow_mut_data_unchecked();
+        // `Vault` is `Copy`, so we take a copy here - that frees up `data` for the extension
+        let vault_account = *Vault::load(data)?;
+
+        if vault_account.lock_mode()? != LockMode::LinearVesting {
+            log!("Only linear vesting vaults can be withdrawn from, use EmptyVault");
+            return Err(DiamondHandsError::InvalidLockMode.into());
+        }
+
+        // Vesting vaults are always created with the extension
+        let Some(extension) = VaultExtension::load_mut(data)? else {
+            log!("Vault has no extension");
+            return Err(ProgramError::InvalidAccountData);
+        };
+
+        let tokens_claimed = extension.tokens_claimed();
+        let total_tokens = tokens_claimed.saturating_add(tokens_in_vault);
+        let tokens_vested =
+            vault_account.vested_tokens(extension.cliff_slots(), total_tokens, clock.slot);
+
+        // Saturating since extending the vesting period can push `tokens_vested` back below
+        // what has already been claimed
+        let tokens_to_withdraw = tokens_vested.saturating_sub(tokens_claimed);
+        if tokens_to_withdraw == 0 {
+            log!(
+                "Nothing to withdraw, {} vested, {} claimed",
+                tokens_vested,
+                tokens_claimed
+            );
+            return Err(DiamondHandsError::NothingToWithdraw.into());
+        }
+
+        extension.set_tokens_claimed(tokens_claimed.saturating_add(tokens_to_withdraw));
+
+        // The seeds always use the admin that locked the vault
+        (
+            *vault_account.seed_admin(Some(extension)),
+            vault_account.bump(),
+            vault_account.vault_index(),
+            vault_account.mint_decimals(),
+            tokens_to_withdraw,
+            extension.in_mint_stats(),
+        )
+    };
+
+    let is_drained = tokens_to_withdraw == tokens_in_vault;
+
+    Vault::invoke_signed(&admin, mint.key(), vault_index, bump, |signers| {
+        // ----------------------- Transfer Tokens -----------------------
+        TransferChecked {
+            token_program,
+            from: vault_token,
+            mint,
+            to: authority_token,
+            authority: vault,
+            amount: tokens_to_withdraw,
+            decimals: mint_decimals,
+        }
+        .invoke_signed(signers)?;
+
+        // ----------------------- Close Vault Token Account -----------------------
+        // Only once everything has been withdrawn
+        if is_drained {
+            CloseAccount {
+                token_program,
+                account: vault_token,
+                destination: authority_token,
+                authority: vault,
+            }
+            .invoke_signed(signers)?;
+        }
+
+        Ok(())
+    })?;
+
+    // ----------------------- Close Vault -----------------------
+    if is_drained {
+        unsafe { Vault::close(vault, authority) };
+    }
+
+    // ----------------------- Mint Stats -----------------------
+    if in_mint_stats {
+        let mint_stats = MintStats::required(mint_stats)?;
+        MintStats::update(program_id, mint_stats, mint, |stats| {
+            stats.remove_tokens(tokens_to_withdraw);
+            if is_drained {
+                stats.remove_vault();
+            }
+        })?;
+    }
+
+    let tokens_remaining = tokens_in_vault.saturating_sub(tokens_to_withdraw);
+    WithdrawEvent::new(
+        vault.key(),
+        authority.key(),
+        tokens_to_withdraw,
+        tokens_remaining,
+    )
+    .emit();
+
+    // ----------------------- Info -----------------------
+    log!(
+        "Vault withdrew {} tokens ( {} ) to {}, {} left",
+        tokens_to_withdraw,
+        mint.key(),
+        authority.key(),
+        tokens_remaining
+    );
+
+    Ok(())
+}
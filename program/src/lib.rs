@@ -3,8 +3,10 @@
 // All in all, a pretty standard entrypoint
 pub mod accounts;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod pod;
+pub mod token_interface;
 pub mod utils;
 
 pinocchio_pubkey::declare_id!("CATvuZTNuyeBkoo5Tpeqtxcn51NDLNMExWPZ5vzQxkEg");
@@ -17,7 +19,12 @@ use pinocchio_log::log;
 
 // Add crate:: prefix to access parent modules
 use crate::instructions::{
-    empty_vault::process_empty_vault, lock_vault::process_lock_vault, VaultProgramInstructions,
+    crank_empty::process_crank_empty, create_basket::process_create_basket,
+    deposit_basket::process_deposit_basket, deposit_vault::process_deposit_vault,
+    empty_basket::process_empty_basket, empty_vault::process_empty_vault,
+    extend_lock::process_extend_lock, lock_vault::process_lock_vault,
+    transfer_admin::process_transfer_admin, update_label::process_update_label,
+    withdraw::process_withdraw, VaultProgramInstructions,
 };
 
 // This is the entrypoint for the program.
@@ -45,5 +52,41 @@ fn process_instruction(
             log!("Emptying Vault");
             process_empty_vault(program_id, accounts, instruction_data)
         }
+        VaultProgramInstructions::DepositVault => {
+            log!("Depositing Vault");
+            process_deposit_vault(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::ExtendLock => {
+            log!("Extending Vault Lock");
+            process_extend_lock(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::Withdraw => {
+            log!("Withdrawing Vault");
+            process_withdraw(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::TransferAdmin => {
+            log!("Transferring Vault Admin");
+            process_transfer_admin(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::CrankEmpty => {
+            log!("Cranking Vault Empty");
+            process_crank_empty(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::CreateBasket => {
+            log!("Creating Basket Vault");
+            process_create_basket(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::DepositBasket => {
+            log!("Depositing Basket Vault");
+            process_deposit_basket(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::EmptyBasket => {
+            log!("Emptying Basket Vault");
+            process_empty_basket(program_id, accounts, instruction_data)
+        }
+        VaultProgramInstructions::UpdateLabel => {
+            log!("Updating Vault Label");
+            process_update_label(program_id, accounts, instruction_data)
+        }
     }
 }
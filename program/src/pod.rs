@@ -112,6 +112,41 @@ impl PodU64 {
     }
 }
 
+// ---------------- PODI64 ------------------------
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PodI64 {
+    pub data: [u8; 8],
+}
+
+impl From<i64> for PodI64 {
+    #[inline(always)]
+    fn from(value: i64) -> Self {
+        Self {
+            data: value.to_le_bytes(),
+        }
+    }
+}
+
+impl From<PodI64> for i64 {
+    #[inline(always)]
+    fn from(pod: PodI64) -> Self {
+        i64::from_le_bytes(pod.data)
+    }
+}
+
+impl PodI64 {
+    #[inline(always)]
+    pub fn get(&self) -> i64 {
+        i64::from_le_bytes(self.data)
+    }
+    #[inline(always)]
+    pub fn set(&mut self, value: i64) {
+        self.data = value.to_le_bytes();
+    }
+}
+
 // ---------------- PODU128 ------------------------
 
 #[repr(C)]
@@ -204,12 +239,13 @@ impl<T> PodOption<T> {
     pub const NONE_TAG: u8 = 0;
     pub const SOME_TAG: u8 = 1;
 
-    /// `None` (leaves value uninitialized)
+    /// `None` (value is zeroed, so a `None` always turns into the same bytes - instruction
+    /// data has to round trip, and we never want to read uninitialized memory in `to_bytes`)
     #[inline(always)]
     pub const fn none() -> Self {
         Self {
             tag: Self::NONE_TAG,
-            value: MaybeUninit::uninit(),
+            value: MaybeUninit::zeroed(),
         }
     }
 
@@ -387,12 +423,14 @@ mod tests {
         assert_eq!(align_of::<PodU16>(), 1);
         assert_eq!(align_of::<PodU32>(), 1);
         assert_eq!(align_of::<PodU64>(), 1);
+        assert_eq!(align_of::<PodI64>(), 1);
         assert_eq!(align_of::<PodU128>(), 1);
         assert_eq!(align_of::<PodBool>(), 1);
 
         assert_eq!(size_of::<PodU16>(), 2);
         assert_eq!(size_of::<PodU32>(), 4);
         assert_eq!(size_of::<PodU64>(), 8);
+        assert_eq!(size_of::<PodI64>(), 8);
         assert_eq!(size_of::<PodU128>(), 16);
         assert_eq!(size_of::<PodBool>(), 1);
 
@@ -451,6 +489,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn podi64_roundtrip() {
+        let vals = [0i64, 1, -1, 1_700_000_000, i64::MIN, i64::MAX];
+        for &v in &vals {
+            let p = PodI64::from(v);
+            assert_eq!(p.get(), v);
+            let back: i64 = p.into();
+            assert_eq!(back, v);
+
+            let mut m = PodI64::default();
+            m.set(v);
+            assert_eq!(m.get(), v);
+        }
+    }
+
     #[test]
     fn podu128_roundtrip() {
         let vals = [
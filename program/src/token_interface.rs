@@ -0,0 +1,205 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref},
+    instruction::{AccountMeta, Instruction, Signer},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::{self, Pubkey},
+    ProgramResult,
+};
+use pinocchio_log::log;
+use pinocchio_token::state::{Mint, TokenAccount};
+
+// `pinocchio-token` is hardwired to the legacy Token program ( both the owner checks in
+// `from_account_info` and the program id in the CPIs ). Token-2022 shares the same base
+// layout and instruction set, so this module is a thin wrapper that does the same work,
+// but against whichever token program owns the accounts.
+//
+// Note: Token-2022 extensions that need extra accounts on transfer ( transfer hooks ) are
+// not supported, the transfer will just fail.
+
+/// The Token-2022 program ID
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    pinocchio_pubkey::from_str("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The Associated Token Account program ID
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pinocchio_pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Token-2022 accounts with extensions are padded to the base token account length, then
+/// followed by a 1 byte `AccountType` - this is how we tell mints and token accounts apart.
+const ACCOUNT_TYPE_OFFSET: usize = TokenAccount::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// The multisig account length, Token-2022 never lets an extended account be this size
+const MULTISIG_LEN: usize = 355;
+
+#[inline(always)]
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id.eq(&pinocchio_token::ID) || program_id.eq(&TOKEN_2022_PROGRAM_ID)
+}
+
+#[inline(always)]
+fn has_account_type(data: &[u8], base_len: usize, account_type: u8) -> bool {
+    if data.len() == base_len {
+        return true;
+    }
+
+    data.len() > ACCOUNT_TYPE_OFFSET
+        && data.len() != MULTISIG_LEN
+        && data[ACCOUNT_TYPE_OFFSET] == account_type
+}
+
+/// The owner's associated token account for `mint` - the seeds include the token program, so
+/// legacy and Token-2022 ATAs are different addresses
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Loads a mint owned by `token_program` - legacy mints have to be exactly `Mint::LEN`,
+/// Token-2022 mints can have extensions tacked onto the end.
+pub fn load_mint<'a>(
+    info: &'a AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<Ref<'a, Mint>, ProgramError> {
+    if !info.is_owned_by(token_program.key()) {
+        log!("Mint is not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let data = info.try_borrow_data()?;
+    let is_valid = if token_program.key().eq(&TOKEN_2022_PROGRAM_ID) {
+        has_account_type(&data, Mint::LEN, ACCOUNT_TYPE_MINT)
+    } else {
+        data.len() == Mint::LEN
+    };
+    if !is_valid {
+        log!("Mint account data is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(Ref::map(data, |data| unsafe {
+        Mint::from_bytes_unchecked(data)
+    }))
+}
+
+/// Loads a token account owned by `token_program` - same deal as `load_mint`
+pub fn load_token_account<'a>(
+    info: &'a AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<Ref<'a, TokenAccount>, ProgramError> {
+    if !info.is_owned_by(token_program.key()) {
+        log!("Token account is not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let data = info.try_borrow_data()?;
+    let is_valid = if token_program.key().eq(&TOKEN_2022_PROGRAM_ID) {
+        has_account_type(&data, TokenAccount::LEN, ACCOUNT_TYPE_ACCOUNT)
+    } else {
+        data.len() == TokenAccount::LEN
+    };
+    if !is_valid {
+        log!("Token account data is invalid");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(Ref::map(data, |data| unsafe {
+        TokenAccount::from_bytes_unchecked(data)
+    }))
+}
+
+/// `TransferChecked` against either token program, the `decimals` has to match the mint
+///
+/// ### Accounts:
+///   0. `[WRITE]` The source account.
+///   1. `[]` The token mint.
+///   2. `[WRITE]` The destination account.
+///   3. `[SIGNER]` The source account's owner/delegate.
+pub struct TransferChecked<'a> {
+    pub token_program: &'a AccountInfo,
+    pub from: &'a AccountInfo,
+    pub mint: &'a AccountInfo,
+    pub to: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TransferChecked<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas: [AccountMeta; 4] = [
+            AccountMeta::writable(self.from.key()),
+            AccountMeta::readonly(self.mint.key()),
+            AccountMeta::writable(self.to.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        // [12 (TransferChecked) | amount (u64) | decimals (u8)]
+        let mut instruction_data = [0u8; 10];
+        instruction_data[0] = 12;
+        instruction_data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        instruction_data[9] = self.decimals;
+
+        let instruction = Instruction {
+            program_id: self.token_program.key(),
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[self.from, self.mint, self.to, self.authority],
+            signers,
+        )
+    }
+}
+
+/// `CloseAccount` against either token program
+///
+/// ### Accounts:
+///   0. `[WRITE]` The account to close.
+///   1. `[WRITE]` The destination account.
+///   2. `[SIGNER]` The account's owner.
+pub struct CloseAccount<'a> {
+    pub token_program: &'a AccountInfo,
+    pub account: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+}
+
+impl CloseAccount<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas: [AccountMeta; 3] = [
+            AccountMeta::writable(self.account.key()),
+            AccountMeta::writable(self.destination.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.token_program.key(),
+            accounts: &account_metas,
+            data: &[9],
+        };
+
+        invoke_signed(
+            &instruction,
+            &[self.account, self.destination, self.authority],
+            signers,
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError};
 use pinocchio_log::log;
 
-use crate::errors::DiamondHandsError;
+use crate::{errors::DiamondHandsError, token_interface::is_token_program};
 
 // All on-chain accounts implement these: `Discriminator`, `DataLen`, and `Initialized`
 // while not strictly nesscary, it helps out with consistancy. In my current on-chain
@@ -107,15 +107,17 @@ pub fn load_signer(info: &AccountInfo, expect_writable: bool) -> Result<(), Prog
     }
     if expect_writable && !info.is_writable() {
         log!("Signer is not writable");
-        return Err(ProgramError::InvalidAccountData);
+        return Err(DiamondHandsError::SignerNotWritable.into());
     }
 
     Ok(())
 }
 
+/// Either the legacy Token program or Token-2022 - the mint and token accounts are
+/// checked against whichever one is passed in
 pub fn load_token_program(info: &AccountInfo) -> Result<(), ProgramError> {
-    if info.key().ne(&pinocchio_token::id()) {
-        log!("Account is not the token program");
+    if !is_token_program(info.key()) {
+        log!("Account is not a token program");
         return Err(ProgramError::IncorrectProgramId);
     }
 
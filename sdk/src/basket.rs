@@ -0,0 +1,327 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_instruction::Instruction;
+use solana_pubkey::Pubkey;
+use solcat_diamond_hands_program::pod::PodOption;
+
+use crate::{
+    accounts::{
+        basket_vault::{BasketEntry, BasketVault},
+        vault::LockMode,
+        VaultProgramDiscriminator,
+    },
+    compute_budget::{
+        CREATE_BASKET_COMPUTE_UNITS, DEPOSIT_BASKET_COMPUTE_UNITS, EMPTY_BASKET_COMPUTE_UNITS,
+        EMPTY_BASKET_MINT_COMPUTE_UNITS,
+    },
+    create_basket_ix, deposit_basket_ix, empty_basket_ix,
+    instructions::create_basket::CreateBasketIxData,
+    reader::Reader,
+    state::LockModeDef,
+    utils::DataLen,
+};
+
+// A basket locks a handful of mints under one unlock. Every mint is its own `DepositBasket`,
+// and `EmptyBasket` takes 4 accounts per mint, so a basket with more than a few mints doesn't
+// fit in one transaction ( 1232 bytes, and 64 accounts ). The `*_txs` builders split the work
+// up into transactions that do - send them in order, each one only needs the admin to sign.
+//
+// Emptying is safe to split up, every mint that was emptied is taken out of the basket, so a
+// failed transaction can just be retried with the mints that are left.
+
+/// How many mints go in one transaction, for deposits and empties. Each mint is 3 new accounts
+/// plus the idempotent ATA instruction, this leaves plenty of room for the compute budget
+/// instructions in front
+pub const BASKET_MINTS_PER_TRANSACTION: usize = 4;
+
+/// One mint to deposit into a basket, `token_program` is the owner of the mint account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasketDeposit {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    /// `None` deposits all of the admin's tokens
+    pub tokens_to_deposit: Option<u64>,
+}
+
+/// The instructions for one transaction, and a CU limit with some headroom for them
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketTransaction {
+    pub instructions: Vec<Instruction>,
+    pub compute_units: u32,
+}
+
+/// Creates the basket and deposits every mint. The create goes in the first transaction with
+/// the first few deposits, so it is always sent first
+pub fn lock_basket_txs(
+    admin: &Pubkey,
+    ix_data: CreateBasketIxData,
+    deposits: &[BasketDeposit],
+) -> Vec<BasketTransaction> {
+    let create_ix = create_basket_ix(admin, ix_data);
+    let mut txs = deposit_basket_txs(admin, ix_data.basket_index.get(), deposits);
+
+    match txs.first_mut() {
+        Some(first) => {
+            first.instructions.insert(0, create_ix);
+            first.compute_units += CREATE_BASKET_COMPUTE_UNITS;
+        }
+        None => txs.push(BasketTransaction {
+            instructions: vec![create_ix],
+            compute_units: CREATE_BASKET_COMPUTE_UNITS,
+        }),
+    }
+
+    txs
+}
+
+/// Deposits into an existing basket, `BASKET_MINTS_PER_TRANSACTION` mints per transaction
+pub fn deposit_basket_txs(
+    admin: &Pubkey,
+    basket_index: u64,
+    deposits: &[BasketDeposit],
+) -> Vec<BasketTransaction> {
+    deposits
+        .chunks(BASKET_MINTS_PER_TRANSACTION)
+        .map(|deposits| BasketTransaction {
+            instructions: deposits
+                .iter()
+                .flat_map(|deposit| {
+                    deposit_basket_ix(
+                        admin,
+                        basket_index,
+                        &deposit.mint,
+                        &deposit.token_program,
+                        deposit.tokens_to_deposit,
+                    )
+                })
+                .collect(),
+            compute_units: DEPOSIT_BASKET_COMPUTE_UNITS * deposits.len() as u32,
+        })
+        .collect()
+}
+
+/// Empties every mint in `mints` ( mint, token program ), `BASKET_MINTS_PER_TRANSACTION` at a
+/// time. Pass every mint in the basket and the last transaction closes it - an empty basket is
+/// one transaction with no mints
+pub fn empty_basket_txs(
+    admin: &Pubkey,
+    basket_index: u64,
+    mints: &[(Pubkey, Pubkey)],
+) -> Vec<BasketTransaction> {
+    if mints.is_empty() {
+        return vec![BasketTransaction {
+            instructions: empty_basket_ix(admin, basket_index, &[]),
+            compute_units: EMPTY_BASKET_COMPUTE_UNITS,
+        }];
+    }
+
+    mints
+        .chunks(BASKET_MINTS_PER_TRANSACTION)
+        .map(|mints| BasketTransaction {
+            instructions: empty_basket_ix(admin, basket_index, mints),
+            compute_units: EMPTY_BASKET_COMPUTE_UNITS
+                + EMPTY_BASKET_MINT_COMPUTE_UNITS * mints.len() as u32,
+        })
+        .collect()
+}
+
+/// One mint in a basket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BasketEntryState {
+    pub mint: Pubkey,
+    pub vault_token: Pubkey,
+    pub mint_decimals: u8,
+}
+
+/// The owned version of a basket account, same idea as `VaultState`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BasketState {
+    pub bump: u8,
+    pub admin: Pubkey,
+    pub basket_index: u64,
+    pub start_slot: u64,
+    pub slots_locked: u64,
+    #[serde(with = "LockModeDef")]
+    pub lock_mode: LockMode,
+    pub unlock_timestamp: i64,
+    /// In no particular order, emptying a mint moves the last one into its place
+    pub entries: Vec<BasketEntryState>,
+}
+
+impl BasketState {
+    /// Vaults and baskets live under the same program, this tells them apart without parsing
+    pub fn is_basket_account(data: &[u8]) -> bool {
+        // The discriminator is a `PodOption<u8>` - the tag, then the value
+        data.get(..2)
+            == Some(&[
+                PodOption::<u8>::SOME_TAG,
+                VaultProgramDiscriminator::BasketVault as u8,
+            ])
+    }
+
+    /// Checks the discriminator, and that the length matches the number of mints
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if !Self::is_basket_account(data) {
+            return Err(anyhow!("Account is not a basket vault"));
+        }
+
+        // Same order as the `repr(C, packed)` fields of the `BasketVault`
+        let mut reader = Reader::new(data);
+        let _discriminator = reader.read_option(Reader::read_u8)?;
+        let bump = reader.read_u8()?;
+        let admin = reader.read_pubkey()?;
+        let basket_index = reader.read_u64()?;
+        let start_slot = reader.read_u64()?;
+        let slots_locked = reader.read_u64()?;
+        let lock_mode = LockMode::try_from(reader.read_u8()?)
+            .map_err(|_| anyhow!("Basket account has an invalid lock mode"))?;
+        let unlock_timestamp = reader.read_i64()?;
+        let mint_count = reader.read_u8()? as usize;
+
+        let expected_len = BasketVault::account_len(mint_count);
+        if data.len() != expected_len {
+            return Err(anyhow!(
+                "Basket account has the wrong size {}, expected {} for {} mints",
+                data.len(),
+                expected_len,
+                mint_count
+            ));
+        }
+
+        let entries = data[BasketVault::LEN..]
+            .chunks_exact(BasketEntry::LEN)
+            .map(|entry| {
+                let mut reader = Reader::new(entry);
+                Ok(BasketEntryState {
+                    mint: reader.read_pubkey()?,
+                    vault_token: reader.read_pubkey()?,
+                    mint_decimals: reader.read_u8()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            bump,
+            admin,
+            basket_index,
+            start_slot,
+            slots_locked,
+            lock_mode,
+            unlock_timestamp,
+            entries,
+        })
+    }
+
+    /// `None` for unix timestamp baskets, same as `VaultState::unlock_slot`
+    pub fn unlock_slot(&self) -> Option<u64> {
+        match self.lock_mode {
+            LockMode::UnixTimestamp => None,
+            _ => Some(self.start_slot.saturating_add(self.slots_locked)),
+        }
+    }
+
+    pub fn is_unlockable(&self, current_slot: u64) -> bool {
+        self.unlock_slot()
+            .is_some_and(|unlock_slot| current_slot >= unlock_slot)
+    }
+
+    /// `unix_timestamp` should come from the `Clock` sysvar, that is what the program checks
+    pub fn is_unlockable_at_timestamp(&self, unix_timestamp: i64) -> bool {
+        self.lock_mode == LockMode::UnixTimestamp && unix_timestamp >= self.unlock_timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accounts::vault::Vault, basket_address,
+        instructions::empty_basket::EMPTY_BASKET_ACCOUNTS_PER_MINT, utils::Discriminator,
+        VaultInstruction,
+    };
+
+    #[test]
+    fn test_basket_txs() -> Result<()> {
+        let admin = Pubkey::new_unique();
+        let deposits = (0..BASKET_MINTS_PER_TRANSACTION * 2 + 1)
+            .map(|_| BasketDeposit {
+                mint: Pubkey::new_unique(),
+                token_program: spl_token_interface::id(),
+                tokens_to_deposit: None,
+            })
+            .collect::<Vec<_>>();
+
+        // The create goes first, in front of the first batch of deposits ( ATA + deposit each )
+        let txs = lock_basket_txs(&admin, CreateBasketIxData::new(0, 1, 100), &deposits);
+        assert_eq!(txs.len(), 3);
+        assert_eq!(
+            txs[0].instructions.len(),
+            1 + 2 * BASKET_MINTS_PER_TRANSACTION
+        );
+        assert_eq!(txs[2].instructions.len(), 2);
+        let (_, basket_bump) = basket_address(&admin, 1);
+        assert_eq!(
+            VaultInstruction::unpack(&txs[0].instructions[0].data)?,
+            VaultInstruction::CreateBasket {
+                basket_bump,
+                lock_mode: LockMode::Slot,
+                slots_to_lock: 100,
+                unlock_timestamp: 0,
+                basket_index: 1,
+            }
+        );
+        assert_eq!(
+            txs[0].compute_units,
+            CREATE_BASKET_COMPUTE_UNITS
+                + DEPOSIT_BASKET_COMPUTE_UNITS * BASKET_MINTS_PER_TRANSACTION as u32
+        );
+        assert_eq!(
+            deposit_basket_txs(&admin, 1, &deposits[..1])[0].compute_units,
+            DEPOSIT_BASKET_COMPUTE_UNITS
+        );
+
+        // Emptying is one `EmptyBasket` per transaction, after an ATA for every mint in it
+        let mints = deposits
+            .iter()
+            .map(|deposit| (deposit.mint, deposit.token_program))
+            .collect::<Vec<_>>();
+        let txs = empty_basket_txs(&admin, 1, &mints);
+        assert_eq!(txs.len(), 3);
+        let empty_ix = txs[0].instructions.last().unwrap();
+        assert_eq!(
+            VaultInstruction::unpack(&empty_ix.data)?,
+            VaultInstruction::EmptyBasket
+        );
+        assert_eq!(
+            empty_ix.accounts.len(),
+            3 + EMPTY_BASKET_ACCOUNTS_PER_MINT * BASKET_MINTS_PER_TRANSACTION
+        );
+        assert_eq!(empty_ix.accounts[3].pubkey, deposits[0].mint);
+
+        // An empty basket still needs closing
+        let txs = empty_basket_txs(&admin, 1, &[]);
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].instructions.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_basket_state_rejects_bad_data() {
+        // A vault is not a basket
+        let mut data = [0u8; Vault::LEN];
+        data[0] = 1;
+        data[1] = Vault::DISCRIMINATOR;
+        assert!(!BasketState::is_basket_account(&data));
+        assert!(BasketState::from_account_data(&data).is_err());
+
+        // The mint count has to match the length
+        let mut data = vec![0u8; BasketVault::account_len(1)];
+        data[0] = 1;
+        data[1] = BasketVault::DISCRIMINATOR;
+        assert!(BasketState::is_basket_account(&data));
+        assert!(BasketState::from_account_data(&data).is_err());
+        data[BasketVault::MINT_COUNT_OFFSET] = 1;
+        assert!(BasketState::from_account_data(&data).is_ok());
+    }
+}
@@ -0,0 +1,472 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::sysvar;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::{
+    accounts::{
+        vault::{LockMode, Vault},
+        vault_extension::{VaultExtension, VaultLabel},
+    },
+    basket::{
+        deposit_basket_txs, empty_basket_txs, lock_basket_txs, BasketDeposit, BasketState,
+        BasketTransaction,
+    },
+    basket_address, clock_unix_timestamp,
+    compute_budget::{
+        recommended_unit_price, ComputeBudget, CRANK_EMPTY_COMPUTE_UNITS,
+        EMPTY_VAULT_COMPUTE_UNITS, LOCK_VAULT_COMPUTE_UNITS, UNWRAP_SOL_COMPUTE_UNITS,
+        UPDATE_LABEL_COMPUTE_UNITS, WRAP_SOL_COMPUTE_UNITS,
+    },
+    crank_empty_ix, empty_vault_as_beneficiary_ix, id,
+    instructions::{create_basket::CreateBasketIxData, lock_vault::LockVaultIxData},
+    is_token_program, lock_vault_with_ix_data_ix, mint_stats_address,
+    native_sol::{empty_sol_vault_ix, lock_sol_with_ix_data_ix},
+    state::{MintStatsState, VaultState},
+    update_label_ix,
+};
+
+/// A vault and its address, as it was when it was fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultAccount {
+    pub address: Pubkey,
+    pub state: VaultState,
+}
+
+impl VaultAccount {
+    pub fn from_account_data(address: Pubkey, data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            address,
+            state: VaultState::from_account_data(data)?,
+        })
+    }
+
+    /// For `getProgramAccounts` results - baskets and mint stats can match the same filters as
+    /// vaults, those are `None`. A vault that can't be read is still an error
+    pub fn from_program_account(address: Pubkey, data: &[u8]) -> Result<Option<Self>> {
+        if !VaultState::is_vault_account(data) {
+            return Ok(None);
+        }
+
+        Self::from_account_data(address, data).map(Some)
+    }
+}
+
+/// The `getProgramAccounts` config for every account with `pubkey` at `offset`
+/// ( `Vault::ADMIN_OFFSET`, `Vault::MINT_OFFSET`, `VaultExtension::BENEFICIARY_OFFSET` ).
+/// Public so the blocking `RpcClient` can make the same query, run the results through
+/// `VaultAccount::from_program_account`
+pub fn vaults_by_pubkey_config(offset: usize, pubkey: &Pubkey) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            offset,
+            pubkey.to_bytes().to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// Async wrapper around the nonblocking `RpcClient` for everything you'd normally do with a vault,
+/// so you don't have to deal with `getProgramAccounts` filters and blockhashes yourself
+pub struct VaultClient {
+    rpc_client: RpcClient,
+    compute_budget: ComputeBudget,
+}
+
+impl VaultClient {
+    pub fn new(rpc_url: String) -> Self {
+        Self::new_with_rpc_client(RpcClient::new(rpc_url))
+    }
+
+    /// For when you need a different commitment or timeout
+    pub fn new_with_rpc_client(rpc_client: RpcClient) -> Self {
+        Self {
+            rpc_client,
+            compute_budget: ComputeBudget::default(),
+        }
+    }
+
+    /// Put in front of every transaction this client sends. The limit is only a default, each
+    /// instruction uses its own `*_COMPUTE_UNITS` if no limit is set
+    pub fn with_compute_budget(mut self, compute_budget: ComputeBudget) -> Self {
+        self.compute_budget = compute_budget;
+        self
+    }
+
+    pub fn rpc_client(&self) -> &RpcClient {
+        &self.rpc_client
+    }
+
+    // ----------------------- READING -----------------------
+    pub async fn get_vault(&self, vault: &Pubkey) -> Result<VaultAccount> {
+        let account = self
+            .rpc_client
+            .get_account(vault)
+            .await
+            .map_err(|e| anyhow!("Could not fetch vault {}: {}", vault, e))?;
+
+        if account.owner.ne(&id()) {
+            return Err(anyhow!("{} is not a vault account", vault));
+        }
+
+        VaultAccount::from_account_data(*vault, &account.data)
+    }
+
+    /// Every vault `admin` currently controls, including ones transferred to them
+    pub async fn get_vaults_by_admin(&self, admin: &Pubkey) -> Result<Vec<VaultAccount>> {
+        self.get_vaults_by_pubkey(Vault::ADMIN_OFFSET, admin).await
+    }
+
+    /// Every vault for `mint`, from every admin
+    pub async fn get_vaults_by_mint(&self, mint: &Pubkey) -> Result<Vec<VaultAccount>> {
+        self.get_vaults_by_pubkey(Vault::MINT_OFFSET, mint).await
+    }
+
+    /// Every vault locked for `beneficiary`, only vaults with an extension can have one
+    pub async fn get_vaults_by_beneficiary(
+        &self,
+        beneficiary: &Pubkey,
+    ) -> Result<Vec<VaultAccount>> {
+        self.get_vaults_by_pubkey(VaultExtension::BENEFICIARY_OFFSET, beneficiary)
+            .await
+    }
+
+    async fn get_vaults_by_pubkey(
+        &self,
+        offset: usize,
+        pubkey: &Pubkey,
+    ) -> Result<Vec<VaultAccount>> {
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&id(), vaults_by_pubkey_config(offset, pubkey))
+            .await
+            .map_err(|e| anyhow!("Could not fetch vaults: {}", e))?;
+
+        // Anything that isn't a vault just doesn't match, rather than failing the whole lookup
+        accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                VaultAccount::from_program_account(address, &account.data).transpose()
+            })
+            .collect()
+    }
+
+    pub async fn get_basket(&self, basket: &Pubkey) -> Result<BasketState> {
+        let account = self
+            .rpc_client
+            .get_account(basket)
+            .await
+            .map_err(|e| anyhow!("Could not fetch basket {}: {}", basket, e))?;
+
+        if account.owner.ne(&id()) {
+            return Err(anyhow!("{} is not a basket account", basket));
+        }
+
+        BasketState::from_account_data(&account.data)
+    }
+
+    /// The totals for every vault of `mint` in one fetch. `None` if nobody has locked the mint
+    /// since the stats existed
+    pub async fn get_mint_stats(&self, mint: &Pubkey) -> Result<Option<MintStatsState>> {
+        let (mint_stats, _) = mint_stats_address(mint);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&mint_stats, self.rpc_client.commitment())
+            .await
+            .map_err(|e| anyhow!("Could not fetch mint stats {}: {}", mint_stats, e))?;
+
+        match account.value {
+            Some(account) if account.owner.eq(&id()) => {
+                MintStatsState::from_account_data(&account.data).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The mint's owner tells us if it is a Token or Token-2022 mint
+    pub async fn get_token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+        let mint_account = self
+            .rpc_client
+            .get_account(mint)
+            .await
+            .map_err(|e| anyhow!("Could not fetch mint {}: {}", mint, e))?;
+
+        if !is_token_program(&mint_account.owner) {
+            return Err(anyhow!(
+                "Mint is not owned by a token program: {}",
+                mint_account.owner
+            ));
+        }
+
+        Ok(mint_account.owner)
+    }
+
+    /// What to set `ComputeBudget::with_unit_price` to right now, from `getRecentPrioritizationFees`
+    /// for the accounts the transaction writes to ( the vault, say )
+    pub async fn get_recommended_unit_price(&self, accounts: &[Pubkey]) -> Result<u64> {
+        let fees = self
+            .rpc_client
+            .get_recent_prioritization_fees(accounts)
+            .await
+            .map_err(|e| anyhow!("Could not fetch prioritization fees: {}", e))?;
+
+        Ok(recommended_unit_price(&fees))
+    }
+
+    // ----------------------- SENDING -----------------------
+    /// Locks into a new vault, build `ix_data` with any of the `LockVaultIxData` constructors.
+    /// Same as `lock_vault_with_ix_data_ix`, the bump and index are filled in for you
+    pub async fn lock(
+        &self,
+        admin: &Keypair,
+        mint: &Pubkey,
+        vault_index: Option<u64>,
+        ix_data: LockVaultIxData,
+    ) -> Result<Signature> {
+        let token_program = self.get_token_program(mint).await?;
+        let ixs =
+            lock_vault_with_ix_data_ix(&admin.pubkey(), mint, vault_index, &token_program, ix_data);
+
+        self.send(&ixs, admin, LOCK_VAULT_COMPUTE_UNITS).await
+    }
+
+    /// Same as `lock`, but wraps `lamports` of the admin's SOL and locks that
+    pub async fn lock_sol(
+        &self,
+        admin: &Keypair,
+        vault_index: Option<u64>,
+        ix_data: LockVaultIxData,
+        lamports: u64,
+    ) -> Result<Signature> {
+        let ixs = lock_sol_with_ix_data_ix(&admin.pubkey(), vault_index, ix_data, lamports);
+
+        self.send(
+            &ixs,
+            admin,
+            LOCK_VAULT_COMPUTE_UNITS + WRAP_SOL_COMPUTE_UNITS,
+        )
+        .await
+    }
+
+    /// Empties an unlocked vault. `authority` is the admin, or the beneficiary if there is one -
+    /// everything else is read from the vault, so this also works after a `TransferAdmin`.
+    /// SOL vaults are unwrapped, the authority gets SOL back
+    pub async fn empty(&self, authority: &Keypair, vault: &Pubkey) -> Result<Signature> {
+        let state = self.get_vault(vault).await?.state;
+        if state.is_native_sol() {
+            let ixs = empty_sol_vault_ix(&authority.pubkey(), &state.seed_admin, state.vault_index);
+            return self
+                .send(
+                    &ixs,
+                    authority,
+                    EMPTY_VAULT_COMPUTE_UNITS + UNWRAP_SOL_COMPUTE_UNITS,
+                )
+                .await;
+        }
+        let token_program = self.get_token_program(&state.mint).await?;
+
+        // The "as beneficiary" builder is just the one that takes the seed admin separately
+        let ixs = empty_vault_as_beneficiary_ix(
+            &authority.pubkey(),
+            &state.seed_admin,
+            &state.mint,
+            state.vault_index,
+            &token_program,
+        );
+
+        self.send(&ixs, authority, EMPTY_VAULT_COMPUTE_UNITS).await
+    }
+
+    /// Empties an unlocked vault for someone else, `payer` pays the fees and gets nothing back -
+    /// the tokens and rent go to the vault's withdraw authority
+    pub async fn crank_empty(&self, payer: &Keypair, vault: &Pubkey) -> Result<Signature> {
+        let state = self.get_vault(vault).await?.state;
+        let token_program = self.get_token_program(&state.mint).await?;
+
+        let ixs = crank_empty_ix(
+            &payer.pubkey(),
+            &state.withdraw_authority(),
+            &state.seed_admin,
+            &state.mint,
+            state.vault_index,
+            &token_program,
+        );
+
+        self.send(&ixs, payer, CRANK_EMPTY_COMPUTE_UNITS).await
+    }
+
+    /// Renames a vault, `label` has to be at most `VaultLabel::MAX_LEN` bytes and an empty one
+    /// clears it. Only the admin can
+    pub async fn update_label(
+        &self,
+        admin: &Keypair,
+        vault: &Pubkey,
+        label: &str,
+    ) -> Result<Signature> {
+        let state = self.get_vault(vault).await?.state;
+        let label = VaultLabel::new(label.as_bytes()).map_err(|_| {
+            anyhow!(
+                "Label has to be at most {} bytes, got {}",
+                VaultLabel::MAX_LEN,
+                label.len()
+            )
+        })?;
+
+        let ix = update_label_ix(
+            &admin.pubkey(),
+            &state.seed_admin,
+            &state.mint,
+            state.vault_index,
+            label,
+        );
+
+        self.send(&[ix], admin, UPDATE_LABEL_COMPUTE_UNITS).await
+    }
+
+    /// Creates a basket and deposits `deposits` ( mint, amount ) into it, over as many transactions
+    /// as it takes. `None` deposits all of the admin's tokens of that mint
+    pub async fn lock_basket(
+        &self,
+        admin: &Keypair,
+        ix_data: CreateBasketIxData,
+        deposits: &[(Pubkey, Option<u64>)],
+    ) -> Result<Vec<Signature>> {
+        let deposits = self.basket_deposits(deposits).await?;
+        let txs = lock_basket_txs(&admin.pubkey(), ix_data, &deposits);
+
+        self.send_basket_txs(&txs, admin).await
+    }
+
+    /// More tokens into an existing basket, new mints are added to it
+    pub async fn deposit_basket(
+        &self,
+        admin: &Keypair,
+        basket_index: u64,
+        deposits: &[(Pubkey, Option<u64>)],
+    ) -> Result<Vec<Signature>> {
+        let deposits = self.basket_deposits(deposits).await?;
+        let txs = deposit_basket_txs(&admin.pubkey(), basket_index, &deposits);
+
+        self.send_basket_txs(&txs, admin).await
+    }
+
+    /// Empties every mint out of an unlocked basket and closes it. If one of the transactions
+    /// fails, calling this again picks up the mints that are left
+    pub async fn empty_basket(&self, admin: &Keypair, basket_index: u64) -> Result<Vec<Signature>> {
+        let (basket, _) = basket_address(&admin.pubkey(), basket_index);
+        let state = self.get_basket(&basket).await?;
+
+        let mut mints = Vec::with_capacity(state.entries.len());
+        for entry in &state.entries {
+            mints.push((entry.mint, self.get_token_program(&entry.mint).await?));
+        }
+        let txs = empty_basket_txs(&admin.pubkey(), basket_index, &mints);
+
+        self.send_basket_txs(&txs, admin).await
+    }
+
+    async fn basket_deposits(
+        &self,
+        deposits: &[(Pubkey, Option<u64>)],
+    ) -> Result<Vec<BasketDeposit>> {
+        let mut basket_deposits = Vec::with_capacity(deposits.len());
+        for (mint, tokens_to_deposit) in deposits {
+            basket_deposits.push(BasketDeposit {
+                mint: *mint,
+                token_program: self.get_token_program(mint).await?,
+                tokens_to_deposit: *tokens_to_deposit,
+            });
+        }
+
+        Ok(basket_deposits)
+    }
+
+    /// One at a time and in order, the first one might create the basket
+    async fn send_basket_txs(
+        &self,
+        txs: &[BasketTransaction],
+        payer: &Keypair,
+    ) -> Result<Vec<Signature>> {
+        let mut signatures = Vec::with_capacity(txs.len());
+        for tx in txs {
+            signatures.push(self.send(&tx.instructions, payer, tx.compute_units).await?);
+        }
+
+        Ok(signatures)
+    }
+
+    async fn send(
+        &self,
+        ixs: &[Instruction],
+        payer: &Keypair,
+        default_unit_limit: u32,
+    ) -> Result<Signature> {
+        let mut compute_budget = self.compute_budget;
+        // Only when there is a price, without one the limit doesn't buy us anything
+        if compute_budget.unit_price_micro_lamports.is_some() && compute_budget.unit_limit.is_none()
+        {
+            compute_budget.unit_limit = Some(default_unit_limit);
+        }
+        let ixs = compute_budget.prepend(ixs.iter().cloned());
+
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let tx =
+            Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[payer], blockhash);
+
+        Ok(self.rpc_client.send_and_confirm_transaction(&tx).await?)
+    }
+
+    // ----------------------- WAITING -----------------------
+    /// Polls every `poll_interval` until the vault can be emptied, then hands back the vault.
+    /// The vault is re-fetched each time, so an `ExtendLock` in the meantime is picked up.
+    /// Linear vesting vaults count as unlockable once they are fully vested.
+    pub async fn wait_until_unlockable(
+        &self,
+        vault: &Pubkey,
+        poll_interval: Duration,
+    ) -> Result<VaultAccount> {
+        loop {
+            let vault_account = self.get_vault(vault).await?;
+
+            if self.is_unlockable(&vault_account.state).await? {
+                return Ok(vault_account);
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Same checks as `Vault::check_unlock_okay` on-chain
+    async fn is_unlockable(&self, state: &VaultState) -> Result<bool> {
+        if state.lock_mode == LockMode::UnixTimestamp {
+            let unix_timestamp = self.get_unix_timestamp().await?;
+            return Ok(state.is_unlockable_at_timestamp(unix_timestamp));
+        }
+
+        Ok(state.is_unlockable(self.rpc_client.get_slot().await?))
+    }
+
+    /// The program checks the `Clock` sysvar, not our local clock, so we read the same thing
+    pub async fn get_unix_timestamp(&self) -> Result<i64> {
+        let clock_account = self.rpc_client.get_account(&sysvar::clock::ID).await?;
+
+        clock_unix_timestamp(&clock_account.data)
+    }
+}
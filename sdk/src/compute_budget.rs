@@ -0,0 +1,134 @@
+use solana_client::rpc_response::RpcPrioritizationFee;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_instruction::Instruction;
+
+// CU limits for what each builder returns ( including the idempotent ATA instructions ).
+// `test_compute_budget_limits` runs each one with its limit attached and logs the
+// `units_consumed` - a limit is the Token-2022 number ( it costs more than the legacy Token
+// program ) plus a 50% margin, rounded up. A transaction that goes over its limit fails, so
+// re-run it after changing an instruction and keep the margin.
+pub const LOCK_VAULT_COMPUTE_UNITS: u32 = 80_000;
+pub const DEPOSIT_VAULT_COMPUTE_UNITS: u32 = 30_000;
+pub const EXTEND_LOCK_COMPUTE_UNITS: u32 = 20_000;
+pub const WITHDRAW_COMPUTE_UNITS: u32 = 60_000;
+pub const EMPTY_VAULT_COMPUTE_UNITS: u32 = 60_000;
+pub const CRANK_EMPTY_COMPUTE_UNITS: u32 = 60_000;
+pub const TRANSFER_ADMIN_COMPUTE_UNITS: u32 = 20_000;
+pub const UPDATE_LABEL_COMPUTE_UNITS: u32 = 15_000;
+// On top of the above for the `native_sol` builders, wrapping creates the wSOL token account
+pub const WRAP_SOL_COMPUTE_UNITS: u32 = 30_000;
+pub const UNWRAP_SOL_COMPUTE_UNITS: u32 = 5_000;
+// Baskets are per mint - every deposit is its own `DepositBasket`, and `EmptyBasket` is the
+// basket itself plus every mint in it. See `basket::BasketTransaction`
+pub const CREATE_BASKET_COMPUTE_UNITS: u32 = 20_000;
+pub const DEPOSIT_BASKET_COMPUTE_UNITS: u32 = 60_000;
+pub const EMPTY_BASKET_COMPUTE_UNITS: u32 = 10_000;
+pub const EMPTY_BASKET_MINT_COMPUTE_UNITS: u32 = 60_000;
+
+/// The `SetComputeUnitLimit` / `SetComputeUnitPrice` instructions to put in front of a
+/// transaction, so it doesn't sit in the queue when the network is busy. Anything left as
+/// `None` is left to the runtime default.
+///
+/// The priority fee is `unit_limit * unit_price`, so always set a limit along with a price -
+/// otherwise you pay for the default 200k CU per instruction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price_micro_lamports: Option<u64>,
+}
+
+impl ComputeBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_unit_limit(mut self, unit_limit: u32) -> Self {
+        self.unit_limit = Some(unit_limit);
+        self
+    }
+
+    pub fn with_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.unit_price_micro_lamports = Some(micro_lamports);
+        self
+    }
+
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let limit = self
+            .unit_limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit);
+        let price = self
+            .unit_price_micro_lamports
+            .map(ComputeBudgetInstruction::set_compute_unit_price);
+
+        limit.into_iter().chain(price).collect()
+    }
+
+    /// `ixs` with the compute budget instructions in front, e.g.
+    /// `budget.prepend(lock_vault_ix(..))`
+    pub fn prepend(&self, ixs: impl IntoIterator<Item = Instruction>) -> Vec<Instruction> {
+        self.instructions().into_iter().chain(ixs).collect()
+    }
+}
+
+/// A unit price from `getRecentPrioritizationFees` - the 75th percentile, so we land ahead of
+/// most of the recent transactions that touched the same accounts without overpaying for the
+/// odd spike. Zero if there are no samples
+pub fn recommended_unit_price(fees: &[RpcPrioritizationFee]) -> u64 {
+    let mut fees: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+    if fees.is_empty() {
+        return 0;
+    }
+
+    fees.sort_unstable();
+    fees[(fees.len() - 1) * 3 / 4]
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_pubkey::Pubkey;
+
+    use super::*;
+    use crate::{extend_lock_ix, id};
+
+    #[test]
+    fn test_compute_budget_instructions() {
+        assert!(ComputeBudget::default().instructions().is_empty());
+        assert_eq!(
+            ComputeBudget::new()
+                .with_unit_limit(50_000)
+                .instructions()
+                .len(),
+            1
+        );
+
+        // The budget goes in front, the vault instructions keep their order
+        let ix = extend_lock_ix(&Pubkey::new_unique(), &Pubkey::new_unique(), None, 5);
+        let ixs = ComputeBudget::new()
+            .with_unit_limit(50_000)
+            .with_unit_price(1_000)
+            .prepend([ix.clone()]);
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[2], ix);
+        assert_ne!(ixs[0].program_id, id());
+        assert_eq!(ixs[0].program_id, ixs[1].program_id);
+    }
+
+    #[test]
+    fn test_recommended_unit_price() {
+        let fees = |prioritization_fees: &[u64]| {
+            prioritization_fees
+                .iter()
+                .enumerate()
+                .map(|(slot, &prioritization_fee)| RpcPrioritizationFee {
+                    slot: slot as u64,
+                    prioritization_fee,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(recommended_unit_price(&[]), 0);
+        assert_eq!(recommended_unit_price(&fees(&[7])), 7);
+        // 75th percentile, whatever order the RPC hands them back in
+        assert_eq!(recommended_unit_price(&fees(&[500, 0, 300, 100, 200])), 300);
+    }
+}
@@ -1,24 +1,118 @@
 use anyhow::Result;
-use solana_instruction::{AccountMeta, Instruction};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
 use solana_pubkey::Pubkey;
 use spl_associated_token_account_interface::{
-    address::get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    address::get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
 
+pub mod basket;
+pub use basket::BasketState;
+
+pub mod client;
+pub use client::{VaultAccount, VaultClient};
+
+pub mod compute_budget;
+pub use compute_budget::ComputeBudget;
+
+pub mod native_sol;
+pub use native_sol::{is_native_mint, native_mint};
+
+pub mod state;
+pub use state::{MintStatsState, VaultState};
+
+pub mod vault_instruction;
+pub use vault_instruction::VaultInstruction;
+
+mod reader;
+
 pub mod accounts {
+    pub use solcat_diamond_hands_program::accounts::VaultProgramDiscriminator;
+
     pub mod vault {
-        pub use solcat_diamond_hands_program::accounts::vault::Vault;
+        pub use solcat_diamond_hands_program::accounts::vault::{LockMode, Vault};
+    }
+
+    pub mod vault_extension {
+        pub use solcat_diamond_hands_program::accounts::vault_extension::{
+            VaultExtension, VaultLabel,
+        };
+    }
+
+    pub mod basket_vault {
+        pub use solcat_diamond_hands_program::accounts::basket_vault::{BasketEntry, BasketVault};
+    }
+
+    pub mod mint_stats {
+        pub use solcat_diamond_hands_program::accounts::mint_stats::{
+            average_lock_slots, MintStats,
+        };
     }
 }
 
 pub mod instructions {
+    pub use solcat_diamond_hands_program::instructions::VaultProgramInstructions;
+
     pub mod lock_vault {
-        pub use solcat_diamond_hands_program::instructions::lock_vault::LockVaultIxData;
+        pub use solcat_diamond_hands_program::instructions::lock_vault::{
+            LegacyLockVaultIxData, LockVaultIxData,
+        };
     }
 
     pub mod empty_vault {
         pub use solcat_diamond_hands_program::instructions::empty_vault::EmptyVaultIxData;
     }
+
+    pub mod crank_empty {
+        pub use solcat_diamond_hands_program::instructions::crank_empty::CrankEmptyIxData;
+    }
+
+    pub mod deposit_vault {
+        pub use solcat_diamond_hands_program::instructions::deposit_vault::DepositVaultIxData;
+    }
+
+    pub mod extend_lock {
+        pub use solcat_diamond_hands_program::instructions::extend_lock::ExtendLockIxData;
+    }
+
+    pub mod withdraw {
+        pub use solcat_diamond_hands_program::instructions::withdraw::WithdrawIxData;
+    }
+
+    pub mod transfer_admin {
+        pub use solcat_diamond_hands_program::instructions::transfer_admin::TransferAdminIxData;
+    }
+
+    pub mod update_label {
+        pub use solcat_diamond_hands_program::instructions::update_label::UpdateLabelIxData;
+    }
+
+    pub mod create_basket {
+        pub use solcat_diamond_hands_program::instructions::create_basket::CreateBasketIxData;
+    }
+
+    pub mod deposit_basket {
+        pub use solcat_diamond_hands_program::instructions::deposit_basket::DepositBasketIxData;
+    }
+
+    pub mod empty_basket {
+        pub use solcat_diamond_hands_program::instructions::empty_basket::{
+            EmptyBasketIxData, EMPTY_BASKET_ACCOUNTS_PER_MINT,
+        };
+    }
+}
+
+pub mod events {
+    pub use solcat_diamond_hands_program::events::{
+        BasketDepositEvent, CrankEmptyEvent, CreateBasketEvent, DepositEvent, EmptyEvent,
+        ExtendLockEvent, LockEvent, TransferAdminEvent, UpdateLabelEvent, VaultEventDiscriminator,
+        WithdrawEvent,
+    };
+}
+
+pub mod errors {
+    pub use solcat_diamond_hands_program::errors::DiamondHandsError;
 }
 
 pub mod utils {
@@ -30,7 +124,24 @@ pub fn id() -> Pubkey {
     solcat_diamond_hands_program::id().into()
 }
 
+// ----------------------- TOKEN PROGRAMS -----------------------
+/// The vault works with both the legacy Token program and Token-2022, the caller picks
+/// which one by looking at the owner of the mint account
+pub fn token_2022_id() -> Pubkey {
+    solcat_diamond_hands_program::token_interface::TOKEN_2022_PROGRAM_ID.into()
+}
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id.eq(&spl_token_interface::id()) || program_id.eq(&token_2022_id())
+}
+
+/// The token account for `owner` - the ATA derivation depends on the token program
+pub fn token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
+
 // ----------------------- VAULT -----------------------
+/// The v1 vault - one per admin and mint
 pub fn vault_address(admin: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
     let seeds = [
         accounts::vault::Vault::SEED,
@@ -40,30 +151,150 @@ pub fn vault_address(admin: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&seeds, &id())
 }
 
+/// Indexed vaults have the little-endian `vault_index` as an extra seed, so an admin can
+/// have as many vaults per mint as they want
+pub fn vault_address_with_index(admin: &Pubkey, mint: &Pubkey, vault_index: u64) -> (Pubkey, u8) {
+    let seeds = [
+        accounts::vault::Vault::SEED,
+        &admin.to_bytes(),
+        &mint.to_bytes(),
+        &vault_index.to_le_bytes(),
+    ];
+    Pubkey::find_program_address(&seeds, &id())
+}
+
+/// `None` is the v1 vault, `Some` is the indexed vault - all of the instruction builders take this
+pub fn find_vault_address(admin: &Pubkey, mint: &Pubkey, vault_index: Option<u64>) -> (Pubkey, u8) {
+    match vault_index {
+        Some(vault_index) => vault_address_with_index(admin, mint, vault_index),
+        None => vault_address(admin, mint),
+    }
+}
+
+// ----------------------- MINT STATS -----------------------
+/// One per mint. Lock, deposit, withdraw, empty and crank take it, and the first lock that
+/// passes it creates it - vaults locked without it are never counted
+pub fn mint_stats_address(mint: &Pubkey) -> (Pubkey, u8) {
+    let seeds = [accounts::mint_stats::MintStats::SEED, &mint.to_bytes()];
+    Pubkey::find_program_address(&seeds, &id())
+}
+
+// ----------------------- BASKET -----------------------
+/// Baskets are one per admin and index, the mints aren't part of the seeds
+pub fn basket_address(admin: &Pubkey, basket_index: u64) -> (Pubkey, u8) {
+    let seeds = [
+        accounts::basket_vault::BasketVault::SEED,
+        &admin.to_bytes(),
+        &basket_index.to_le_bytes(),
+    ];
+    Pubkey::find_program_address(&seeds, &id())
+}
+
+/// Zero-copy, straight from the program - `VaultState::from_account_data` is the safe, owned version
 pub fn deserialize_vault(data: &[u8]) -> Result<&accounts::vault::Vault> {
     let vault_account = unsafe {
-        solcat_diamond_hands_program::utils::load_account::<accounts::vault::Vault>(data)
+        accounts::vault::Vault::load(data)
             .map_err(|_| anyhow::anyhow!("failed to deserialize vault"))?
     };
     Ok(vault_account)
 }
 
+/// `None` for vaults that were locked before the extension existed
+pub fn deserialize_vault_extension(
+    data: &[u8],
+) -> Result<Option<&accounts::vault_extension::VaultExtension>> {
+    let vault_extension = unsafe {
+        accounts::vault_extension::VaultExtension::load(data)
+            .map_err(|_| anyhow::anyhow!("failed to deserialize vault extension"))?
+    };
+    Ok(vault_extension)
+}
+
+/// `token_program` is the owner of the `mint` account, either Token or Token-2022.
+/// `vault_index` of `None` locks into the v1 vault, `Some` into that indexed vault
 pub fn lock_vault_ix(
     admin: &Pubkey,
     mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
     slots_to_lock: u64,
     tokens_to_lock: Option<u64>,
+) -> [Instruction; 2] {
+    let ix_data = instructions::lock_vault::LockVaultIxData::new(0, slots_to_lock, tokens_to_lock);
+
+    lock_vault_with_ix_data_ix(admin, mint, vault_index, token_program, ix_data)
+}
+
+/// Same as `lock_vault_ix`, but the vault unlocks at a wall-clock unix timestamp
+/// ( `Clock::unix_timestamp` ) instead of after a number of slots
+pub fn lock_vault_until_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+    unlock_timestamp: i64,
+    tokens_to_lock: Option<u64>,
+) -> [Instruction; 2] {
+    let ix_data = instructions::lock_vault::LockVaultIxData::new_unix_timestamp(
+        0,
+        unlock_timestamp,
+        tokens_to_lock,
+    );
+
+    lock_vault_with_ix_data_ix(admin, mint, vault_index, token_program, ix_data)
+}
+
+/// Same as `lock_vault_ix`, but the tokens vest linearly over `slots_to_vest` instead of all
+/// unlocking at once. Nothing vests until `cliff_slots`, vested tokens are taken out with `withdraw_ix`
+pub fn lock_vault_vesting_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+    cliff_slots: u64,
+    slots_to_vest: u64,
+    tokens_to_lock: Option<u64>,
+) -> [Instruction; 2] {
+    let ix_data = instructions::lock_vault::LockVaultIxData::new_linear_vesting(
+        0,
+        cliff_slots,
+        slots_to_vest,
+        tokens_to_lock,
+    );
+
+    lock_vault_with_ix_data_ix(admin, mint, vault_index, token_program, ix_data)
+}
+
+/// For anything the helpers above don't cover, like locking for someone else with
+/// `LockVaultIxData::with_beneficiary`. The `vault_bump`, `mint_stats_bump` and `vault_index` in
+/// `ix_data` are overwritten from the PDAs, so you can just pass `0` for the bumps
+pub fn lock_vault_with_ix_data_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+    ix_data: instructions::lock_vault::LockVaultIxData,
 ) -> [Instruction; 2] {
     let program_id = id();
-    let token_program = spl_token_interface::id();
+    let token_program = *token_program;
     let system_program = solana_system_interface::program::id();
 
-    let (vault, vault_bump) = vault_address(admin, mint);
+    let (vault, vault_bump) = find_vault_address(admin, mint, vault_index);
+    let (mint_stats, mint_stats_bump) = mint_stats_address(mint);
+    let ix_data = instructions::lock_vault::LockVaultIxData {
+        vault_bump,
+        mint_stats_bump,
+        ..ix_data
+    };
+    let ix_data = match vault_index {
+        Some(vault_index) => ix_data.with_vault_index(vault_index),
+        None => ix_data,
+    };
 
-    let admin_token = get_associated_token_address(admin, mint);
-    let vault_token = get_associated_token_address(&vault, mint);
+    let admin_token = token_address(admin, mint, &token_program);
+    let vault_token = token_address(&vault, mint, &token_program);
 
-    // [vault, admin, mint, admin_token, vault_token, token_program, system_program]
+    // [vault, admin, mint, admin_token, vault_token, token_program, system_program, mint_stats]
     let accounts = vec![
         AccountMeta::new(vault, false),
         AccountMeta::new(*admin, true),
@@ -72,10 +303,9 @@ pub fn lock_vault_ix(
         AccountMeta::new(vault_token, false),
         AccountMeta::new_readonly(token_program, false),
         AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new(mint_stats, false),
     ];
 
-    let ix_data =
-        instructions::lock_vault::LockVaultIxData::new(vault_bump, slots_to_lock, tokens_to_lock);
     let ix_data_bytes = unsafe { ix_data.to_bytes() };
 
     let lock_vault_ix = Instruction {
@@ -90,25 +320,57 @@ pub fn lock_vault_ix(
     [vault_ata_ix, lock_vault_ix]
 }
 
-pub fn empty_vault_ix(admin: &Pubkey, mint: &Pubkey) -> [Instruction; 2] {
+/// `token_program` is the owner of the `mint` account, either Token or Token-2022
+pub fn empty_vault_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    build_empty_vault_ix(admin, admin, mint, vault_index, token_program)
+}
+
+/// Empties a vault that `admin` locked for `beneficiary` - the beneficiary signs and gets
+/// the tokens and the rent. `admin` is only needed to find the vault.
+/// This is also how a new admin empties a vault after `transfer_admin_ix`, `admin` is
+/// always the admin that locked the vault
+pub fn empty_vault_as_beneficiary_ix(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    build_empty_vault_ix(beneficiary, admin, mint, vault_index, token_program)
+}
+
+pub(crate) fn build_empty_vault_ix(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
     let program_id = id();
-    let token_program = spl_token_interface::id();
+    let token_program = *token_program;
     let system_program = solana_system_interface::program::id();
 
-    let (vault, _) = vault_address(admin, mint);
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
+    let (mint_stats, _) = mint_stats_address(mint);
 
-    let admin_token = get_associated_token_address(admin, mint);
-    let vault_token = get_associated_token_address(&vault, mint);
+    let authority_token = token_address(authority, mint, &token_program);
+    let vault_token = token_address(&vault, mint, &token_program);
 
-    // [vault, admin, mint, admin_token, vault_token, token_program, system_program]
+    // [vault, authority, mint, authority_token, vault_token, token_program, system_program, mint_stats]
     let accounts = vec![
         AccountMeta::new(vault, false),
-        AccountMeta::new(*admin, true),
+        AccountMeta::new(*authority, true),
         AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new(admin_token, false),
+        AccountMeta::new(authority_token, false),
         AccountMeta::new(vault_token, false),
         AccountMeta::new_readonly(token_program, false),
         AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new(mint_stats, false),
     ];
 
     let ix_data = instructions::empty_vault::EmptyVaultIxData::new();
@@ -128,8 +390,560 @@ pub fn empty_vault_ix(admin: &Pubkey, mint: &Pubkey) -> [Instruction; 2] {
     // freak out and send me angry messages saying, I rugged them. So we put this in just in case!
     // Note, `idempotent` means it will only try to create the account if it does not exsist, so its
     // safe in both cases.
-    let admin_ata_ix =
-        create_associated_token_account_idempotent(admin, admin, mint, &token_program);
+    let authority_ata_ix =
+        create_associated_token_account_idempotent(authority, authority, mint, &token_program);
+
+    [authority_ata_ix, empty_vault_ix]
+}
+
+/// Empties an unlocked vault for its withdraw authority, anyone can send this one. `payer` signs
+/// and pays for everything, including creating the authority's ATA if it was closed, but the
+/// tokens and the rent all go to `authority`.
+///
+/// `authority` is the beneficiary, or the admin if there is no beneficiary, and `seed_admin` is
+/// the admin that locked the vault - `VaultState::withdraw_authority` and `VaultState::seed_admin`
+pub fn crank_empty_ix(
+    payer: &Pubkey,
+    authority: &Pubkey,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    let program_id = id();
+    let token_program = *token_program;
+    let system_program = solana_system_interface::program::id();
+
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+    let (mint_stats, _) = mint_stats_address(mint);
+
+    let authority_token = token_address(authority, mint, &token_program);
+    let vault_token = token_address(&vault, mint, &token_program);
+
+    // [vault, cranker, authority, mint, authority_token, vault_token, token_program, system_program, mint_stats]
+    let accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new(*authority, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(authority_token, false),
+        AccountMeta::new(vault_token, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new(mint_stats, false),
+    ];
+
+    let ix_data = instructions::crank_empty::CrankEmptyIxData::new();
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    let crank_empty_ix = Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    };
+
+    // Same reason as `build_empty_vault_ix`, except the payer covers the rent for it
+    let authority_ata_ix =
+        create_associated_token_account_idempotent(payer, authority, mint, &token_program);
+
+    [authority_ata_ix, crank_empty_ix]
+}
+
+/// Adds more tokens to an existing vault, the lock is not changed. The vault token account
+/// already exists, so there is no need for the idempotent ATA instruction here.
+/// `tokens_to_deposit` of `None` deposits all of the admin's tokens.
+///
+/// `admin` is the vault's admin now and signs, `seed_admin` is the admin that locked it
+/// ( `VaultState::seed_admin` ) - the same as `admin` unless the vault was transferred
+pub fn deposit_vault_ix(
+    admin: &Pubkey,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+    tokens_to_deposit: Option<u64>,
+) -> Instruction {
+    let program_id = id();
+    let token_program = *token_program;
+    let system_program = solana_system_interface::program::id();
+
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+    let (mint_stats, _) = mint_stats_address(mint);
+
+    let admin_token = token_address(admin, mint, &token_program);
+    let vault_token = token_address(&vault, mint, &token_program);
+
+    // [vault, admin, mint, admin_token, vault_token, token_program, system_program, mint_stats]
+    let accounts = vec![
+        AccountMeta::new_readonly(vault, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(admin_token, false),
+        AccountMeta::new(vault_token, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new(mint_stats, false),
+    ];
+
+    let ix_data = instructions::deposit_vault::DepositVaultIxData::new(tokens_to_deposit);
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    }
+}
+
+/// Adds `slots_to_add` to a slot based vault's lock
+pub fn extend_lock_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    slots_to_add: u64,
+) -> Instruction {
+    let ix_data = instructions::extend_lock::ExtendLockIxData::new(slots_to_add);
+
+    build_extend_lock_ix(admin, admin, mint, vault_index, &ix_data)
+}
+
+/// Moves a unix timestamp vault's unlock to `unlock_timestamp`, it has to be later
+/// than the current unlock timestamp
+pub fn extend_lock_until_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    unlock_timestamp: i64,
+) -> Instruction {
+    let ix_data = instructions::extend_lock::ExtendLockIxData::new_unix_timestamp(unlock_timestamp);
+
+    build_extend_lock_ix(admin, admin, mint, vault_index, &ix_data)
+}
+
+/// Extends a vault that `admin` locked for `beneficiary` - once there is a beneficiary, only
+/// they can extend the lock. Build `ix_data` with `ExtendLockIxData::new` or `new_unix_timestamp`
+pub fn extend_lock_as_beneficiary_ix(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    ix_data: &instructions::extend_lock::ExtendLockIxData,
+) -> Instruction {
+    build_extend_lock_ix(beneficiary, admin, mint, vault_index, ix_data)
+}
+
+fn build_extend_lock_ix(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    ix_data: &instructions::extend_lock::ExtendLockIxData,
+) -> Instruction {
+    let program_id = id();
+
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
+
+    // [vault, authority]
+    let accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*authority, true),
+    ];
 
-    [admin_ata_ix, empty_vault_ix]
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    }
+}
+
+/// Withdraws everything that has vested so far from a linear vesting vault - the vault is
+/// closed once it is drained
+pub fn withdraw_ix(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    build_withdraw_ix(admin, admin, mint, vault_index, token_program)
+}
+
+/// Same as `empty_vault_as_beneficiary_ix`, the vested tokens go to the beneficiary
+pub fn withdraw_as_beneficiary_ix(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    build_withdraw_ix(beneficiary, admin, mint, vault_index, token_program)
+}
+
+pub(crate) fn build_withdraw_ix(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    token_program: &Pubkey,
+) -> [Instruction; 2] {
+    let program_id = id();
+    let token_program = *token_program;
+    let system_program = solana_system_interface::program::id();
+
+    let (vault, _) = find_vault_address(admin, mint, vault_index);
+    let (mint_stats, _) = mint_stats_address(mint);
+
+    let authority_token = token_address(authority, mint, &token_program);
+    let vault_token = token_address(&vault, mint, &token_program);
+
+    // [vault, authority, mint, authority_token, vault_token, token_program, system_program, mint_stats]
+    let accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*authority, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(authority_token, false),
+        AccountMeta::new(vault_token, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(system_program, false),
+        AccountMeta::new(mint_stats, false),
+    ];
+
+    let ix_data = instructions::withdraw::WithdrawIxData::new();
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    let withdraw_ix = Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    };
+
+    // Same as `empty_vault_ix`, just in case the authority closed their token account
+    let authority_ata_ix =
+        create_associated_token_account_idempotent(authority, authority, mint, &token_program);
+
+    [authority_ata_ix, withdraw_ix]
+}
+
+/// Creates an empty basket, build `ix_data` with `CreateBasketIxData::new` or `new_unix_timestamp` -
+/// the bump is filled in for you. `basket::lock_basket_txs` does this and the deposits in one go
+pub fn create_basket_ix(
+    admin: &Pubkey,
+    ix_data: instructions::create_basket::CreateBasketIxData,
+) -> Instruction {
+    let program_id = id();
+    let system_program = solana_system_interface::program::id();
+
+    let (basket, basket_bump) = basket_address(admin, ix_data.basket_index.get());
+    let ix_data = instructions::create_basket::CreateBasketIxData {
+        basket_bump,
+        ..ix_data
+    };
+
+    // [basket, admin, system_program]
+    let accounts = vec![
+        AccountMeta::new(basket, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    }
+}
+
+/// Deposits one mint into the basket, the first deposit of a mint adds it to the basket.
+/// Unlike `deposit_vault_ix` the basket's token account might not exist yet, so this creates it.
+/// `tokens_to_deposit` of `None` deposits all of the admin's tokens
+pub fn deposit_basket_ix(
+    admin: &Pubkey,
+    basket_index: u64,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    tokens_to_deposit: Option<u64>,
+) -> [Instruction; 2] {
+    let program_id = id();
+    let token_program = *token_program;
+    let system_program = solana_system_interface::program::id();
+
+    let (basket, _) = basket_address(admin, basket_index);
+
+    let admin_token = token_address(admin, mint, &token_program);
+    let vault_token = token_address(&basket, mint, &token_program);
+
+    // [basket, admin, mint, admin_token, vault_token, token_program, system_program]
+    let accounts = vec![
+        AccountMeta::new(basket, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(admin_token, false),
+        AccountMeta::new(vault_token, false),
+        AccountMeta::new_readonly(token_program, false),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+
+    let ix_data = instructions::deposit_basket::DepositBasketIxData::new(tokens_to_deposit);
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    let deposit_basket_ix = Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    };
+
+    let vault_ata_ix =
+        create_associated_token_account_idempotent(admin, &basket, mint, &token_program);
+
+    [vault_ata_ix, deposit_basket_ix]
+}
+
+/// Empties `mints` ( mint, token program ) out of an unlocked basket. Once the last mint in the
+/// basket is emptied the basket is closed - with no `mints` this closes a basket that never had
+/// anything deposited. Big baskets don't fit in one transaction, `basket::empty_basket_txs` splits them up
+pub fn empty_basket_ix(
+    admin: &Pubkey,
+    basket_index: u64,
+    mints: &[(Pubkey, Pubkey)],
+) -> Vec<Instruction> {
+    let program_id = id();
+    let system_program = solana_system_interface::program::id();
+
+    let (basket, _) = basket_address(admin, basket_index);
+
+    // [basket, admin, system_program, ..[mint, admin_token, vault_token, token_program]]
+    let mut accounts = vec![
+        AccountMeta::new(basket, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+    for (mint, token_program) in mints {
+        accounts.extend([
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(token_address(admin, mint, token_program), false),
+            AccountMeta::new(token_address(&basket, mint, token_program), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ]);
+    }
+
+    let ix_data = instructions::empty_basket::EmptyBasketIxData::new();
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    let empty_basket_ix = Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    };
+
+    // Same as `build_empty_vault_ix`, in case the admin closed any of their token accounts
+    mints
+        .iter()
+        .map(|(mint, token_program)| {
+            create_associated_token_account_idempotent(admin, admin, mint, token_program)
+        })
+        .chain([empty_basket_ix])
+        .collect()
+}
+
+/// Hands the vault over to `new_admin`, both have to sign. The vault PDA never changes, so
+/// `seed_admin` is the admin that locked the vault - the same as `admin` for the first transfer
+pub fn transfer_admin_ix(
+    admin: &Pubkey,
+    new_admin: &Pubkey,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+) -> Instruction {
+    let program_id = id();
+    let system_program = solana_system_interface::program::id();
+
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+
+    // [vault, admin, new_admin, system_program]
+    let accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(*new_admin, true),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+
+    let ix_data = instructions::transfer_admin::TransferAdminIxData::new();
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    }
+}
+
+/// Renames a vault, an empty `label` clears it. Only the admin can, `seed_admin` is the admin the
+/// vault was locked by ( `VaultState::seed_admin` ), the same as `admin` unless it was transferred
+pub fn update_label_ix(
+    admin: &Pubkey,
+    seed_admin: &Pubkey,
+    mint: &Pubkey,
+    vault_index: Option<u64>,
+    label: accounts::vault_extension::VaultLabel,
+) -> Instruction {
+    let program_id = id();
+    let system_program = solana_system_interface::program::id();
+
+    let (vault, _) = find_vault_address(seed_admin, mint, vault_index);
+
+    // [vault, admin, system_program]
+    let accounts = vec![
+        AccountMeta::new(vault, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(system_program, false),
+    ];
+
+    let ix_data = instructions::update_label::UpdateLabelIxData::new(label);
+    let ix_data_bytes = unsafe { ix_data.to_bytes() };
+
+    Instruction {
+        program_id,
+        accounts,
+        data: ix_data_bytes.to_vec(),
+    }
+}
+
+// ----------------------- CLOCK -----------------------
+/// Timestamp locks are checked against `Clock::unix_timestamp`, not the local clock - this reads
+/// it out of the raw `Clock` sysvar account
+pub fn clock_unix_timestamp(clock_data: &[u8]) -> Result<i64> {
+    const OFFSET: usize = core::mem::offset_of!(solana_program::clock::Clock, unix_timestamp);
+
+    clock_data
+        .get(OFFSET..OFFSET + core::mem::size_of::<i64>())
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or_else(|| anyhow::anyhow!("Clock sysvar data is too short"))
+}
+
+// ----------------------- ERRORS -----------------------
+/// Our errors come back as `InstructionError::Custom(code)`, `None` for anything else
+pub fn decode_instruction_error(error: &InstructionError) -> Option<errors::DiamondHandsError> {
+    match error {
+        InstructionError::Custom(code) => errors::DiamondHandsError::from_code(*code),
+        _ => None,
+    }
+}
+
+// ----------------------- EVENTS -----------------------
+/// Every event the program emits, see `events` for the byte layouts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VaultEvent {
+    Lock(events::LockEvent),
+    Deposit(events::DepositEvent),
+    ExtendLock(events::ExtendLockEvent),
+    Withdraw(events::WithdrawEvent),
+    Empty(events::EmptyEvent),
+    TransferAdmin(events::TransferAdminEvent),
+    CrankEmpty(events::CrankEmptyEvent),
+    CreateBasket(events::CreateBasketEvent),
+    BasketDeposit(events::BasketDepositEvent),
+    UpdateLabel(events::UpdateLabelEvent),
+}
+
+impl VaultEvent {
+    /// Decodes the raw bytes of one event, `None` if it is not one of ours
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        use events::VaultEventDiscriminator as D;
+
+        let discriminator = *data.first()?;
+        let event = match discriminator {
+            d if d == D::Lock as u8 => Self::Lock(decode_event(data)?),
+            d if d == D::Deposit as u8 => Self::Deposit(decode_event(data)?),
+            d if d == D::ExtendLock as u8 => Self::ExtendLock(decode_event(data)?),
+            d if d == D::Withdraw as u8 => Self::Withdraw(decode_event(data)?),
+            d if d == D::Empty as u8 => Self::Empty(decode_event(data)?),
+            d if d == D::TransferAdmin as u8 => Self::TransferAdmin(decode_event(data)?),
+            d if d == D::CrankEmpty as u8 => Self::CrankEmpty(decode_event(data)?),
+            d if d == D::CreateBasket as u8 => Self::CreateBasket(decode_event(data)?),
+            d if d == D::BasketDeposit as u8 => Self::BasketDeposit(decode_event(data)?),
+            d if d == D::UpdateLabel as u8 => Self::UpdateLabel(decode_event(data)?),
+            _ => return None,
+        };
+
+        Some(event)
+    }
+}
+
+/// The events are 1-byte aligned, so as long as the length matches we can just copy them out
+fn decode_event<T: utils::DataLen + Copy>(data: &[u8]) -> Option<T> {
+    unsafe { utils::load_account_unchecked::<T>(data).ok().copied() }
+}
+
+/// Pulls the vault events out of a transaction's log messages ( `meta.log_messages` ), in order.
+/// Only `Program data:` lines logged by this program count - anything logged by another
+/// program, including programs we CPI into, is skipped.
+pub fn parse_events<S: AsRef<str>>(logs: &[S]) -> Vec<VaultEvent> {
+    let program_id = id().to_string();
+    let invoke_prefix = format!("Program {} invoke", program_id);
+
+    // Which program is logging - CPIs push onto the stack and pop off once they return
+    let mut program_stack: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        let log = log.as_ref();
+
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if program_stack.last() != Some(&true) {
+                continue;
+            }
+
+            // `sol_log_data` base64 encodes each slice separately, we only ever log one
+            let bytes: Option<Vec<u8>> = data
+                .split_whitespace()
+                .map(|part| STANDARD.decode(part).ok())
+                .collect::<Option<Vec<_>>>()
+                .map(|parts| parts.concat());
+
+            if let Some(event) = bytes.as_deref().and_then(VaultEvent::decode) {
+                events.push(event);
+            }
+        } else if log.starts_with("Program ") && log.contains(" invoke [") {
+            program_stack.push(log.starts_with(&invoke_prefix));
+        } else if log.starts_with("Program ")
+            && (log.ends_with(" success") || log.contains(" failed"))
+        {
+            program_stack.pop();
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use events::EmptyEvent;
+
+    #[test]
+    fn test_parse_events_skips_other_programs() {
+        let vault = Pubkey::new_unique().to_bytes();
+        let authority = Pubkey::new_unique().to_bytes();
+        let mint = Pubkey::new_unique().to_bytes();
+        let event = EmptyEvent::new(&vault, &authority, &mint, 42);
+        let data = STANDARD.encode(unsafe { utils::to_bytes(&event) });
+
+        let other_program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", id()),
+            "Program log: Emptying Vault".to_string(),
+            format!("Program {} invoke [2]", other_program),
+            format!("Program data: {}", data),
+            format!("Program {} success", other_program),
+            format!("Program data: {}", data),
+            format!("Program {} success", id()),
+            format!("Program data: {}", data),
+        ];
+
+        assert_eq!(parse_events(&logs), vec![VaultEvent::Empty(event)]);
+    }
 }
@@ -0,0 +1,186 @@
+use solana_instruction::Instruction;
+use solana_pubkey::Pubkey;
+use solcat_diamond_hands_program::pod::{PodOption, PodU64};
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+
+use crate::{
+    build_empty_vault_ix, build_withdraw_ix, deposit_vault_ix,
+    instructions::lock_vault::LockVaultIxData, lock_vault_with_ix_data_ix, token_address,
+};
+
+// Locking SOL is locking wrapped SOL ( wSOL ). The vault only knows about SPL tokens, so these
+// wrap the SOL into the wallet's wSOL token account in front of the lock / deposit, and unwrap it
+// again behind the empty / withdraw. The vault's mint is the native mint, that is how a SOL vault
+// is told apart from any other - see `VaultState::is_native_sol`.
+//
+// wSOL only exists under the legacy Token program, so there is no `token_program` to pass here.
+// Amounts are in lamports, the native mint has 9 decimals like SOL
+
+/// The wSOL mint, `So11111111111111111111111111111111111111112`
+pub fn native_mint() -> Pubkey {
+    spl_token_interface::native_mint::id()
+}
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    mint.eq(&native_mint())
+}
+
+/// Moves `lamports` of `owner`'s SOL into their wSOL token account ( creating it if needed ),
+/// `SyncNative` updates the token balance to match
+pub fn wrap_sol_ixs(owner: &Pubkey, lamports: u64) -> [Instruction; 3] {
+    let token_program = spl_token_interface::id();
+    let owner_token = token_address(owner, &native_mint(), &token_program);
+
+    let owner_ata_ix =
+        create_associated_token_account_idempotent(owner, owner, &native_mint(), &token_program);
+    let transfer_ix = solana_system_interface::instruction::transfer(owner, &owner_token, lamports);
+    // Only fails for a token program that isn't the Token program, which it always is here
+    let sync_native_ix =
+        spl_token_interface::instruction::sync_native(&token_program, &owner_token)
+            .expect("Token program id is valid");
+
+    [owner_ata_ix, transfer_ix, sync_native_ix]
+}
+
+/// Closes `owner`'s wSOL token account, everything in it ( and its rent ) comes back as SOL.
+/// Note, that is all of the wSOL in the account - not just what came out of the vault
+pub fn unwrap_sol_ix(owner: &Pubkey) -> Instruction {
+    let token_program = spl_token_interface::id();
+    let owner_token = token_address(owner, &native_mint(), &token_program);
+
+    // Same as `sync_native`, the token program is always valid
+    spl_token_interface::instruction::close_account(&token_program, &owner_token, owner, owner, &[])
+        .expect("Token program id is valid")
+}
+
+/// `lock_vault_ix` for SOL - `lamports` is wrapped and locked for `slots_to_lock`
+pub fn lock_sol_ix(
+    admin: &Pubkey,
+    vault_index: Option<u64>,
+    slots_to_lock: u64,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let ix_data = LockVaultIxData::new(0, slots_to_lock, None);
+
+    lock_sol_with_ix_data_ix(admin, vault_index, ix_data, lamports)
+}
+
+/// `lock_vault_with_ix_data_ix` for SOL, for timestamp locks, vesting and beneficiaries.
+/// `tokens_to_lock` in `ix_data` is overwritten with `lamports` - "all tokens" would also lock
+/// any wSOL that was already in the admin's token account
+pub fn lock_sol_with_ix_data_ix(
+    admin: &Pubkey,
+    vault_index: Option<u64>,
+    ix_data: LockVaultIxData,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let ix_data = LockVaultIxData {
+        tokens_to_lock: PodOption::from(Some(PodU64::from(lamports))),
+        ..ix_data
+    };
+    let lock_ixs = lock_vault_with_ix_data_ix(
+        admin,
+        &native_mint(),
+        vault_index,
+        &spl_token_interface::id(),
+        ix_data,
+    );
+
+    wrap_sol_ixs(admin, lamports)
+        .into_iter()
+        .chain(lock_ixs)
+        .collect()
+}
+
+/// `deposit_vault_ix` for SOL, `lamports` more SOL goes into the vault
+pub fn deposit_sol_ix(
+    admin: &Pubkey,
+    seed_admin: &Pubkey,
+    vault_index: Option<u64>,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let deposit_ix = deposit_vault_ix(
+        admin,
+        seed_admin,
+        &native_mint(),
+        vault_index,
+        &spl_token_interface::id(),
+        Some(lamports),
+    );
+
+    wrap_sol_ixs(admin, lamports)
+        .into_iter()
+        .chain([deposit_ix])
+        .collect()
+}
+
+/// `empty_vault_as_beneficiary_ix` for SOL - the `authority` ends up with SOL, not wSOL.
+/// `admin` is the admin that locked the vault, the same as `authority` for your own vault
+pub fn empty_sol_vault_ix(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    vault_index: Option<u64>,
+) -> Vec<Instruction> {
+    let empty_ixs = build_empty_vault_ix(
+        authority,
+        admin,
+        &native_mint(),
+        vault_index,
+        &spl_token_interface::id(),
+    );
+
+    empty_ixs
+        .into_iter()
+        .chain([unwrap_sol_ix(authority)])
+        .collect()
+}
+
+/// `withdraw_as_beneficiary_ix` for SOL, the vested lamports come out as SOL
+pub fn withdraw_sol_ix(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    vault_index: Option<u64>,
+) -> Vec<Instruction> {
+    let withdraw_ixs = build_withdraw_ix(
+        authority,
+        admin,
+        &native_mint(),
+        vault_index,
+        &spl_token_interface::id(),
+    );
+
+    withdraw_ixs
+        .into_iter()
+        .chain([unwrap_sol_ix(authority)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::VaultInstruction;
+
+    #[test]
+    fn test_lock_sol_ix() -> Result<()> {
+        let admin = Pubkey::new_unique();
+        let lamports = 42;
+
+        // Wrap ( ATA, transfer, sync ), then the usual vault ATA and lock
+        let ixs = lock_sol_ix(&admin, Some(3), 100, lamports);
+        assert_eq!(ixs.len(), 5);
+        // The system program is all zeros
+        assert_eq!(ixs[1].program_id, Pubkey::default());
+        assert_eq!(ixs[4].accounts[2].pubkey, native_mint());
+
+        let VaultInstruction::LockVault { tokens_to_lock, .. } =
+            VaultInstruction::unpack(&ixs[4].data)?
+        else {
+            panic!("Last instruction is not a lock");
+        };
+        assert_eq!(tokens_to_lock, Some(lamports));
+
+        Ok(())
+    }
+}
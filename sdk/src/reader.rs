@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use solana_pubkey::Pubkey;
+
+use crate::accounts::vault_extension::VaultLabel;
+
+/// Walks through account or instruction data field by field, every read is bounds checked.
+/// Everything on-chain is `repr(C, packed)` and little-endian, so this is all it takes.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    pub(crate) fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| anyhow!("Data is too short"))?;
+        self.offset += N;
+
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub(crate) fn read_u128(&mut self) -> Result<u128> {
+        Ok(u128::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub(crate) fn read_i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub(crate) fn read_pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::new_from_array(self.read_bytes()?))
+    }
+
+    /// Same checks as `VaultLabel::check`, a label that made it on-chain always passes - the
+    /// length byte, then `VaultLabel::MAX_LEN` bytes with zeros after the label
+    pub(crate) fn read_label(&mut self) -> Result<VaultLabel> {
+        let len = self.read_u8()? as usize;
+        let bytes = self.read_bytes::<{ VaultLabel::MAX_LEN }>()?;
+        if len > VaultLabel::MAX_LEN || bytes[len..].iter().any(|byte| *byte != 0) {
+            return Err(anyhow!("Invalid label"));
+        }
+
+        VaultLabel::new(&bytes[..len]).map_err(|_| anyhow!("Invalid label"))
+    }
+
+    /// `PodOption` is a tag byte followed by the value, the value bytes are there either way
+    pub(crate) fn read_option<T>(&mut self, read: fn(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        let tag = self.read_u8()?;
+        let value = read(self)?;
+
+        match tag {
+            0 => Ok(None),
+            1 => Ok(Some(value)),
+            _ => Err(anyhow!("Invalid option tag {}", tag)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_label() -> Result<()> {
+        let label = |len: u8, text: &[u8]| {
+            let mut data = vec![len];
+            data.extend_from_slice(text);
+            data.resize(1 + VaultLabel::MAX_LEN, 0);
+            data
+        };
+
+        let data = label(4, b"team");
+        assert_eq!(
+            Reader::new(&data).read_label()?,
+            VaultLabel::new(b"team").unwrap()
+        );
+        assert!(Reader::new(&label(0, b"")).read_label()?.is_empty());
+
+        // A length past the end, bytes after the length, and bytes that aren't UTF-8
+        assert!(Reader::new(&label(33, b"team")).read_label().is_err());
+        assert!(Reader::new(&label(2, b"team")).read_label().is_err());
+        assert!(Reader::new(&label(2, &[0xff, 0xfe])).read_label().is_err());
+        // And too short to hold a label at all
+        assert!(Reader::new(&data[..VaultLabel::MAX_LEN])
+            .read_label()
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,414 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use solana_pubkey::Pubkey;
+use solcat_diamond_hands_program::pod::PodOption;
+
+use crate::{
+    accounts::{
+        mint_stats::{average_lock_slots, MintStats},
+        vault::{LockMode, Vault},
+        vault_extension::{VaultExtension, VaultLabel},
+        VaultProgramDiscriminator,
+    },
+    is_native_mint,
+    reader::Reader,
+    utils::DataLen,
+};
+
+/// serde can't derive for the program's `LockMode`, so this mirrors it for `VaultState`
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "LockMode")]
+pub(crate) enum LockModeDef {
+    Slot,
+    UnixTimestamp,
+    LinearVesting,
+}
+
+/// serde for the program's `VaultLabel` - it goes out as a string, or `null` when there is no
+/// label, and has to pass the same checks as on-chain to come back in
+pub(crate) mod label_serde {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        label: &VaultLabel,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let label = label.as_str().filter(|label| !label.is_empty());
+        label.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VaultLabel, D::Error> {
+        let label = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        VaultLabel::new(label.as_bytes()).map_err(|_| {
+            serde::de::Error::custom(format!(
+                "Label has to be at most {} bytes",
+                VaultLabel::MAX_LEN
+            ))
+        })
+    }
+}
+
+/// An owned copy of a vault account. `deserialize_vault` maps the RPC buffer straight onto the
+/// program's `Vault`, which is great on-chain but means `unsafe`, private fields and a borrow
+/// off-chain. This is parsed field by field instead, so it can be kept around, cached and
+/// shipped as JSON.
+///
+/// Vaults from before the extension existed get the extension's "not set" values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultState {
+    pub bump: u8,
+    /// Whoever controls the vault now - see `seed_admin` for the one in the PDA
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub mint_decimals: u8,
+    pub vault_token: Pubkey,
+    pub start_slot: u64,
+    pub slots_locked: u64,
+    #[serde(with = "LockModeDef")]
+    pub lock_mode: LockMode,
+    pub unlock_timestamp: i64,
+    /// `None` for v1 vaults
+    pub vault_index: Option<u64>,
+    pub cliff_slots: u64,
+    pub tokens_claimed: u64,
+    pub beneficiary: Option<Pubkey>,
+    /// The admin the vault PDA was derived from, the same as `admin` until a `TransferAdmin`.
+    /// The instruction builders want this one
+    pub seed_admin: Pubkey,
+    /// Empty if the vault was never named, `label.as_str()` for the text
+    #[serde(with = "label_serde")]
+    pub label: VaultLabel,
+}
+
+impl VaultState {
+    /// Same as `BasketState::is_basket_account` - v1 and indexed vaults, and nothing else the
+    /// program owns ( baskets, mint stats )
+    pub fn is_vault_account(data: &[u8]) -> bool {
+        // The discriminator is a `PodOption<u8>` - the tag, then the value
+        matches!(
+            data.get(..2),
+            Some(&[PodOption::<u8>::SOME_TAG, discriminator])
+                if discriminator == VaultProgramDiscriminator::Vault as u8
+                    || discriminator == VaultProgramDiscriminator::IndexedVault as u8
+        )
+    }
+
+    /// Checks the length and discriminator, and that every field holds a valid value
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != Vault::LEN && data.len() != VaultExtension::ACCOUNT_LEN {
+            return Err(anyhow!(
+                "Vault account has the wrong size {}, expected {} or {}",
+                data.len(),
+                Vault::LEN,
+                VaultExtension::ACCOUNT_LEN
+            ));
+        }
+
+        // Same order as the `repr(C, packed)` fields of the `Vault`
+        let mut reader = Reader::new(data);
+        let discriminator = reader
+            .read_option(Reader::read_u8)?
+            .ok_or_else(|| anyhow!("Vault account is not initialized"))?;
+        let bump = reader.read_u8()?;
+        let admin = reader.read_pubkey()?;
+        let mint = reader.read_pubkey()?;
+        let mint_decimals = reader.read_u8()?;
+        let vault_token = reader.read_pubkey()?;
+        let start_slot = reader.read_u64()?;
+        let slots_locked = reader.read_u64()?;
+        let lock_mode = LockMode::try_from(reader.read_u8()?)
+            .map_err(|_| anyhow!("Vault account has an invalid lock mode"))?;
+        let unlock_timestamp = reader.read_i64()?;
+        let vault_index = reader.read_u64()?;
+
+        let vault_index = match discriminator {
+            d if d == VaultProgramDiscriminator::Vault as u8 => None,
+            d if d == VaultProgramDiscriminator::IndexedVault as u8 => Some(vault_index),
+            d => return Err(anyhow!("Invalid vault discriminator {}", d)),
+        };
+
+        let mut state = Self {
+            bump,
+            admin,
+            mint,
+            mint_decimals,
+            vault_token,
+            start_slot,
+            slots_locked,
+            lock_mode,
+            unlock_timestamp,
+            vault_index,
+            cliff_slots: 0,
+            tokens_claimed: 0,
+            beneficiary: None,
+            seed_admin: admin,
+            label: VaultLabel::default(),
+        };
+
+        if data.len() == Vault::LEN {
+            return Ok(state);
+        }
+
+        let mut reader = Reader::new(&data[VaultExtension::OFFSET..]);
+        state.cliff_slots = reader.read_u64()?;
+        state.tokens_claimed = reader.read_u64()?;
+        state.beneficiary = reader.read_option(Reader::read_pubkey)?;
+        if let Some(seed_admin) = reader.read_option(Reader::read_pubkey)? {
+            state.seed_admin = seed_admin;
+        }
+        state.label = reader.read_label()?;
+
+        Ok(state)
+    }
+
+    /// The slot the vault unlocks at, for linear vesting this is when it is fully vested.
+    /// `None` for unix timestamp vaults, they don't unlock at a set slot
+    pub fn unlock_slot(&self) -> Option<u64> {
+        match self.lock_mode {
+            LockMode::Slot | LockMode::LinearVesting => {
+                Some(self.start_slot.saturating_add(self.slots_locked))
+            }
+            LockMode::UnixTimestamp => None,
+        }
+    }
+
+    /// Same check as the program does for slot based vaults. Unix timestamp vaults are never
+    /// unlockable by slot, use `is_unlockable_at_timestamp` for those
+    pub fn is_unlockable(&self, current_slot: u64) -> bool {
+        self.unlock_slot()
+            .is_some_and(|unlock_slot| current_slot >= unlock_slot)
+    }
+
+    /// `unix_timestamp` should come from the `Clock` sysvar, that is what the program checks
+    pub fn is_unlockable_at_timestamp(&self, unix_timestamp: i64) -> bool {
+        self.lock_mode == LockMode::UnixTimestamp && unix_timestamp >= self.unlock_timestamp
+    }
+
+    /// Who can take the tokens out - the beneficiary if there is one, otherwise the admin
+    pub fn withdraw_authority(&self) -> Pubkey {
+        self.beneficiary.unwrap_or(self.admin)
+    }
+
+    /// SOL vaults lock wSOL, so the amounts are lamports and the `native_sol` builders
+    /// unwrap it on the way out
+    pub fn is_native_sol(&self) -> bool {
+        is_native_mint(&self.mint)
+    }
+
+    /// What a proof-of-lock still vouches for later on - `vault_data` is the vault account now
+    /// ( `None` once it is gone ) and `certified` the vault as it was signed. The vault has to be
+    /// the same one, unlock no earlier, and hold at least as many tokens. Extending the lock,
+    /// deposits, vesting withdrawals and transferring the admin are all fine.
+    /// Returns the vault as it is now
+    pub fn check_still_locked(
+        certified: &VaultState,
+        certified_balance: u64,
+        vault_data: Option<&[u8]>,
+        vault_token_balance: u64,
+    ) -> Result<Self> {
+        let Some(vault_data) = vault_data else {
+            return Err(anyhow!(
+                "Vault no longer exists, it has been emptied since it was certified"
+            ));
+        };
+        let state = Self::from_account_data(vault_data)?;
+
+        // None of these can change while the vault exists
+        let same_vault = state.mint.eq(&certified.mint)
+            && state.vault_token.eq(&certified.vault_token)
+            && state.seed_admin.eq(&certified.seed_admin)
+            && state.vault_index == certified.vault_index
+            && state.start_slot == certified.start_slot
+            && state.lock_mode == certified.lock_mode
+            && state.beneficiary == certified.beneficiary;
+        if !same_vault {
+            return Err(anyhow!(
+                "Vault on-chain is not the vault in the certificate"
+            ));
+        }
+
+        // Locks only ever get longer
+        let unlocks_later = match state.lock_mode {
+            LockMode::UnixTimestamp => state.unlock_timestamp >= certified.unlock_timestamp,
+            LockMode::Slot | LockMode::LinearVesting => {
+                state.slots_locked >= certified.slots_locked
+            }
+        };
+        if !unlocks_later {
+            return Err(anyhow!("Vault unlocks earlier than the certificate says"));
+        }
+
+        // Tokens only leave a vault through vesting withdrawals ( `tokens_claimed` ) or emptying
+        // it, which closes it. Everything else in the balance is deposits
+        let total = vault_token_balance.saturating_add(state.tokens_claimed);
+        let certified_total = certified_balance.saturating_add(certified.tokens_claimed);
+        if total < certified_total {
+            return Err(anyhow!(
+                "Vault holds fewer tokens than the certificate says {} < {}",
+                total,
+                certified_total
+            ));
+        }
+
+        Ok(state)
+    }
+}
+
+/// An owned copy of a mint's `MintStats` - the totals for every vault of the mint, without
+/// having to find and fetch them all. Only vaults locked since the stats existed are counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintStatsState {
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub mint_decimals: u8,
+    /// Tokens in vaults right now, in base units
+    pub tokens_locked: u64,
+    /// Vaults that haven't been emptied yet
+    pub active_vaults: u64,
+    pub lifetime_locks: u64,
+    pub lifetime_tokens_locked: u128,
+    pub lifetime_token_slots: u128,
+}
+
+impl MintStatsState {
+    /// Checks the length and discriminator
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() != MintStats::LEN {
+            return Err(anyhow!(
+                "Mint stats account has the wrong size {}, expected {}",
+                data.len(),
+                MintStats::LEN
+            ));
+        }
+
+        // Same order as the `repr(C, packed)` fields of the `MintStats`
+        let mut reader = Reader::new(data);
+        let discriminator = reader.read_option(Reader::read_u8)?;
+        if discriminator != Some(VaultProgramDiscriminator::MintStats as u8) {
+            return Err(anyhow!("Account is not a mint stats account"));
+        }
+
+        Ok(Self {
+            bump: reader.read_u8()?,
+            mint: reader.read_pubkey()?,
+            mint_decimals: reader.read_u8()?,
+            tokens_locked: reader.read_u64()?,
+            active_vaults: reader.read_u64()?,
+            lifetime_locks: reader.read_u64()?,
+            lifetime_tokens_locked: reader.read_u128()?,
+            lifetime_token_slots: reader.read_u128()?,
+        })
+    }
+
+    /// Same as `MintStats::average_lock_slots` - weighted by the tokens each lock locked, and
+    /// unix timestamp locks are an estimate
+    pub fn average_lock_slots(&self) -> u64 {
+        average_lock_slots(self.lifetime_token_slots, self.lifetime_tokens_locked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        utils::{to_bytes, Discriminator},
+        VaultInstruction,
+    };
+
+    #[test]
+    fn test_vault_state_rejects_bad_data() {
+        // Wrong size, and the right size but never initialized
+        assert!(VaultState::from_account_data(&[0u8; 10]).is_err());
+        assert!(VaultState::from_account_data(&[0u8; Vault::LEN]).is_err());
+
+        // Initialized, but with a lock mode that doesn't exist
+        let mut data = [0u8; Vault::LEN];
+        data[0] = 1;
+        data[1] = Vault::DISCRIMINATOR;
+        data[Vault::LOCK_MODE_OFFSET] = 0xff;
+        assert!(VaultState::from_account_data(&data).is_err());
+
+        // Both kinds of vault are vaults, only by the discriminator
+        assert!(VaultState::is_vault_account(&data));
+        data[1] = VaultProgramDiscriminator::IndexedVault as u8;
+        assert!(VaultState::is_vault_account(&data));
+        data[1] = VaultProgramDiscriminator::BasketVault as u8;
+        assert!(!VaultState::is_vault_account(&data));
+        assert!(!VaultState::is_vault_account(&data[1..]));
+    }
+
+    #[test]
+    fn test_vault_label_rules() -> Result<()> {
+        assert!(VaultLabel::default().is_empty());
+        assert!(VaultLabel::new(&[b'a'; VaultLabel::MAX_LEN]).is_ok());
+        assert!(VaultLabel::new(&[b'a'; VaultLabel::MAX_LEN + 1]).is_err());
+        assert!(VaultLabel::new(&[0xff, 0xfe]).is_err());
+
+        // Bytes, not characters
+        let label = VaultLabel::new("🐱 reserve".as_bytes()).unwrap();
+        assert_eq!(label.as_bytes().len(), 12);
+        assert_eq!(label.to_string(), "🐱 reserve");
+        assert!(label.check().is_ok());
+
+        // A label is a string in JSON, and no label is `null`
+        let json = serde_json::to_value(VaultInstruction::UpdateLabel { label })?;
+        assert_eq!(json["UpdateLabel"]["label"], "🐱 reserve");
+        let json = serde_json::to_value(VaultInstruction::UpdateLabel {
+            label: VaultLabel::default(),
+        })?;
+        assert!(json["UpdateLabel"]["label"].is_null());
+        assert_eq!(
+            serde_json::from_value::<VaultInstruction>(json)?,
+            VaultInstruction::UpdateLabel {
+                label: VaultLabel::default()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mint_stats_state() -> Result<()> {
+        let mint = Pubkey::new_unique();
+
+        // Two locks, then most of the first one is emptied
+        let mut mint_stats = MintStats::new(mint.to_bytes(), 255, 6);
+        mint_stats.add_lock(300, 100);
+        mint_stats.add_lock(100, 500);
+        mint_stats.remove_tokens(300);
+        mint_stats.remove_vault();
+        let mut data = unsafe { to_bytes(&mint_stats) }.to_vec();
+
+        let stats = MintStatsState::from_account_data(&data)?;
+        assert_eq!(stats.bump, 255);
+        assert_eq!(stats.mint, mint);
+        assert_eq!(stats.mint_decimals, 6);
+        assert_eq!(stats.tokens_locked, 100);
+        assert_eq!(stats.active_vaults, 1);
+        assert_eq!(stats.lifetime_locks, 2);
+        assert_eq!(stats.lifetime_tokens_locked, 400);
+        assert_eq!(stats.lifetime_token_slots, 80_000);
+        assert_eq!(stats.average_lock_slots(), 200);
+        assert_eq!(&data[MintStats::MINT_OFFSET..][..32], mint.as_ref());
+
+        // The mint sits where a vault has its admin, so an admin filter can turn these up
+        assert_eq!(MintStats::MINT_OFFSET, Vault::ADMIN_OFFSET);
+        assert!(!VaultState::is_vault_account(&data));
+
+        // A vault is not mint stats, even if it were the right size - the discriminator is a
+        // `PodOption`, the tag and then the value
+        data[..2].copy_from_slice(&[1, VaultProgramDiscriminator::Vault as u8]);
+        assert!(MintStatsState::from_account_data(&data).is_err());
+        assert!(MintStatsState::from_account_data(&data[1..]).is_err());
+
+        // Nothing locked yet
+        let data = unsafe { to_bytes(&MintStats::new(mint.to_bytes(), 255, 6)) }.to_vec();
+        let empty = MintStatsState::from_account_data(&data)?;
+        assert_eq!(empty.lifetime_locks, 0);
+        assert_eq!(empty.average_lock_slots(), 0);
+
+        Ok(())
+    }
+}
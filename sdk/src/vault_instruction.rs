@@ -0,0 +1,419 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_pubkey::Pubkey;
+use solcat_diamond_hands_program::pod::{PodI64, PodOption, PodU64};
+
+use crate::{
+    accounts::{vault::LockMode, vault_extension::VaultLabel},
+    instructions::{
+        crank_empty::CrankEmptyIxData,
+        create_basket::CreateBasketIxData,
+        deposit_basket::DepositBasketIxData,
+        deposit_vault::DepositVaultIxData,
+        empty_basket::EmptyBasketIxData,
+        empty_vault::EmptyVaultIxData,
+        extend_lock::ExtendLockIxData,
+        lock_vault::{LegacyLockVaultIxData, LockVaultIxData},
+        transfer_admin::TransferAdminIxData,
+        update_label::UpdateLabelIxData,
+        withdraw::WithdrawIxData,
+        VaultProgramInstructions,
+    },
+    reader::Reader,
+    state::{label_serde, LockModeDef},
+    utils::{DataLen, Discriminator},
+};
+
+/// Every instruction the program has, decoded from raw instruction data. The `*IxData` structs
+/// only go one way ( `unsafe to_bytes` ), this goes both - `unpack` reads an instruction out of
+/// a transaction, and `pack` gives back the exact same bytes.
+///
+/// The fields are the same as the `*IxData` structs, just without the Pods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VaultInstruction {
+    LockVault {
+        vault_bump: u8,
+        #[serde(with = "LockModeDef")]
+        lock_mode: LockMode,
+        slots_to_lock: u64,
+        cliff_slots: u64,
+        unlock_timestamp: i64,
+        tokens_to_lock: Option<u64>,
+        vault_index: Option<u64>,
+        beneficiary: Option<Pubkey>,
+        #[serde(with = "label_serde")]
+        label: VaultLabel,
+        mint_stats_bump: u8,
+    },
+    EmptyVault,
+    DepositVault {
+        tokens_to_deposit: Option<u64>,
+    },
+    ExtendLock {
+        slots_to_add: u64,
+        unlock_timestamp: i64,
+    },
+    Withdraw,
+    TransferAdmin,
+    CrankEmpty,
+    CreateBasket {
+        basket_bump: u8,
+        #[serde(with = "LockModeDef")]
+        lock_mode: LockMode,
+        slots_to_lock: u64,
+        unlock_timestamp: i64,
+        basket_index: u64,
+    },
+    DepositBasket {
+        tokens_to_deposit: Option<u64>,
+    },
+    EmptyBasket,
+    UpdateLabel {
+        #[serde(with = "label_serde")]
+        label: VaultLabel,
+    },
+}
+
+impl VaultInstruction {
+    /// Same rules as the program - a known discriminator, the exact length, and valid values.
+    /// `LockVault` can also be the original `LegacyLockVaultIxData`, it comes out the same way
+    /// the program reads it - `pack` always gives back the current layout
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let discriminator = data
+            .first()
+            .ok_or_else(|| anyhow!("Instruction data is empty"))?;
+        let instruction = VaultProgramInstructions::try_from(discriminator)
+            .map_err(|_| anyhow!("Unknown instruction discriminator {}", discriminator))?;
+
+        let expected_len = match instruction {
+            VaultProgramInstructions::LockVault => LockVaultIxData::LEN,
+            VaultProgramInstructions::EmptyVault => EmptyVaultIxData::LEN,
+            VaultProgramInstructions::DepositVault => DepositVaultIxData::LEN,
+            VaultProgramInstructions::ExtendLock => ExtendLockIxData::LEN,
+            VaultProgramInstructions::Withdraw => WithdrawIxData::LEN,
+            VaultProgramInstructions::TransferAdmin => TransferAdminIxData::LEN,
+            VaultProgramInstructions::CrankEmpty => CrankEmptyIxData::LEN,
+            VaultProgramInstructions::CreateBasket => CreateBasketIxData::LEN,
+            VaultProgramInstructions::DepositBasket => DepositBasketIxData::LEN,
+            VaultProgramInstructions::EmptyBasket => EmptyBasketIxData::LEN,
+            VaultProgramInstructions::UpdateLabel => UpdateLabelIxData::LEN,
+        };
+        let legacy_lock = matches!(instruction, VaultProgramInstructions::LockVault)
+            && data.len() == LegacyLockVaultIxData::LEN;
+        if data.len() != expected_len && !legacy_lock {
+            return Err(anyhow!(
+                "Instruction data has the wrong size {}, expected {}",
+                data.len(),
+                expected_len
+            ));
+        }
+
+        // Same order as the `repr(C, packed)` fields, after the discriminator
+        let mut reader = Reader::new(&data[1..]);
+        let instruction = match instruction {
+            VaultProgramInstructions::LockVault if legacy_lock => {
+                let legacy = LegacyLockVaultIxData::new(
+                    reader.read_u8()?,
+                    reader.read_u64()?,
+                    reader.read_option(Reader::read_u64)?,
+                );
+                // Same conversion as `LockVaultIxData::load`
+                let ix_data = LockVaultIxData::from(legacy);
+                Self::read_lock_vault(&mut Reader::new(&unsafe { ix_data.to_bytes() }[1..]))?
+            }
+            VaultProgramInstructions::LockVault => Self::read_lock_vault(&mut reader)?,
+            VaultProgramInstructions::EmptyVault => Self::EmptyVault,
+            VaultProgramInstructions::DepositVault => Self::DepositVault {
+                tokens_to_deposit: reader.read_option(Reader::read_u64)?,
+            },
+            VaultProgramInstructions::ExtendLock => Self::ExtendLock {
+                slots_to_add: reader.read_u64()?,
+                unlock_timestamp: reader.read_i64()?,
+            },
+            VaultProgramInstructions::Withdraw => Self::Withdraw,
+            VaultProgramInstructions::TransferAdmin => Self::TransferAdmin,
+            VaultProgramInstructions::CrankEmpty => Self::CrankEmpty,
+            VaultProgramInstructions::CreateBasket => Self::CreateBasket {
+                basket_bump: reader.read_u8()?,
+                lock_mode: LockMode::try_from(reader.read_u8()?)
+                    .map_err(|_| anyhow!("Instruction data has an invalid lock mode"))?,
+                slots_to_lock: reader.read_u64()?,
+                unlock_timestamp: reader.read_i64()?,
+                basket_index: reader.read_u64()?,
+            },
+            VaultProgramInstructions::DepositBasket => Self::DepositBasket {
+                tokens_to_deposit: reader.read_option(Reader::read_u64)?,
+            },
+            VaultProgramInstructions::EmptyBasket => Self::EmptyBasket,
+            VaultProgramInstructions::UpdateLabel => Self::UpdateLabel {
+                label: reader.read_label()?,
+            },
+        };
+
+        Ok(instruction)
+    }
+
+    /// `LockVault` in the current `LockVaultIxData` layout
+    fn read_lock_vault(reader: &mut Reader) -> Result<Self> {
+        Ok(Self::LockVault {
+            vault_bump: reader.read_u8()?,
+            lock_mode: LockMode::try_from(reader.read_u8()?)
+                .map_err(|_| anyhow!("Instruction data has an invalid lock mode"))?,
+            slots_to_lock: reader.read_u64()?,
+            cliff_slots: reader.read_u64()?,
+            unlock_timestamp: reader.read_i64()?,
+            tokens_to_lock: reader.read_option(Reader::read_u64)?,
+            vault_index: reader.read_option(Reader::read_u64)?,
+            beneficiary: reader.read_option(Reader::read_pubkey)?,
+            label: reader.read_label()?,
+            mint_stats_bump: reader.read_u8()?,
+        })
+    }
+    /// The instruction data the program expects
+    pub fn pack(&self) -> Vec<u8> {
+        match *self {
+            Self::LockVault {
+                vault_bump,
+                lock_mode,
+                slots_to_lock,
+                cliff_slots,
+                unlock_timestamp,
+                tokens_to_lock,
+                vault_index,
+                beneficiary,
+                label,
+                mint_stats_bump,
+            } => {
+                let ix_data = LockVaultIxData {
+                    discriminator: LockVaultIxData::DISCRIMINATOR,
+                    vault_bump,
+                    lock_mode: lock_mode as u8,
+                    slots_to_lock: PodU64::from(slots_to_lock),
+                    cliff_slots: PodU64::from(cliff_slots),
+                    unlock_timestamp: PodI64::from(unlock_timestamp),
+                    tokens_to_lock: PodOption::from(tokens_to_lock.map(PodU64::from)),
+                    vault_index: PodOption::from(vault_index.map(PodU64::from)),
+                    beneficiary: PodOption::from(beneficiary.map(|b| b.to_bytes())),
+                    label,
+                    mint_stats_bump,
+                };
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+            Self::EmptyVault => unsafe { EmptyVaultIxData::new().to_bytes() }.to_vec(),
+            Self::DepositVault { tokens_to_deposit } => {
+                let ix_data = DepositVaultIxData::new(tokens_to_deposit);
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+            Self::ExtendLock {
+                slots_to_add,
+                unlock_timestamp,
+            } => {
+                let ix_data = ExtendLockIxData {
+                    discriminator: ExtendLockIxData::DISCRIMINATOR,
+                    slots_to_add: PodU64::from(slots_to_add),
+                    unlock_timestamp: PodI64::from(unlock_timestamp),
+                };
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+            Self::Withdraw => unsafe { WithdrawIxData::new().to_bytes() }.to_vec(),
+            Self::TransferAdmin => unsafe { TransferAdminIxData::new().to_bytes() }.to_vec(),
+            Self::CrankEmpty => unsafe { CrankEmptyIxData::new().to_bytes() }.to_vec(),
+            Self::CreateBasket {
+                basket_bump,
+                lock_mode,
+                slots_to_lock,
+                unlock_timestamp,
+                basket_index,
+            } => {
+                let ix_data = CreateBasketIxData {
+                    discriminator: CreateBasketIxData::DISCRIMINATOR,
+                    basket_bump,
+                    lock_mode: lock_mode as u8,
+                    slots_to_lock: PodU64::from(slots_to_lock),
+                    unlock_timestamp: PodI64::from(unlock_timestamp),
+                    basket_index: PodU64::from(basket_index),
+                };
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+            Self::DepositBasket { tokens_to_deposit } => {
+                let ix_data = DepositBasketIxData::new(tokens_to_deposit);
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+            Self::EmptyBasket => unsafe { EmptyBasketIxData::new().to_bytes() }.to_vec(),
+            Self::UpdateLabel { label } => {
+                let ix_data = UpdateLabelIxData::new(label);
+                unsafe { ix_data.to_bytes() }.to_vec()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::offset_of;
+
+    use super::*;
+    use crate::{
+        crank_empty_ix, deposit_vault_ix, empty_vault_ix, extend_lock_ix, extend_lock_until_ix,
+        find_vault_address, lock_vault_ix, lock_vault_until_ix, lock_vault_with_ix_data_ix,
+        mint_stats_address, transfer_admin_ix, update_label_ix, withdraw_ix,
+    };
+
+    #[test]
+    fn test_vault_instruction_round_trip() -> Result<()> {
+        let admin = Pubkey::new_unique();
+        let beneficiary = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_program = spl_token_interface::id();
+
+        // Everything the builders make should decode, and pack back into the exact same bytes
+        let label = VaultLabel::new("vesting".as_bytes()).unwrap();
+        let vesting_ix_data = LockVaultIxData::new_linear_vesting(0, 100, 1000, Some(500))
+            .with_beneficiary(beneficiary.to_bytes())
+            .with_label(label);
+        let ixs = [
+            lock_vault_ix(&admin, &mint, None, &token_program, 10, None)[1].clone(),
+            lock_vault_until_ix(
+                &admin,
+                &mint,
+                Some(3),
+                &token_program,
+                1_700_000_000,
+                Some(42),
+            )[1]
+            .clone(),
+            lock_vault_with_ix_data_ix(&admin, &mint, Some(7), &token_program, vesting_ix_data)[1]
+                .clone(),
+            empty_vault_ix(&admin, &mint, None, &token_program)[1].clone(),
+            deposit_vault_ix(&admin, &admin, &mint, None, &token_program, Some(100)),
+            deposit_vault_ix(&admin, &admin, &mint, None, &token_program, None),
+            extend_lock_ix(&admin, &mint, None, 50),
+            extend_lock_until_ix(&admin, &mint, None, 1_800_000_000),
+            withdraw_ix(&admin, &mint, None, &token_program)[1].clone(),
+            transfer_admin_ix(&admin, &beneficiary, &admin, &mint, None),
+            crank_empty_ix(&beneficiary, &admin, &admin, &mint, None, &token_program)[1].clone(),
+            update_label_ix(&admin, &admin, &mint, None, label),
+        ];
+        for ix in &ixs {
+            assert_eq!(VaultInstruction::unpack(&ix.data)?.pack(), ix.data);
+        }
+
+        // And the fields come out the way they went in
+        let (_, vault_bump) = find_vault_address(&admin, &mint, Some(7));
+        let (_, mint_stats_bump) = mint_stats_address(&mint);
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[2].data)?,
+            VaultInstruction::LockVault {
+                vault_bump,
+                lock_mode: LockMode::LinearVesting,
+                slots_to_lock: 1000,
+                cliff_slots: 100,
+                unlock_timestamp: 0,
+                tokens_to_lock: Some(500),
+                vault_index: Some(7),
+                beneficiary: Some(beneficiary),
+                label,
+                mint_stats_bump,
+            }
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[3].data)?,
+            VaultInstruction::EmptyVault
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[5].data)?,
+            VaultInstruction::DepositVault {
+                tokens_to_deposit: None
+            }
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[7].data)?,
+            VaultInstruction::ExtendLock {
+                slots_to_add: 0,
+                unlock_timestamp: 1_800_000_000
+            }
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[9].data)?,
+            VaultInstruction::TransferAdmin
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[10].data)?,
+            VaultInstruction::CrankEmpty
+        );
+        assert_eq!(
+            VaultInstruction::unpack(&ixs[11].data)?,
+            VaultInstruction::UpdateLabel { label }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vault_instruction_unpacks_legacy_lock() -> Result<()> {
+        // The original layout - bump, slots, tokens
+        let discriminator = VaultProgramInstructions::LockVault as u8;
+        let tokens = [&[1][..], &600u64.to_le_bytes()].concat();
+        let legacy = [&[discriminator, 254][..], &10u64.to_le_bytes(), &tokens].concat();
+        let legacy_ix_data = LegacyLockVaultIxData::new(254, 10, Some(600));
+        assert_eq!(unsafe { legacy_ix_data.to_bytes() }, &legacy[..]);
+
+        // A slot lock into a v1 vault, the same as the program reads it
+        let instruction = VaultInstruction::unpack(&legacy)?;
+        assert_eq!(
+            instruction,
+            VaultInstruction::LockVault {
+                vault_bump: 254,
+                lock_mode: LockMode::Slot,
+                slots_to_lock: 10,
+                cliff_slots: 0,
+                unlock_timestamp: 0,
+                tokens_to_lock: Some(600),
+                vault_index: None,
+                beneficiary: None,
+                label: VaultLabel::default(),
+                mint_stats_bump: 0,
+            }
+        );
+        let ix_data = LockVaultIxData::from(legacy_ix_data);
+        assert_eq!(instruction.pack(), unsafe { ix_data.to_bytes() });
+
+        // Only the legacy and current lengths, like `LockVaultIxData::load`
+        for len in [LegacyLockVaultIxData::LEN + 1, LockVaultIxData::LEN - 1] {
+            let mut data = instruction.pack();
+            data.resize(len, 0);
+            assert!(VaultInstruction::unpack(&data).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vault_instruction_rejects_bad_data() {
+        let admin = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_program = spl_token_interface::id();
+
+        // Empty, and discriminators that don't exist
+        assert!(VaultInstruction::unpack(&[]).is_err());
+        assert!(VaultInstruction::unpack(&[0]).is_err());
+        assert!(VaultInstruction::unpack(&[0xff]).is_err());
+
+        // Too long - the program wants the exact length
+        let mut data = extend_lock_ix(&admin, &mint, None, 50).data;
+        data.push(0);
+        assert!(VaultInstruction::unpack(&data).is_err());
+
+        // A lock mode that doesn't exist
+        let mut data = lock_vault_ix(&admin, &mint, None, &token_program, 10, None)[1]
+            .data
+            .clone();
+        data[offset_of!(LockVaultIxData, lock_mode)] = 0xff;
+        assert!(VaultInstruction::unpack(&data).is_err());
+
+        // An option tag that is not 0 or 1
+        let mut data = deposit_vault_ix(&admin, &admin, &mint, None, &token_program, Some(1)).data;
+        data[offset_of!(DepositVaultIxData, tokens_to_deposit)] = 2;
+        assert!(VaultInstruction::unpack(&data).is_err());
+    }
+}